@@ -0,0 +1,140 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use smiles_with_selectors::{definitions::bond::BondType, workspace::Workspace};
+
+const SIZES: [usize; 4] = [25_000, 50_000, 100_000, 200_000];
+
+/// a linear chain of carbons
+fn chain(atoms: usize) -> String {
+    "C".repeat(atoms)
+}
+
+/// polystyrene, 8 atoms and a ring per repeating unit
+fn polystyrene(atoms: usize) -> String {
+    "CC(c1ccccc1)".repeat(atoms / 8)
+}
+
+/// a mixture of ethanes, one component per 2 atoms
+fn ethanes(atoms: usize) -> String {
+    vec!["CC"; atoms / 2].join(".")
+}
+
+fn get_atoms_of_structure(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_atoms_of_structure");
+    group.sample_size(10);
+    for size in SIZES {
+        for (name, smiles) in [("chain", chain(size)), ("polystyrene", polystyrene(size))] {
+            let mut workspace = Workspace::new();
+            let id = workspace.add_structure(&smiles).unwrap();
+            // start from the last atom so the walk to root is the longest
+            let leaf = *workspace.structure_atoms(id).unwrap().last().unwrap();
+            group.throughput(Throughput::Elements(size as u64));
+            group.bench_with_input(BenchmarkId::new(name, size), &leaf, |b, leaf| {
+                b.iter(|| workspace.get_atoms_of_structure(*leaf).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn to_sws(c: &mut Criterion) {
+    let mut group = c.benchmark_group("to_sws");
+    group.sample_size(10);
+    for size in SIZES {
+        for (name, smiles) in [("chain", chain(size)), ("polystyrene", polystyrene(size))] {
+            let mut workspace = Workspace::new();
+            let id = workspace.add_structure(&smiles).unwrap();
+            let root = workspace.structure_root(id).unwrap();
+            group.throughput(Throughput::Elements(size as u64));
+            group.bench_with_input(BenchmarkId::new(name, size), &root, |b, root| {
+                b.iter(|| workspace.to_sws(*root).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn connect(c: &mut Criterion) {
+    let mut group = c.benchmark_group("connect");
+    group.sample_size(10);
+    for size in SIZES {
+        let smiles = chain(size / 2);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_function(BenchmarkId::new("chain", size), |b| {
+            b.iter_batched(
+                || {
+                    let mut workspace = Workspace::new();
+                    let first = workspace.add_structure(&smiles).unwrap();
+                    let second = workspace.add_structure(&smiles).unwrap();
+                    let from = *workspace.structure_atoms(first).unwrap().last().unwrap();
+                    // the whole second chain has to be reversed
+                    let to = *workspace.structure_atoms(second).unwrap().last().unwrap();
+                    (workspace, from, to)
+                },
+                |(mut workspace, from, to)| {
+                    workspace.connect(from, to, BondType::Single).unwrap();
+                    workspace
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    for size in SIZES {
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_function(BenchmarkId::new("ethanes", size), |b| {
+            b.iter_batched(
+                || {
+                    let mut workspace = Workspace::new();
+                    let id = workspace.add_structure("C").unwrap();
+                    let hub = workspace.structure_root(id).unwrap();
+                    // every structure joined leaves the registry
+                    let roots = (0..size / 2)
+                        .map(|_| {
+                            let id = workspace.add_structure("CC").unwrap();
+                            workspace.structure_root(id).unwrap()
+                        })
+                        .collect::<Vec<_>>();
+                    (workspace, hub, roots)
+                },
+                |(mut workspace, hub, roots)| {
+                    for root in roots {
+                        workspace.connect(hub, root, BondType::Single).unwrap();
+                    }
+                    workspace
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn break_bond(c: &mut Criterion) {
+    let mut group = c.benchmark_group("break_bond");
+    group.sample_size(10);
+    for size in SIZES {
+        let smiles = ethanes(size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_function(BenchmarkId::new("ethanes", size), |b| {
+            b.iter_batched(
+                || {
+                    let mut workspace = Workspace::new();
+                    let id = workspace.add_structure(&smiles).unwrap();
+                    let atoms = workspace.structure_atoms(id).unwrap();
+                    (workspace, atoms)
+                },
+                |(mut workspace, atoms)| {
+                    // every component splits and is remembered as a new anchor
+                    for pair in atoms.chunks(2) {
+                        workspace.break_bond(pair[0], pair[1], None).unwrap();
+                    }
+                    workspace
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, get_atoms_of_structure, to_sws, connect, break_bond);
+criterion_main!(benches);
@@ -0,0 +1,168 @@
+use std::{
+    fmt::Display,
+    io::{BufRead, Write},
+};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::molecule::Molecule;
+
+/// A line of a `.smi` or `.sws` file: a SMILES (or SwS) with an optional
+/// CXSMILES extension block, then an optional name and other columns,
+/// separated by whitespaces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// line number in the file, starts from 1
+    pub line: usize,
+    pub smiles: String,
+    pub name: Option<String>,
+    pub columns: Vec<String>,
+}
+
+impl Record {
+    /// Split a line into a record, blank lines and lines start with `#`
+    /// are not records.
+    pub fn from_line(line: usize, text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            return None;
+        }
+        let (smiles, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let rest = rest.trim_start();
+        // a CXSMILES extension block belongs to the SMILES
+        let (smiles, rest) = match rest.strip_prefix('|').and_then(|block| block.find('|')) {
+            Some(end) => (format!("{} {}", smiles, &rest[..end + 2]), &rest[end + 2..]),
+            None => (smiles.to_string(), rest),
+        };
+        let mut fields = rest.split_whitespace().map(String::from);
+        Some(Self {
+            line,
+            smiles,
+            name: fields.next(),
+            columns: fields.collect(),
+        })
+    }
+}
+
+/// What went wrong with a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordErrorKind {
+    /// the line can't be read, or the output can't be written to
+    Io,
+    /// the SMILES can't be parsed, the run goes on with other records
+    Parse,
+    /// the parsed molecule can't be written back as SwS
+    Write,
+}
+
+/// A record failed to be read, parsed or written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordError {
+    pub kind: RecordErrorKind,
+    pub line: usize,
+    /// empty if the line can't be read
+    pub smiles: String,
+    pub message: String,
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            RecordErrorKind::Io => "I/O error",
+            RecordErrorKind::Parse => "parse error",
+            RecordErrorKind::Write => "write error",
+        };
+        write!(
+            f,
+            "line {}: {}: {}: {}",
+            self.line, kind, self.smiles, self.message
+        )
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// Read all records of a file, one record per line. Reading stops at the
+/// first line can't be read.
+pub fn read_records<R: BufRead>(reader: R) -> Result<Vec<Record>, RecordError> {
+    let mut records = vec![];
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|err| RecordError {
+            kind: RecordErrorKind::Io,
+            line: index + 1,
+            smiles: String::new(),
+            message: err.to_string(),
+        })?;
+        if let Some(record) = Record::from_line(index + 1, &line) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// Parse records as molecules, results are in the order of records.
+/// Records are parsed in parallel with feature `rayon`.
+pub fn parse_records(records: &[Record]) -> Vec<Result<Molecule, RecordError>> {
+    map_in_order(records, |record| {
+        Molecule::from_sws(&record.smiles).map_err(|message| RecordError {
+            kind: RecordErrorKind::Parse,
+            line: record.line,
+            smiles: record.smiles.clone(),
+            message,
+        })
+    })
+}
+
+/// Write parsed molecules as SwS, with names and columns of their records.
+/// Lines are written in the order of records, records failed to be parsed
+/// are skipped. Returns how many lines are written, or the first record
+/// failed to be written.
+pub fn write_records<W: Write>(
+    writer: &mut W,
+    records: &[Record],
+    results: &[Result<Molecule, RecordError>],
+) -> Result<usize, RecordError> {
+    let pairs = records.iter().zip(results.iter()).collect::<Vec<_>>();
+    let lines = map_in_order(&pairs, |(record, result)| {
+        result.as_ref().ok().map(|molecule| {
+            let mut line = molecule.to_sws().ok_or_else(|| RecordError {
+                kind: RecordErrorKind::Write,
+                line: record.line,
+                smiles: record.smiles.clone(),
+                message: "Failed to write the molecule".to_string(),
+            })?;
+            for field in record.name.iter().chain(record.columns.iter()) {
+                line.push(' ');
+                line.push_str(field);
+            }
+            Ok(line)
+        })
+    });
+    let mut written = 0;
+    for (record, line) in records.iter().zip(lines) {
+        if let Some(line) = line {
+            writeln!(writer, "{}", line?).map_err(|err| RecordError {
+                kind: RecordErrorKind::Io,
+                line: record.line,
+                smiles: record.smiles.clone(),
+                message: err.to_string(),
+            })?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(feature = "rayon")]
+pub(crate) fn map_in_order<T: Sync, U: Send>(
+    items: &[T],
+    f: impl Fn(&T) -> U + Sync + Send,
+) -> Vec<U> {
+    items.par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn map_in_order<T, U>(items: &[T], f: impl Fn(&T) -> U) -> Vec<U> {
+    items.iter().map(f).collect()
+}
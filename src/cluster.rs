@@ -0,0 +1,200 @@
+use std::collections::BTreeSet;
+
+use petgraph::stable_graph::NodeIndex;
+
+use crate::{
+    batch::map_in_order,
+    fingerprint::{fingerprint, index::SimilarityIndex, BitFingerprint, FingerprintOptions},
+    workspace::{structure::StructureId, Workspace},
+};
+
+/// A structure to be clustered or picked
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry<Id = StructureId> {
+    pub id: Id,
+    pub fingerprint: BitFingerprint,
+    /// selectors in the structure, sorted. For a product of substitutions
+    /// they're the sites the fragments came from.
+    pub sites: Vec<String>,
+}
+
+impl<Id> Entry<Id> {
+    /// Entry of the structure which the given atom belongs to
+    pub fn new(
+        id: Id,
+        workspace: &Workspace,
+        node: NodeIndex,
+        options: &FingerprintOptions,
+    ) -> Option<Self> {
+        let mut sites = BTreeSet::new();
+        for root in workspace.components(node)? {
+            for atom in workspace.get_atoms_of_structure(root)? {
+                if let Some(selector) = &workspace.graph[atom].selector {
+                    sites.insert(selector.clone());
+                }
+            }
+        }
+        Some(Self {
+            id,
+            fingerprint: fingerprint(workspace, node, options)?,
+            sites: sites.into_iter().collect(),
+        })
+    }
+}
+
+/// Entries of all structures in the workspace
+pub fn workspace_entries(
+    workspace: &Workspace,
+    options: &FingerprintOptions,
+) -> Option<Vec<Entry>> {
+    workspace
+        .structures()
+        .map(|id| Entry::new(id, workspace, workspace.structure_root(id)?, options))
+        .collect()
+}
+
+/// Entries of SwS given by an iterator, like products of an enumeration.
+/// The first SwS failed to be parsed is returned as error.
+pub fn entries<Id, S: AsRef<str>>(
+    products: impl IntoIterator<Item = (Id, S)>,
+    options: &FingerprintOptions,
+) -> Result<Vec<Entry<Id>>, String> {
+    let mut workspace = Workspace::new();
+    let mut entries = vec![];
+    for (id, sws) in products {
+        workspace.clear();
+        let structure = workspace.add_structure(sws.as_ref())?;
+        let root = workspace
+            .structure_root(structure)
+            .ok_or(format!("Structure of {} not found", sws.as_ref()))?;
+        entries.push(
+            Entry::new(id, &workspace, root, options)
+                .ok_or(format!("Failed to fingerprint {}", sws.as_ref()))?,
+        );
+    }
+    Ok(entries)
+}
+
+/// A cluster by indices of entries, the centroid is also the first member
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cluster {
+    pub centroid: usize,
+    pub members: Vec<usize>,
+}
+
+/// Result of `butina`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Clustering {
+    /// clusters from the largest one
+    pub clusters: Vec<Cluster>,
+    /// index of the cluster of each entry
+    pub assignments: Vec<usize>,
+}
+
+/// Taylor-Butina clustering: entries with most neighbors, which are entries
+/// with Tanimoto not less than `threshold`, become centroids in turn, and
+/// take their neighbors not clustered yet. Neighbors are searched in
+/// parallel with feature `rayon`.
+pub fn butina<Id>(entries: &[Entry<Id>], threshold: f64) -> Clustering {
+    let size = entries
+        .first()
+        .map(|entry| entry.fingerprint.size())
+        .unwrap_or_default();
+    let mut index = SimilarityIndex::new(size);
+    for (position, entry) in entries.iter().enumerate() {
+        // fingerprints of other sizes are clustered alone
+        index.insert(position, &entry.fingerprint).ok();
+    }
+    let fingerprints = entries
+        .iter()
+        .map(|entry| &entry.fingerprint)
+        .collect::<Vec<_>>();
+    let neighbors = map_in_order(&fingerprints, |fingerprint| {
+        let mut neighbors = index
+            .threshold(fingerprint, threshold)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+        neighbors.sort();
+        neighbors
+    });
+    let mut order = (0..entries.len()).collect::<Vec<_>>();
+    order.sort_by_key(|position| std::cmp::Reverse(neighbors[*position].len()));
+    let mut assignments = vec![usize::MAX; entries.len()];
+    let mut clusters = vec![];
+    for centroid in order {
+        if assignments[centroid] != usize::MAX {
+            continue;
+        }
+        let mut members = vec![centroid];
+        assignments[centroid] = clusters.len();
+        for neighbor in neighbors[centroid].iter() {
+            if assignments[*neighbor] == usize::MAX {
+                assignments[*neighbor] = clusters.len();
+                members.push(*neighbor);
+            }
+        }
+        clusters.push(Cluster { centroid, members });
+    }
+    Clustering {
+        clusters,
+        assignments,
+    }
+}
+
+/// MaxMin diversity picking: the entry with the largest Tanimoto distance
+/// to its closest picked entry is picked in turn, until `count` entries are
+/// picked. Picking goes on from `picked`, or from the first entry if it's
+/// empty. Returns indices of entries in the order they're picked, or an
+/// error if an index in `picked` is out of range.
+pub fn max_min<Id>(
+    entries: &[Entry<Id>],
+    count: usize,
+    picked: &[usize],
+) -> Result<Vec<usize>, String> {
+    if let Some(pick) = picked.iter().find(|pick| **pick >= entries.len()) {
+        return Err(format!(
+            "Picked entry {} is out of {} entries",
+            pick,
+            entries.len()
+        ));
+    }
+    let count = count.min(entries.len());
+    let mut picked = picked.to_vec();
+    if picked.is_empty() && count > 0 {
+        picked.push(0);
+    }
+    // distances to the closest picked entry
+    let mut distances = vec![f64::INFINITY; entries.len()];
+    let mut updated = 0;
+    while picked.len() < count {
+        for pick in picked[updated..].iter() {
+            distances[*pick] = f64::NEG_INFINITY;
+            for (position, entry) in entries.iter().enumerate() {
+                // fingerprints of other sizes share nothing
+                let similarity = entry
+                    .fingerprint
+                    .tanimoto(&entries[*pick].fingerprint)
+                    .unwrap_or_default();
+                let distance = 1. - similarity;
+                if distance < distances[position] {
+                    distances[position] = distance;
+                }
+            }
+        }
+        updated = picked.len();
+        let next = (0..entries.len()).fold(None, |best: Option<usize>, position| match best {
+            Some(best) if distances[best] >= distances[position] => Some(best),
+            _ => Some(position),
+        });
+        match next {
+            Some(next) if distances[next] != f64::NEG_INFINITY => picked.push(next),
+            _ => break,
+        }
+    }
+    Ok(picked)
+}
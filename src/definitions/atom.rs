@@ -1,9 +1,13 @@
 use std::{fmt::Display, str::FromStr};
 
-use super::{chirality::ChiralityType, element::Element};
+use super::{
+    chirality::{ChiralityType, StereoGroup},
+    element::Element,
+};
 use crate::tokenizer::{AROMATIC_ORGANIC_RE, NAGETIVE_RE, ORGANIC_SUBSET_RE, STANDARD_NODE_RE};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Atom {
     pub element: Element,
     pub isotope: Option<u16>,
@@ -13,42 +17,53 @@ pub struct Atom {
     pub selector: Option<String>,
     pub aromatic: bool,
     pub react_id: Option<usize>,
+    pub stereo_group: Option<StereoGroup>,
+    pub label: Option<String>,
+    pub coordinates: Option<[f64; 3]>,
+    pub radical_electrons: usize,
 }
 
 impl Atom {
-    pub fn to_token(&self) -> String {
-        let mut token = String::new();
-        if self.element.is_organic_subset()
-            && self.isotope == None
+    /// If the atom has to be written in brackets. As in SwS, a bracket atom
+    /// without `H` is written as it is, it takes hydrogens of the default
+    /// valence when read.
+    pub fn needs_bracket(&self) -> bool {
+        !(self.element.is_organic_subset()
+            && self.isotope.is_none()
             && self.charge == 0
             && self.explicit_hydrogen == 0
-            && self.selector == None
-            && self.react_id == None
-        {
+            && self.chirality_type.is_none()
+            && self.selector.is_none()
+            && self.react_id.is_none())
+    }
+
+    pub fn to_token(&self) -> String {
+        let mut token = String::new();
+        if !self.needs_bracket() {
             token.push_str(&self.core_token());
         } else {
-            token.push_str("[");
+            token.push('[');
             if let Some(isotope) = self.isotope {
                 token.push_str(&isotope.to_string());
             }
             token.push_str(&self.core_token());
             if self.explicit_hydrogen != 0 {
-                token.push_str("H");
+                token.push('H');
                 if self.explicit_hydrogen > 1 {
                     token.push_str(&self.explicit_hydrogen.to_string());
                 }
             }
             token.push_str(&self.charge_token());
             if let Some(react_id) = self.react_id {
-                token.push_str(":");
+                token.push(':');
                 token.push_str(&react_id.to_string());
             }
             if let Some(selector) = &self.selector {
-                token.push_str("{");
+                token.push('{');
                 token.push_str(selector);
-                token.push_str("}");
+                token.push('}');
             }
-            token.push_str("]");
+            token.push(']');
         }
         token
     }
@@ -70,9 +85,9 @@ impl Atom {
     fn charge_token(&self) -> String {
         let mut charge_token = String::new();
         if self.charge < 0 {
-            charge_token.push_str("-");
+            charge_token.push('-');
         } else if self.charge > 0 {
-            charge_token.push_str("+");
+            charge_token.push('+');
         }
         if self.charge.abs() > 1 {
             charge_token.push_str(&self.charge.abs().to_string())
@@ -92,32 +107,32 @@ impl Atom {
                 selector: None,
                 aromatic,
                 react_id: None,
+                stereo_group: None,
+                label: None,
+                coordinates: None,
+                radical_electrons: 0,
             })
         } else if let Some(captured) = STANDARD_NODE_RE.captures(token) {
             let (element, aromatic, chirality_type) = Self::minimal_node_info(&captured)?;
-            let isotope: Option<u16> =
-                captured
-                    .name("isotope")
-                    .and_then(|m| match m.as_str().parse() {
-                        Ok(isotope) => Some(isotope),
-                        Err(_) => None,
-                    });
+            let isotope: Option<u16> = captured
+                .name("isotope")
+                .and_then(|m| m.as_str().parse().ok());
             let explicit_hydrogen = captured
                 .name("explicit_hydrogen")
-                .and_then(|_| match captured.name("explicit_hydrogen_num") {
-                    Some(num) => Some(num.as_str().parse::<usize>().unwrap_or(1)),
-                    None => Some(1),
+                .map(|_| match captured.name("explicit_hydrogen_num") {
+                    Some(num) => num.as_str().parse::<usize>().unwrap_or(1),
+                    None => 1,
                 })
                 .map_or(0, |v| v);
             let charge = {
                 if let Some(charge) = captured
                     .name("charge_num")
-                    .and_then(|m| Some(m.as_str().parse::<isize>().unwrap()))
+                    .map(|m| m.as_str().parse::<isize>())
+                    .transpose()
+                    .ok()?
                 {
                     charge
-                } else if let Some(charge_str) =
-                    captured.name("charge").and_then(|m| Some(m.as_str()))
-                {
+                } else if let Some(charge_str) = captured.name("charge").map(|m| m.as_str()) {
                     charge_str.len() as isize * {
                         if NAGETIVE_RE.is_match(charge_str) {
                             -1
@@ -131,18 +146,18 @@ impl Atom {
             };
             let react_id = captured
                 .name("react_id")
-                .and_then(|m| Some(m.as_str()))
-                .and_then(|s| Some(s.parse::<usize>().unwrap()));
+                .map(|m| m.as_str())
+                .map(|s| s.parse::<usize>())
+                .transpose()
+                .ok()?;
             let selector = captured
                 .name("selector")
-                .and_then(|m| {
-                    Some(
-                        m.as_str()
-                            .strip_prefix("{")
-                            .unwrap()
-                            .strip_suffix("}")
-                            .unwrap(),
-                    )
+                .map(|m| {
+                    m.as_str()
+                        .strip_prefix("{")
+                        .unwrap()
+                        .strip_suffix("}")
+                        .unwrap()
                 })
                 .map(String::from);
             Some(Atom {
@@ -154,6 +169,10 @@ impl Atom {
                 selector,
                 aromatic,
                 react_id,
+                stereo_group: None,
+                label: None,
+                coordinates: None,
+                radical_electrons: 0,
             })
         } else {
             None
@@ -163,15 +182,15 @@ impl Atom {
     fn minimal_node_info(
         captured: &regex::Captures,
     ) -> Option<(Element, bool, Option<ChiralityType>)> {
-        let element = captured.name("element").and_then(|m| Some(m.as_str()))?;
+        let element = captured.name("element").map(|m| m.as_str())?;
         let aromatic = AROMATIC_ORGANIC_RE.is_match(element);
         let chirality_type = captured
             .name("chirality")
-            .and_then(|m| Some(m.as_str()))
+            .map(|m| m.as_str())
             .and_then(ChiralityType::new);
         let mut capitalized = element[0..1].to_uppercase();
         capitalized.push_str(&element[1..]);
-        let element = Element::from_str(&capitalized).expect("Invalid given element.");
+        let element = Element::from_str(&capitalized).ok()?;
         Some((element, aromatic, chirality_type))
     }
 }
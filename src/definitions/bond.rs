@@ -3,6 +3,7 @@ use std::fmt::Display;
 use crate::tokenizer::BOND_RE;
 
 #[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BondType {
     Single,
     UpSingle,
@@ -32,9 +33,22 @@ impl BondType {
             None
         }
     }
+
+    /// valence used by the bond on each atom, an aromatic bond uses 1 and
+    /// the extra valence of aromatic atoms is counted by atoms.
+    pub fn valence(&self) -> usize {
+        match self {
+            BondType::Single | BondType::UpSingle | BondType::DownSingle | BondType::Aromatic => 1,
+            BondType::Double => 2,
+            BondType::Triple => 3,
+            BondType::Quad => 4,
+            BondType::NoBond => 0,
+        }
+    }
 }
 
 #[derive(PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bond {
     bond_type: BondType,
     ring: bool,
@@ -45,12 +59,12 @@ impl Bond {
         Bond { bond_type, ring }
     }
 
+    pub fn bond_type(&self) -> BondType {
+        self.bond_type
+    }
+
     pub fn is_no_bond(&self) -> bool {
-        if let BondType::NoBond = self.bond_type {
-            true
-        } else {
-            false
-        }
+        matches!(self.bond_type, BondType::NoBond)
     }
 
     pub fn is_ring_bond(&self) -> bool {
@@ -1,4 +1,5 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ChiralityType {
     Clockwise,
     Counter,
@@ -19,4 +20,42 @@ impl ChiralityType {
             Self::Counter => "@"
         }
     }
-}
\ No newline at end of file
+
+    pub fn inverted(&self) -> Self {
+        match self {
+            Self::Clockwise => Self::Counter,
+            Self::Counter => Self::Clockwise,
+        }
+    }
+
+    /// Get the chirality of the same center when its neighbors are listed
+    /// in another order. Returns None if the orders are not permutations
+    /// of each other.
+    pub fn reorder<T: PartialEq>(&self, from: &[T], to: &[T]) -> Option<Self> {
+        if from.len() != to.len() {
+            return None;
+        }
+        let positions = to
+            .iter()
+            .map(|item| from.iter().position(|other| other == item))
+            .collect::<Option<Vec<_>>>()?;
+        let mut swaps = 0;
+        for (index, position) in positions.iter().enumerate() {
+            swaps += positions[index + 1..]
+                .iter()
+                .filter(|other| *other < position)
+                .count();
+        }
+        Some(if swaps % 2 == 0 { *self } else { self.inverted() })
+    }
+}
+/// Enhanced stereo of a stereo center: its configuration is known
+/// (`Absolute`), or it's one of two configurations (`Or`), or a mixture of
+/// both (`And`). Centers of a same `Or` or `And` group change together.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StereoGroup {
+    Absolute,
+    Or(usize),
+    And(usize),
+}
@@ -1,8 +1,12 @@
 use strum_macros::{AsRefStr, EnumString};
 
-#[derive(EnumString, AsRefStr, Debug, PartialEq)]
+#[derive(EnumString, AsRefStr, Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Element {
-    H = 1,
+    /// `*`, a wildcard or attachment point
+    #[strum(serialize = "*")]
+    Any = 0,
+    H,
     He,
     Li,
     Be,
@@ -47,7 +51,7 @@ pub enum Element {
     Tc,
     Ru,
     Rh,
-    Rd,
+    Pd,
     Ag,
     Cd,
     In,
@@ -91,6 +95,16 @@ pub enum Element {
 }
 
 impl Element {
+    /// palladium used to be misspelled
+    #[deprecated(note = "use `Element::Pd`")]
+    #[allow(non_upper_case_globals)]
+    pub const Rd: Self = Self::Pd;
+
+    /// Atomic number, 0 for `*`
+    pub fn atomic_number(&self) -> usize {
+        *self as usize
+    }
+
     pub fn default_hydrogen(&self) -> usize {
         match self {
             Self::F | Self::Cl | Self::Br | Self::I => 1,
@@ -101,19 +115,156 @@ impl Element {
         }
     }
 
-    pub fn is_organic_subset(&self) -> bool {
+    /// Single bond covalent radius in angstrom (Cordero et al. 2008), 1.5
+    /// for elements not listed.
+    pub fn covalent_radius(&self) -> f64 {
         match self {
-            Self::B
-            | Self::C
-            | Self::N
-            | Self::O
-            | Self::F
-            | Self::P
-            | Self::S
-            | Self::Cl
-            | Self::Br 
-            | Self::I => true,
-            _ => false,
+            Self::H => 0.31,
+            Self::Li => 1.28,
+            Self::Be => 0.96,
+            Self::B => 0.84,
+            Self::C => 0.76,
+            Self::N => 0.71,
+            Self::O => 0.66,
+            Self::F => 0.57,
+            Self::Na => 1.66,
+            Self::Mg => 1.41,
+            Self::Al => 1.21,
+            Self::Si => 1.11,
+            Self::P => 1.07,
+            Self::S => 1.05,
+            Self::Cl => 1.02,
+            Self::K => 2.03,
+            Self::Ca => 1.76,
+            Self::Fe => 1.32,
+            Self::Co => 1.26,
+            Self::Ni => 1.24,
+            Self::Cu => 1.32,
+            Self::Zn => 1.22,
+            Self::Ge => 1.20,
+            Self::As => 1.19,
+            Self::Se => 1.20,
+            Self::Br => 1.20,
+            Self::Ag => 1.45,
+            Self::Sn => 1.39,
+            Self::Sb => 1.39,
+            Self::Te => 1.38,
+            Self::I => 1.39,
+            Self::Pt => 1.36,
+            Self::Au => 1.36,
+            Self::Hg => 1.32,
+            _ => 1.5,
         }
     }
+
+    /// Standard atomic weight (IUPAC 2021, abridged), the mass number of the
+    /// most stable isotope for elements without stable ones, 0 for `*`.
+    pub fn atomic_weight(&self) -> f64 {
+        match self {
+            Self::Any => 0.,
+            Self::H => 1.008,
+            Self::He => 4.0026,
+            Self::Li => 6.94,
+            Self::Be => 9.0122,
+            Self::B => 10.81,
+            Self::C => 12.011,
+            Self::N => 14.007,
+            Self::O => 15.999,
+            Self::F => 18.998,
+            Self::Ne => 20.180,
+            Self::Na => 22.990,
+            Self::Mg => 24.305,
+            Self::Al => 26.982,
+            Self::Si => 28.085,
+            Self::P => 30.974,
+            Self::S => 32.06,
+            Self::Cl => 35.45,
+            Self::Ar => 39.948,
+            Self::K => 39.098,
+            Self::Ca => 40.078,
+            Self::Sc => 44.956,
+            Self::Ti => 47.867,
+            Self::V => 50.942,
+            Self::Cr => 51.996,
+            Self::Mn => 54.938,
+            Self::Fe => 55.845,
+            Self::Co => 58.933,
+            Self::Ni => 58.693,
+            Self::Cu => 63.546,
+            Self::Zn => 65.38,
+            Self::Ga => 69.723,
+            Self::Ge => 72.630,
+            Self::As => 74.922,
+            Self::Se => 78.971,
+            Self::Br => 79.904,
+            Self::Kr => 83.798,
+            Self::Rb => 85.468,
+            Self::Sr => 87.62,
+            Self::Y => 88.906,
+            Self::Zr => 91.224,
+            Self::Nb => 92.906,
+            Self::Mo => 95.95,
+            Self::Tc => 98.,
+            Self::Ru => 101.07,
+            Self::Rh => 102.91,
+            Self::Pd => 106.42,
+            Self::Ag => 107.87,
+            Self::Cd => 112.41,
+            Self::In => 114.82,
+            Self::Sn => 118.71,
+            Self::Sb => 121.76,
+            Self::Te => 127.60,
+            Self::I => 126.90,
+            Self::Xe => 131.29,
+            Self::Cs => 132.91,
+            Self::Ba => 137.33,
+            Self::La => 138.91,
+            Self::Ce => 140.12,
+            Self::Pr => 140.91,
+            Self::Nd => 144.24,
+            Self::Pm => 145.,
+            Self::Sm => 150.36,
+            Self::Eu => 151.96,
+            Self::Gd => 157.25,
+            Self::Tb => 158.93,
+            Self::Dy => 162.50,
+            Self::Ho => 164.93,
+            Self::Er => 167.26,
+            Self::Tm => 168.93,
+            Self::Yb => 173.05,
+            Self::Lu => 174.97,
+            Self::Hf => 178.49,
+            Self::Ta => 180.95,
+            Self::W => 183.84,
+            Self::Re => 186.21,
+            Self::Os => 190.23,
+            Self::Ir => 192.22,
+            Self::Pt => 195.08,
+            Self::Au => 196.97,
+            Self::Hg => 200.59,
+            Self::Tl => 204.38,
+            Self::Pb => 207.2,
+            Self::Bi => 208.98,
+            Self::Po => 209.,
+            Self::At => 210.,
+            Self::Rn => 222.,
+        }
+    }
+
+    pub fn is_organic_subset(&self) -> bool {
+        matches!(
+            self,
+            Self::Any
+                | Self::B
+                | Self::C
+                | Self::N
+                | Self::O
+                | Self::F
+                | Self::P
+                | Self::S
+                | Self::Cl
+                | Self::Br
+                | Self::I
+        )
+    }
 }
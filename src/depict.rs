@@ -0,0 +1,519 @@
+use std::{collections::HashMap, fmt::Write};
+
+use petgraph::{
+    stable_graph::{EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+};
+
+use crate::{
+    definitions::{bond::BondType, element::Element},
+    geometry::{add, centroid, distance, normal, scale, sub, unit, Point},
+    workspace::Workspace,
+};
+
+/// Options of `to_svg`
+#[derive(Debug, Clone)]
+pub struct DepictOptions {
+    /// length of bonds in pixels
+    pub bond_length: f64,
+    /// draw aromatic rings as circles, or as Kekulé structures
+    pub aromatic_circles: bool,
+    /// highlight atoms with selectors and annotate them with their selectors
+    pub show_selectors: bool,
+}
+
+impl Default for DepictOptions {
+    fn default() -> Self {
+        Self {
+            bond_length: 30.,
+            aromatic_circles: true,
+            show_selectors: true,
+        }
+    }
+}
+
+/// Render the structure which the given atom belongs to as SVG. Coordinates
+/// of atoms are used if all atoms have them, otherwise 2D coordinates are
+/// computed without changing the workspace.
+pub fn to_svg(workspace: &Workspace, node: NodeIndex, options: &DepictOptions) -> Option<String> {
+    let mut atoms = vec![];
+    for root in workspace.components(node)? {
+        atoms.append(&mut workspace.get_atoms_of_structure(root)?);
+    }
+    let laid_out;
+    let workspace = if atoms
+        .iter()
+        .all(|atom| workspace.graph[*atom].coordinates.is_some())
+    {
+        workspace
+    } else {
+        let mut copy = workspace.clone();
+        copy.compute_2d_coords(node)?;
+        laid_out = copy;
+        &laid_out
+    };
+    Depiction::new(workspace, node, atoms, options)?.render()
+}
+
+struct Depiction<'a> {
+    workspace: &'a Workspace,
+    options: &'a DepictOptions,
+    atoms: Vec<NodeIndex>,
+    bonds: Vec<EdgeIndex>,
+    rings: Vec<Vec<NodeIndex>>,
+    /// positions in pixels
+    positions: HashMap<NodeIndex, Point>,
+    width: f64,
+    height: f64,
+}
+
+impl<'a> Depiction<'a> {
+    fn new(
+        workspace: &'a Workspace,
+        node: NodeIndex,
+        atoms: Vec<NodeIndex>,
+        options: &'a DepictOptions,
+    ) -> Option<Self> {
+        let bonds = atoms
+            .iter()
+            .flat_map(|atom| workspace.graph.edges(*atom).map(|edge| edge.id()))
+            .filter(|edge| !workspace.graph[*edge].is_no_bond())
+            .collect::<Vec<_>>();
+        let points = atoms
+            .iter()
+            .map(|atom| {
+                let [x, y, _] = workspace.graph[*atom].coordinates?;
+                Some((*atom, (x, y)))
+            })
+            .collect::<Option<HashMap<_, _>>>()?;
+        // scale the average bond to the bond length in pixels
+        let lengths = bonds
+            .iter()
+            .filter_map(|edge| {
+                let (from, to) = workspace.graph.edge_endpoints(*edge)?;
+                Some(distance(points[&from], points[&to]))
+            })
+            .filter(|length| *length > 1e-6)
+            .collect::<Vec<_>>();
+        let factor = if lengths.is_empty() {
+            1.
+        } else {
+            options.bond_length * lengths.len() as f64 / lengths.iter().sum::<f64>()
+        };
+        let (mut min_x, mut max_x) = (f64::MAX, f64::MIN);
+        let (mut min_y, mut max_y) = (f64::MAX, f64::MIN);
+        for (x, y) in points.values() {
+            min_x = min_x.min(*x);
+            max_x = max_x.max(*x);
+            min_y = min_y.min(*y);
+            max_y = max_y.max(*y);
+        }
+        let margin = options.bond_length;
+        let positions = points
+            .into_iter()
+            .map(|(atom, (x, y))| {
+                let point = ((x - min_x) * factor + margin, (max_y - y) * factor + margin);
+                (atom, point)
+            })
+            .collect();
+        Some(Self {
+            workspace,
+            options,
+            atoms,
+            bonds,
+            rings: workspace.rings(node)?,
+            positions,
+            width: (max_x - min_x) * factor + 2. * margin,
+            height: (max_y - min_y) * factor + 2. * margin,
+        })
+    }
+
+    fn render(&self) -> Option<String> {
+        let length = self.options.bond_length;
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.1}" height="{:.1}" viewBox="0 0 {:.1} {:.1}">"#,
+            self.width, self.height, self.width, self.height
+        )
+        .ok()?;
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).ok()?;
+        if self.options.show_selectors {
+            for atom in self.selector_atoms() {
+                let (x, y) = self.positions[&atom];
+                writeln!(
+                    svg,
+                    r##"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="#ffe082" fill-opacity="0.8"/>"##,
+                    x,
+                    y,
+                    length * 0.4
+                )
+                .ok()?;
+            }
+        }
+        // y of pixels goes down
+        let flipped = self
+            .positions
+            .iter()
+            .map(|(atom, (x, y))| (*atom, (*x, -*y)))
+            .collect();
+        let wedges = self.workspace.wedges(&self.atoms, &flipped);
+        let kekule = if self.options.aromatic_circles {
+            None
+        } else {
+            self.workspace.kekulize(self.atoms[0])
+        };
+        for edge in self.bonds.iter() {
+            self.render_bond(&mut svg, *edge, &wedges, kekule.as_ref())?;
+        }
+        if self.options.aromatic_circles {
+            for ring in self.rings.iter().filter(|ring| self.is_aromatic_ring(ring)) {
+                let points = ring
+                    .iter()
+                    .map(|atom| self.positions[atom])
+                    .collect::<Vec<_>>();
+                let center = centroid(&points)?;
+                let radius = points
+                    .iter()
+                    .map(|point| distance(*point, center))
+                    .sum::<f64>()
+                    / points.len() as f64
+                    * (std::f64::consts::PI / points.len() as f64).cos()
+                    * 0.65;
+                writeln!(
+                    svg,
+                    r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="none" stroke="black" stroke-width="1"/>"#,
+                    center.0, center.1, radius
+                )
+                .ok()?;
+            }
+        }
+        for atom in self.atoms.iter() {
+            self.render_label(&mut svg, *atom)?;
+        }
+        if self.options.show_selectors {
+            for atom in self.selector_atoms() {
+                let selector = self.workspace.graph[atom].selector.as_deref()?;
+                let direction = self.free_direction(atom);
+                let (x, y) = add(self.positions[&atom], scale(direction, length * 0.85));
+                writeln!(
+                    svg,
+                    r##"<text x="{:.2}" y="{:.2}" font-family="sans-serif" font-size="{:.1}" font-style="italic" fill="#c62828" text-anchor="middle" dominant-baseline="central">{{{}}}</text>"##,
+                    x,
+                    y,
+                    length * 0.35,
+                    escape(selector)
+                )
+                .ok()?;
+            }
+        }
+        svg.push_str("</svg>\n");
+        Some(svg)
+    }
+
+    fn render_bond(
+        &self,
+        svg: &mut String,
+        edge: EdgeIndex,
+        wedges: &HashMap<EdgeIndex, (NodeIndex, bool)>,
+        kekule: Option<&HashMap<EdgeIndex, BondType>>,
+    ) -> Option<()> {
+        let length = self.options.bond_length;
+        let (from, to) = self.workspace.graph.edge_endpoints(edge)?;
+        let (start, end) = self.trimmed(from, to);
+        if let Some((center, hashed)) = wedges.get(&edge) {
+            let (start, end) = if *center == from {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            let normal = scale(normal(start, end), length * 0.1);
+            if *hashed {
+                for step in 0..=6 {
+                    let ratio = step as f64 / 6.;
+                    let point = add(start, scale(sub(end, start), ratio));
+                    let half = scale(normal, ratio.max(0.1));
+                    line(svg, add(point, half), sub(point, half), "black")?;
+                }
+            } else {
+                writeln!(
+                    svg,
+                    r#"<polygon points="{:.2},{:.2} {:.2},{:.2} {:.2},{:.2}" fill="black"/>"#,
+                    start.0,
+                    start.1,
+                    end.0 + normal.0,
+                    end.1 + normal.1,
+                    end.0 - normal.0,
+                    end.1 - normal.1
+                )
+                .ok()?;
+            }
+            return Some(());
+        }
+        let bond_type = match self.workspace.graph[edge].bond_type() {
+            BondType::Aromatic => kekule
+                .and_then(|bonds| bonds.get(&edge).copied())
+                .unwrap_or(BondType::Aromatic),
+            bond_type => bond_type,
+        };
+        let gap = length * 0.16;
+        let ring = self.smallest_ring_of(from, to);
+        match (bond_type, ring) {
+            (BondType::Double, Some(center)) => {
+                line(svg, start, end, "black")?;
+                let (inner_start, inner_end) = inner_line(start, end, center, gap);
+                line(svg, inner_start, inner_end, "black")?;
+            }
+            (BondType::Aromatic, Some(center)) if !self.options.aromatic_circles => {
+                line(svg, start, end, "black")?;
+                let (inner_start, inner_end) = inner_line(start, end, center, gap);
+                dashed_line(svg, inner_start, inner_end)?;
+            }
+            (BondType::Double | BondType::Triple | BondType::Quad, _) => {
+                let count = bond_type.valence();
+                let normal = normal(start, end);
+                for index in 0..count {
+                    let offset = scale(normal, gap * (index as f64 - (count - 1) as f64 / 2.));
+                    line(svg, add(start, offset), add(end, offset), "black")?;
+                }
+            }
+            _ => line(svg, start, end, "black")?,
+        }
+        Some(())
+    }
+
+    fn render_label(&self, svg: &mut String, node: NodeIndex) -> Option<()> {
+        let atom = &self.workspace.graph[node];
+        let neighbors = self.neighbors(node);
+        let shown = atom.element != Element::C
+            || atom.isotope.is_some()
+            || atom.charge != 0
+            || atom.radical_electrons != 0
+            || atom.label.is_some()
+            || neighbors.is_empty();
+        if !shown {
+            return Some(());
+        }
+        let size = self.options.bond_length * 0.5;
+        let (x, y) = self.positions[&node];
+        let mut text = String::new();
+        if let Some(isotope) = atom.isotope {
+            write!(
+                text,
+                r#"<tspan font-size="{:.1}" dy="{:.1}">{}</tspan><tspan dy="{:.1}">"#,
+                size * 0.65,
+                -size * 0.3,
+                isotope,
+                size * 0.3
+            )
+            .ok()?;
+        } else {
+            text.push_str("<tspan>");
+        }
+        let symbol = match &atom.label {
+            Some(label) => escape(label),
+            None => atom.element.as_ref().to_string(),
+        };
+        let hydrogens = match (atom.label.is_some(), atom.element) {
+            (true, _) | (false, Element::Any) => 0,
+            _ => self.workspace.implicit_hydrogen_count(node)?,
+        };
+        let hydrogen_text = match hydrogens {
+            0 => String::new(),
+            1 => "H".to_string(),
+            count => format!(
+                r#"H<tspan font-size="{:.1}" dy="{:.1}">{}</tspan><tspan dy="{:.1}"></tspan>"#,
+                size * 0.65,
+                size * 0.25,
+                count,
+                -size * 0.25
+            ),
+        };
+        // hydrogens go before the symbol if bonds are all on the right
+        let left = !neighbors.is_empty()
+            && neighbors
+                .iter()
+                .all(|neighbor| self.positions[neighbor].0 > x + 1e-6);
+        if left {
+            text.push_str(&hydrogen_text);
+            text.push_str(&symbol);
+        } else {
+            text.push_str(&symbol);
+            text.push_str(&hydrogen_text);
+        }
+        text.push_str("</tspan>");
+        let charge = match atom.charge {
+            0 => String::new(),
+            1 => "+".to_string(),
+            -1 => "−".to_string(),
+            charge if charge > 0 => format!("{}+", charge),
+            charge => format!("{}−", -charge),
+        };
+        let radical = "•".repeat(atom.radical_electrons);
+        if !charge.is_empty() || !radical.is_empty() {
+            write!(
+                text,
+                r#"<tspan font-size="{:.1}" dy="{:.1}">{}{}</tspan>"#,
+                size * 0.65,
+                -size * 0.35,
+                charge,
+                radical
+            )
+            .ok()?;
+        }
+        writeln!(
+            svg,
+            r#"<text x="{:.2}" y="{:.2}" font-family="sans-serif" font-size="{:.1}" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+            x,
+            y,
+            size,
+            color(atom.element),
+            text
+        )
+        .ok()
+    }
+
+    fn selector_atoms(&self) -> Vec<NodeIndex> {
+        self.atoms
+            .iter()
+            .copied()
+            .filter(|atom| self.workspace.graph[*atom].selector.is_some())
+            .collect()
+    }
+
+    fn neighbors(&self, atom: NodeIndex) -> Vec<NodeIndex> {
+        self.workspace
+            .graph
+            .edges_directed(atom, petgraph::Incoming)
+            .chain(
+                self.workspace
+                    .graph
+                    .edges_directed(atom, petgraph::Outgoing),
+            )
+            .filter(|edge| !edge.weight().is_no_bond())
+            .map(|edge| {
+                if edge.source() == atom {
+                    edge.target()
+                } else {
+                    edge.source()
+                }
+            })
+            .collect()
+    }
+
+    /// direction with most free space around an atom
+    fn free_direction(&self, atom: NodeIndex) -> Point {
+        let position = self.positions[&atom];
+        let sum = self
+            .neighbors(atom)
+            .iter()
+            .filter_map(|neighbor| unit(sub(self.positions[neighbor], position)))
+            .fold((0., 0.), add);
+        unit(scale(sum, -1.)).unwrap_or((0., 1.))
+    }
+
+    /// Ends of a bond, shortened at atoms with labels
+    fn trimmed(&self, from: NodeIndex, to: NodeIndex) -> (Point, Point) {
+        let (mut start, mut end) = (self.positions[&from], self.positions[&to]);
+        let direction = unit(sub(end, start)).unwrap_or((1., 0.));
+        let trim = self.options.bond_length * 0.3;
+        if self.has_label(from) {
+            start = add(start, scale(direction, trim));
+        }
+        if self.has_label(to) {
+            end = sub(end, scale(direction, trim));
+        }
+        (start, end)
+    }
+
+    fn has_label(&self, atom: NodeIndex) -> bool {
+        let atom = &self.workspace.graph[atom];
+        atom.element != Element::C
+            || atom.isotope.is_some()
+            || atom.charge != 0
+            || atom.radical_electrons != 0
+            || atom.label.is_some()
+    }
+
+    /// Center of the smallest ring containing the bond
+    fn smallest_ring_of(&self, from: NodeIndex, to: NodeIndex) -> Option<Point> {
+        self.rings
+            .iter()
+            .find(|ring| {
+                ring.iter().enumerate().any(|(index, atom)| {
+                    let next = ring[(index + 1) % ring.len()];
+                    (*atom, next) == (from, to) || (*atom, next) == (to, from)
+                })
+            })
+            .and_then(|ring| {
+                centroid(
+                    &ring
+                        .iter()
+                        .map(|atom| self.positions[atom])
+                        .collect::<Vec<_>>(),
+                )
+            })
+    }
+
+    fn is_aromatic_ring(&self, ring: &[NodeIndex]) -> bool {
+        ring.iter().enumerate().all(|(index, atom)| {
+            self.workspace
+                .get_edge_undirected(*atom, ring[(index + 1) % ring.len()])
+                .is_some_and(|(bond, _, _)| bond.is_aromatic())
+        })
+    }
+}
+
+fn line(svg: &mut String, from: Point, to: Point, color: &str) -> Option<()> {
+    writeln!(
+        svg,
+        r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-width="1.5" stroke-linecap="round"/>"#,
+        from.0, from.1, to.0, to.1, color
+    )
+    .ok()
+}
+
+fn dashed_line(svg: &mut String, from: Point, to: Point) -> Option<()> {
+    writeln!(
+        svg,
+        r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="black" stroke-width="1.5" stroke-dasharray="3,3"/>"#,
+        from.0, from.1, to.0, to.1
+    )
+    .ok()
+}
+
+/// The second line of a double bond in a ring, moved to the ring center
+/// and shortened
+fn inner_line(start: Point, end: Point, center: Point, gap: f64) -> (Point, Point) {
+    let mut normal = normal(start, end);
+    let middle = scale(add(start, end), 0.5);
+    if distance(add(middle, normal), center) > distance(sub(middle, normal), center) {
+        normal = scale(normal, -1.);
+    }
+    let shorten = scale(sub(end, start), 0.15);
+    (
+        add(add(start, scale(normal, gap)), shorten),
+        sub(add(end, scale(normal, gap)), shorten),
+    )
+}
+
+/// Color of element labels
+fn color(element: Element) -> &'static str {
+    match element {
+        Element::N => "#3050f8",
+        Element::O => "#ff0d0d",
+        Element::S => "#c6a600",
+        Element::P => "#ff8000",
+        Element::F | Element::Cl => "#1fb01f",
+        Element::Br => "#a62929",
+        Element::I => "#940094",
+        _ => "black",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,147 @@
+pub mod qc;
+
+use std::{collections::HashMap, fmt::Write};
+
+use petgraph::stable_graph::NodeIndex;
+
+use crate::workspace::Workspace;
+
+/// Atoms of all components of the structure which the given atom belongs
+/// to, with their coordinates. Returns None if an atom has no coordinates.
+fn atoms_with_coordinates(
+    workspace: &Workspace,
+    node: NodeIndex,
+) -> Option<Vec<(usize, NodeIndex, [f64; 3])>> {
+    let mut atoms = vec![];
+    for (component, root) in workspace.components(node)?.into_iter().enumerate() {
+        for atom in workspace.get_atoms_of_structure(root)? {
+            atoms.push((component, atom, workspace.get_atom(atom)?.coordinates?));
+        }
+    }
+    Some(atoms)
+}
+
+/// Write the structure as XYZ. The comment line has the name of the
+/// structure and selectors of atoms as `index:selector`, indexes start
+/// from 1. Returns None if an atom has no coordinates.
+pub fn to_xyz(workspace: &Workspace, node: NodeIndex) -> Option<String> {
+    let atoms = atoms_with_coordinates(workspace, node)?;
+    let mut comment = workspace
+        .structure_of(node)
+        .and_then(|id| workspace.structure_name(id))
+        .unwrap_or_default()
+        .to_string();
+    let selectors = atoms
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (_, atom, _))| {
+            let selector = workspace.get_atom(*atom)?.selector.as_ref()?;
+            Some(format!("{}:{}", index + 1, selector))
+        })
+        .collect::<Vec<_>>();
+    if !selectors.is_empty() {
+        if !comment.is_empty() {
+            comment.push(' ');
+        }
+        comment.push_str("selectors ");
+        comment.push_str(&selectors.join(" "));
+    }
+    let mut xyz = format!("{}\n{}\n", atoms.len(), comment);
+    for (_, atom, [x, y, z]) in atoms.iter() {
+        writeln!(
+            xyz,
+            "{:<2} {:>12.6} {:>12.6} {:>12.6}",
+            workspace.get_atom(*atom)?.element.as_ref(),
+            x,
+            y,
+            z
+        )
+        .ok()?;
+    }
+    Some(xyz)
+}
+
+/// Write the structure as PDB `HETATM` and `CONECT` records, each component
+/// is a residue `UNL`. An atom with selector is named by the first 4 letters
+/// and digits of its selector, and the full selector is kept in a `REMARK`
+/// line. Returns None if an atom has no coordinates.
+pub fn to_pdb(workspace: &Workspace, node: NodeIndex) -> Option<String> {
+    let atoms = atoms_with_coordinates(workspace, node)?;
+    let serial_of = atoms
+        .iter()
+        .enumerate()
+        .map(|(index, (_, atom, _))| (*atom, index + 1))
+        .collect::<HashMap<_, _>>();
+    let mut pdb = String::new();
+    if let Some(name) = workspace
+        .structure_of(node)
+        .and_then(|id| workspace.structure_name(id))
+    {
+        writeln!(pdb, "COMPND    {}", name).ok()?;
+    }
+    for (index, (_, atom, _)) in atoms.iter().enumerate() {
+        if let Some(selector) = workspace.get_atom(*atom)?.selector.as_ref() {
+            writeln!(pdb, "REMARK   1 SELECTOR {:>5} {}", index + 1, selector).ok()?;
+        }
+    }
+    let mut element_counts: HashMap<&str, usize> = HashMap::new();
+    for (index, (component, node, [x, y, z])) in atoms.iter().enumerate() {
+        let atom = workspace.get_atom(*node)?;
+        let symbol = atom.element.as_ref();
+        let count = element_counts.entry(symbol).or_default();
+        *count += 1;
+        let name = match &atom.selector {
+            Some(selector) => selector
+                .chars()
+                .filter(|char| char.is_ascii_alphanumeric())
+                .take(4)
+                .collect::<String>(),
+            None => format!("{}{}", symbol.to_uppercase(), count),
+        };
+        // names of one letter elements start from the 14th column
+        let name = if name.len() < 4 && symbol.len() == 1 {
+            format!(" {:<3}", name)
+        } else {
+            format!("{:<4.4}", name)
+        };
+        let charge = match atom.charge {
+            0 => String::new(),
+            charge if charge > 0 => format!("{}+", charge),
+            charge => format!("{}-", -charge),
+        };
+        writeln!(
+            pdb,
+            "HETATM{:>5} {} UNL A{:>4}    {:>8.3}{:>8.3}{:>8.3}{:>6.2}{:>6.2}          {:>2}{:<2}",
+            index + 1,
+            name,
+            component + 1,
+            x,
+            y,
+            z,
+            1.,
+            0.,
+            symbol.to_uppercase(),
+            charge
+        )
+        .ok()?;
+    }
+    for (_, atom, _) in atoms.iter() {
+        let mut bonded = workspace
+            .graph
+            .neighbors_undirected(*atom)
+            .filter_map(|neighbor| serial_of.get(&neighbor).copied())
+            .collect::<Vec<_>>();
+        bonded.sort();
+        bonded.dedup();
+        // at most 4 atoms in a record
+        for chunk in bonded.chunks(4) {
+            write!(pdb, "CONECT{:>5}", serial_of[atom]).ok()?;
+            for serial in chunk {
+                write!(pdb, "{:>5}", serial).ok()?;
+            }
+            pdb.push('\n');
+        }
+    }
+    pdb.push_str("END\n");
+    Some(pdb)
+}
@@ -0,0 +1,389 @@
+use petgraph::stable_graph::NodeIndex;
+
+use super::atoms_with_coordinates;
+use crate::{definitions::element::Element, workspace::Workspace};
+
+/// Settings of a quantum chemistry calculation
+#[derive(Debug, Clone, Default)]
+pub struct QcSettings {
+    /// like `B3LYP`, or `GFN2` for xTB
+    pub method: String,
+    /// default basis set, atoms can override it by `Basis(...)` selectors
+    pub basis: String,
+    /// other keywords, like `Opt` and `Freq`
+    pub keywords: Vec<String>,
+    pub title: String,
+}
+
+/// An atom of the input with directives from its selector
+struct QcAtom {
+    symbol: String,
+    atomic_number: usize,
+    position: [f64; 3],
+    charge: isize,
+    radical_electrons: usize,
+    /// `{Freeze}`
+    frozen: bool,
+    /// `{Fragment(n)}`
+    fragment: Option<usize>,
+    /// `{Basis(name)}`
+    basis: Option<String>,
+}
+
+/// Total charge of the structure which the given atom belongs to
+pub fn total_charge(workspace: &Workspace, node: NodeIndex) -> Option<isize> {
+    let atoms = workspace
+        .components(node)?
+        .into_iter()
+        .map(|root| workspace.get_atoms_of_structure(root))
+        .collect::<Option<Vec<_>>>()?;
+    Some(
+        atoms
+            .iter()
+            .flatten()
+            .filter_map(|atom| workspace.get_atom(*atom))
+            .map(|atom| atom.charge)
+            .sum(),
+    )
+}
+
+/// Spin multiplicity of the structure which the given atom belongs to, all
+/// unpaired electrons of radicals are taken as parallel. Implicit hydrogens
+/// are counted, fails if the parity of electrons doesn't agree with the
+/// unpaired ones.
+pub fn multiplicity(workspace: &Workspace, node: NodeIndex) -> Result<usize, String> {
+    let not_found = || format!("Structure of {:?} not found", node);
+    let mut electrons = 0;
+    let mut unpaired = 0;
+    for root in workspace.components(node).ok_or_else(not_found)? {
+        for index in workspace
+            .get_atoms_of_structure(root)
+            .ok_or_else(not_found)?
+        {
+            let atom = &workspace.graph[index];
+            if atom.element == Element::Any {
+                return Err(format!("Atom {:?} is a wildcard", index));
+            }
+            let hydrogens = workspace.implicit_hydrogen_count(index).unwrap_or_default();
+            electrons += (atom.element.atomic_number() + hydrogens) as isize - atom.charge;
+            unpaired += atom.radical_electrons;
+        }
+    }
+    multiplicity_of(electrons, unpaired)
+}
+
+/// Write a Gaussian input. Frozen atoms get `-1` in the freeze column,
+/// fragments are written as `C(Fragment=1)` with charge and multiplicity of
+/// each fragment, and basis overrides use `Gen` with a basis section.
+pub fn to_gaussian(
+    workspace: &Workspace,
+    node: NodeIndex,
+    settings: &QcSettings,
+) -> Result<String, String> {
+    let atoms = collect_atoms(workspace, node)?;
+    let has_overrides = atoms.iter().any(|atom| atom.basis.is_some());
+    let has_frozen = atoms.iter().any(|atom| atom.frozen);
+    let mut input = String::new();
+    let basis = if has_overrides {
+        "Gen"
+    } else {
+        &settings.basis
+    };
+    let mut route = format!("#p {}/{}", settings.method, basis);
+    for keyword in settings.keywords.iter() {
+        route.push(' ');
+        route.push_str(keyword);
+    }
+    let title = if settings.title.is_empty() {
+        "SwS"
+    } else {
+        &settings.title
+    };
+    input.push_str(&format!("{}\n\n{}\n\n", route, title));
+    // of all atoms, then of each fragment
+    let mut states = vec![charge_and_multiplicity(atoms.iter())?];
+    for (_, members) in fragments(&atoms) {
+        states.push(charge_and_multiplicity(
+            members.iter().map(|index| &atoms[*index]),
+        )?);
+    }
+    for (index, (charge, multiplicity)) in states.iter().enumerate() {
+        if index > 0 {
+            input.push(' ');
+        }
+        input.push_str(&format!("{} {}", charge, multiplicity));
+    }
+    input.push('\n');
+    for atom in atoms.iter() {
+        let mut label = atom.symbol.clone();
+        if let Some(fragment) = atom.fragment {
+            label.push_str(&format!("(Fragment={})", fragment));
+        }
+        let freeze = match (has_frozen, atom.frozen) {
+            (false, _) => String::new(),
+            (true, true) => " -1".to_string(),
+            (true, false) => "  0".to_string(),
+        };
+        let [x, y, z] = atom.position;
+        input.push_str(&format!(
+            "{:<16}{} {:>12.6} {:>12.6} {:>12.6}\n",
+            label, freeze, x, y, z
+        ));
+    }
+    input.push('\n');
+    if has_overrides {
+        for (basis, centers) in group_by_basis(&atoms, &settings.basis) {
+            input.push_str(&format!("{} 0\n{}\n****\n", centers.join(" "), basis));
+        }
+        input.push('\n');
+    }
+    Ok(input)
+}
+
+/// Write an ORCA input. Frozen atoms are cartesian constraints, fragments
+/// are written as `C(1)` and basis overrides as `NewGTO` of atoms.
+pub fn to_orca(
+    workspace: &Workspace,
+    node: NodeIndex,
+    settings: &QcSettings,
+) -> Result<String, String> {
+    let atoms = collect_atoms(workspace, node)?;
+    let mut input = format!("! {} {}", settings.method, settings.basis);
+    for keyword in settings.keywords.iter() {
+        input.push(' ');
+        input.push_str(keyword);
+    }
+    input.push('\n');
+    if !settings.title.is_empty() {
+        input.push_str(&format!("# {}\n", settings.title));
+    }
+    let frozen = atoms
+        .iter()
+        .enumerate()
+        .filter(|(_, atom)| atom.frozen)
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    if !frozen.is_empty() {
+        input.push_str("%geom\n  Constraints\n");
+        for index in frozen {
+            input.push_str(&format!("    {{ C {} C }}\n", index));
+        }
+        input.push_str("  end\nend\n");
+    }
+    let (charge, multiplicity) = charge_and_multiplicity(atoms.iter())?;
+    input.push_str(&format!("* xyz {} {}\n", charge, multiplicity));
+    for atom in atoms.iter() {
+        let mut label = atom.symbol.clone();
+        if let Some(fragment) = atom.fragment {
+            label.push_str(&format!("({})", fragment));
+        }
+        let [x, y, z] = atom.position;
+        input.push_str(&format!("{:<8} {:>12.6} {:>12.6} {:>12.6}", label, x, y, z));
+        if let Some(basis) = &atom.basis {
+            input.push_str(&format!(" NewGTO \"{}\" end", basis));
+        }
+        input.push('\n');
+    }
+    input.push_str("*\n");
+    Ok(input)
+}
+
+/// Input files of xTB
+#[derive(Debug, Clone, PartialEq)]
+pub struct XtbInput {
+    /// coordinates as XYZ
+    pub xyz: String,
+    /// detailed input given by `--input`, with charge, unpaired electrons,
+    /// frozen atoms and fragments
+    pub xcontrol: String,
+}
+
+/// Write xTB inputs, basis overrides are ignored.
+pub fn to_xtb(
+    workspace: &Workspace,
+    node: NodeIndex,
+    settings: &QcSettings,
+) -> Result<XtbInput, String> {
+    let atoms = collect_atoms(workspace, node)?;
+    let mut xyz = format!("{}\n{}\n", atoms.len(), settings.title);
+    for atom in atoms.iter() {
+        let [x, y, z] = atom.position;
+        xyz.push_str(&format!(
+            "{:<2} {:>12.6} {:>12.6} {:>12.6}\n",
+            atom.symbol, x, y, z
+        ));
+    }
+    let (charge, multiplicity) = charge_and_multiplicity(atoms.iter())?;
+    let mut xcontrol = format!("$chrg {}\n$spin {}\n", charge, multiplicity - 1);
+    if let Some(level) = settings
+        .method
+        .to_ascii_uppercase()
+        .strip_prefix("GFN")
+        .and_then(|level| level.strip_suffix("-XTB").or(Some(level)))
+        .filter(|level| !level.is_empty())
+    {
+        xcontrol.push_str(&format!("$gfn\n   method={}\n", level.to_lowercase()));
+    }
+    let frozen = atoms
+        .iter()
+        .enumerate()
+        .filter(|(_, atom)| atom.frozen)
+        .map(|(index, _)| (index + 1).to_string())
+        .collect::<Vec<_>>();
+    if !frozen.is_empty() {
+        xcontrol.push_str(&format!("$fix\n   atoms: {}\n", frozen.join(",")));
+    }
+    let fragments = fragments(&atoms);
+    if !fragments.is_empty() {
+        xcontrol.push_str("$split\n");
+        for (fragment, members) in fragments {
+            let members = members
+                .iter()
+                .map(|index| (index + 1).to_string())
+                .collect::<Vec<_>>();
+            xcontrol.push_str(&format!(
+                "   fragment: {},{}\n",
+                fragment,
+                members.join(",")
+            ));
+        }
+    }
+    xcontrol.push_str("$end\n");
+    Ok(XtbInput { xyz, xcontrol })
+}
+
+/// Collect atoms with their coordinates and directives. Atoms shall have
+/// coordinates and hydrogens shall be atoms, so the electrons are counted
+/// right.
+fn collect_atoms(workspace: &Workspace, node: NodeIndex) -> Result<Vec<QcAtom>, String> {
+    let mut atoms = vec![];
+    let positions = atoms_with_coordinates(workspace, node)
+        .ok_or("Some atoms have no coordinates".to_string())?;
+    for (_, node, position) in positions {
+        let atom = workspace
+            .get_atom(node)
+            .ok_or(format!("Atom {:?} not found", node))?;
+        if atom.element == Element::Any {
+            return Err(format!("Atom {:?} is a wildcard", node));
+        }
+        if workspace.implicit_hydrogen_count(node).unwrap_or_default() != 0 {
+            return Err(format!(
+                "Atom {:?} has implicit hydrogens, add them as atoms first",
+                node
+            ));
+        }
+        let mut qc_atom = QcAtom {
+            symbol: atom.element.as_ref().to_string(),
+            atomic_number: atom.element.atomic_number(),
+            position,
+            charge: atom.charge,
+            radical_electrons: atom.radical_electrons,
+            frozen: false,
+            fragment: None,
+            basis: None,
+        };
+        for (name, argument) in directives(atom.selector.as_deref().unwrap_or_default()) {
+            match (name, argument) {
+                ("Freeze", _) => qc_atom.frozen = true,
+                ("Fragment", Some(argument)) => {
+                    qc_atom.fragment = Some(
+                        argument
+                            .trim()
+                            .parse()
+                            .map_err(|_| format!("Invalid fragment {}", argument))?,
+                    )
+                }
+                ("Basis", Some(argument)) => qc_atom.basis = Some(argument.trim().to_string()),
+                _ => {}
+            }
+        }
+        atoms.push(qc_atom);
+    }
+    Ok(atoms)
+}
+
+/// Split a selector into directives like `Name(argument)`, they're
+/// separated by `,` or `;` out of parentheses.
+fn directives(selector: &str) -> Vec<(&str, Option<&str>)> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, char) in selector.char_indices() {
+        match char {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' | ';' if depth == 0 => {
+                parts.push(&selector[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&selector[start..]);
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| match part.split_once('(') {
+            Some((name, rest)) => (name.trim(), rest.strip_suffix(')')),
+            None => (part, None),
+        })
+        .collect()
+}
+
+/// Atoms of each fragment in order of fragment numbers
+fn fragments(atoms: &[QcAtom]) -> Vec<(usize, Vec<usize>)> {
+    let mut fragments: Vec<(usize, Vec<usize>)> = vec![];
+    for (index, atom) in atoms.iter().enumerate() {
+        if let Some(fragment) = atom.fragment {
+            match fragments.iter_mut().find(|(number, _)| *number == fragment) {
+                Some((_, members)) => members.push(index),
+                None => fragments.push((fragment, vec![index])),
+            }
+        }
+    }
+    fragments.sort_by_key(|(number, _)| *number);
+    fragments
+}
+
+/// Total charge and spin multiplicity of atoms
+fn charge_and_multiplicity<'a>(
+    atoms: impl Iterator<Item = &'a QcAtom>,
+) -> Result<(isize, usize), String> {
+    let (charge, electrons, unpaired) =
+        atoms.fold((0, 0, 0), |(charge, electrons, unpaired), atom| {
+            (
+                charge + atom.charge,
+                electrons + atom.atomic_number as isize - atom.charge,
+                unpaired + atom.radical_electrons,
+            )
+        });
+    Ok((charge, multiplicity_of(electrons, unpaired)?))
+}
+
+/// Multiplicity of unpaired electrons, which shall be as many as electrons
+/// in parity.
+fn multiplicity_of(electrons: isize, unpaired: usize) -> Result<usize, String> {
+    if electrons < unpaired as isize || (electrons - unpaired as isize) % 2 != 0 {
+        return Err(format!(
+            "{} electrons can't have {} unpaired ones, radicals or hydrogens are missing",
+            electrons, unpaired
+        ));
+    }
+    Ok(unpaired + 1)
+}
+
+/// Gaussian centers of each basis, the default basis goes first
+fn group_by_basis(atoms: &[QcAtom], default: &str) -> Vec<(String, Vec<String>)> {
+    let mut groups: Vec<(String, Vec<String>)> = vec![(default.to_string(), vec![])];
+    for (index, atom) in atoms.iter().enumerate() {
+        let basis = atom.basis.as_deref().unwrap_or(default);
+        let center = (index + 1).to_string();
+        match groups.iter_mut().find(|(name, _)| name == basis) {
+            Some((_, centers)) => centers.push(center),
+            None => groups.push((basis.to_string(), vec![center])),
+        }
+    }
+    groups.retain(|(_, centers)| !centers.is_empty());
+    groups
+}
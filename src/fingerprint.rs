@@ -0,0 +1,541 @@
+pub mod index;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use petgraph::{
+    stable_graph::{EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+    Direction::{Incoming, Outgoing},
+};
+
+use crate::{
+    definitions::{bond::BondType, element::Element},
+    workspace::Workspace,
+};
+
+/// Which features are hashed into a fingerprint
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FingerprintKind {
+    /// environments of atoms up to `radius` bonds, like ECFP4 (or FCFP4)
+    /// with radius 2
+    Circular { radius: usize },
+    /// linear paths of 1 to `max_length` bonds
+    Path { max_length: usize },
+}
+
+/// Options of fingerprints
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FingerprintOptions {
+    pub kind: FingerprintKind,
+    /// number of bits that features are folded into
+    pub size: usize,
+    /// describe atoms by pharmacophoric features (donor, acceptor, aromatic,
+    /// halogen, basic and acidic) like FCFP, instead of element, degree,
+    /// charge and so on
+    pub features: bool,
+    /// atoms with selectors differ from the same atoms without them
+    pub selectors: bool,
+}
+
+impl Default for FingerprintOptions {
+    fn default() -> Self {
+        Self {
+            kind: FingerprintKind::Circular { radius: 2 },
+            size: 2048,
+            features: false,
+            selectors: false,
+        }
+    }
+}
+
+/// A fingerprint as a bit vector
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BitFingerprint {
+    size: usize,
+    words: Vec<u64>,
+}
+
+impl BitFingerprint {
+    /// create a fingerprint with all bits unset
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            words: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub fn set(&mut self, bit: usize) {
+        if bit < self.size {
+            self.words[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    pub fn get(&self, bit: usize) -> bool {
+        bit < self.size && self.words[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    /// Iterate over bits set
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.size).filter(|bit| self.get(*bit))
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Count bits set in both fingerprints, they should have the same size
+    pub fn common_ones(&self, other: &Self) -> usize {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
+    }
+
+    /// Tanimoto (Jaccard) similarity, 0 if both are empty. None if they
+    /// have different sizes.
+    pub fn tanimoto(&self, other: &Self) -> Option<f64> {
+        if self.size != other.size {
+            return None;
+        }
+        let common = self.common_ones(other);
+        let union = self.count_ones() + other.count_ones() - common;
+        if union == 0 {
+            Some(0.)
+        } else {
+            Some(common as f64 / union as f64)
+        }
+    }
+
+    /// Dice similarity, 0 if both are empty. None if they have different
+    /// sizes.
+    pub fn dice(&self, other: &Self) -> Option<f64> {
+        if self.size != other.size {
+            return None;
+        }
+        let total = self.count_ones() + other.count_ones();
+        if total == 0 {
+            Some(0.)
+        } else {
+            Some(2. * self.common_ones(other) as f64 / total as f64)
+        }
+    }
+}
+
+/// A fingerprint as counts of features, features are not folded
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CountFingerprint {
+    counts: BTreeMap<u32, u32>,
+}
+
+impl CountFingerprint {
+    /// counts of features by their hashes
+    pub fn counts(&self) -> &BTreeMap<u32, u32> {
+        &self.counts
+    }
+
+    /// Fold features into a count vector of the given size
+    pub fn to_vector(&self, size: usize) -> Vec<u32> {
+        let mut vector = vec![0; size];
+        if size > 0 {
+            for (feature, count) in self.counts.iter() {
+                vector[*feature as usize % size] += count;
+            }
+        }
+        vector
+    }
+
+    /// Fold features into a bit vector of the given size
+    pub fn to_bits(&self, size: usize) -> BitFingerprint {
+        let mut bits = BitFingerprint::new(size);
+        if size > 0 {
+            for feature in self.counts.keys() {
+                bits.set(*feature as usize % size);
+            }
+        }
+        bits
+    }
+
+    /// Tanimoto similarity of counts, sum of minimums over sum of maximums
+    pub fn tanimoto(&self, other: &Self) -> f64 {
+        let (min, max) = self.min_max(other);
+        if max == 0 {
+            0.
+        } else {
+            min as f64 / max as f64
+        }
+    }
+
+    /// Dice similarity of counts
+    pub fn dice(&self, other: &Self) -> f64 {
+        let (min, _) = self.min_max(other);
+        let total = self
+            .counts
+            .values()
+            .chain(other.counts.values())
+            .sum::<u32>();
+        if total == 0 {
+            0.
+        } else {
+            2. * min as f64 / total as f64
+        }
+    }
+
+    fn min_max(&self, other: &Self) -> (u32, u32) {
+        let features = self
+            .counts
+            .keys()
+            .chain(other.counts.keys())
+            .collect::<BTreeSet<_>>();
+        let mut min = 0;
+        let mut max = 0;
+        for feature in features {
+            let a = self.counts.get(feature).copied().unwrap_or_default();
+            let b = other.counts.get(feature).copied().unwrap_or_default();
+            min += a.min(b);
+            max += a.max(b);
+        }
+        (min, max)
+    }
+}
+
+/// Fingerprint of the structure which the given atom belongs to, folded
+/// into `options.size` bits.
+pub fn fingerprint(
+    workspace: &Workspace,
+    node: NodeIndex,
+    options: &FingerprintOptions,
+) -> Option<BitFingerprint> {
+    Some(count_fingerprint(workspace, node, options)?.to_bits(options.size))
+}
+
+/// Features of the structure which the given atom belongs to with their
+/// counts. Hydrogen atoms are counted by the atoms they're bonded to.
+pub fn count_fingerprint(
+    workspace: &Workspace,
+    node: NodeIndex,
+    options: &FingerprintOptions,
+) -> Option<CountFingerprint> {
+    let graph = HeavyAtoms::new(workspace, node, options)?;
+    let features = match options.kind {
+        FingerprintKind::Circular { radius } => graph.circular(radius),
+        FingerprintKind::Path { max_length } => graph.paths(max_length),
+    };
+    let mut counts = BTreeMap::new();
+    for feature in features {
+        *counts.entry(feature).or_default() += 1;
+    }
+    Some(CountFingerprint { counts })
+}
+
+/// Heavy atoms of a structure with their invariants and bonds
+struct HeavyAtoms {
+    invariants: HashMap<NodeIndex, u32>,
+    /// neighbors with bond codes and bonds
+    neighbors: HashMap<NodeIndex, Vec<(NodeIndex, u32, EdgeIndex)>>,
+    atoms: Vec<NodeIndex>,
+}
+
+impl HeavyAtoms {
+    fn new(workspace: &Workspace, node: NodeIndex, options: &FingerprintOptions) -> Option<Self> {
+        let mut atoms = vec![];
+        for root in workspace.components(node)? {
+            atoms.extend(
+                workspace
+                    .get_atoms_of_structure(root)?
+                    .into_iter()
+                    .filter(|atom| workspace.graph[*atom].element != Element::H),
+            );
+        }
+        let in_ring = workspace
+            .rings(node)?
+            .into_iter()
+            .flatten()
+            .collect::<HashSet<_>>();
+        let mut neighbors: HashMap<NodeIndex, Vec<(NodeIndex, u32, EdgeIndex)>> = HashMap::new();
+        for atom in atoms.iter() {
+            for edge in workspace
+                .graph
+                .edges_directed(*atom, Incoming)
+                .chain(workspace.graph.edges_directed(*atom, Outgoing))
+            {
+                let other = if edge.source() == *atom {
+                    edge.target()
+                } else {
+                    edge.source()
+                };
+                if workspace.graph[other].element == Element::H || edge.weight().is_no_bond() {
+                    continue;
+                }
+                neighbors.entry(*atom).or_default().push((
+                    other,
+                    bond_code(edge.weight().bond_type()),
+                    edge.id(),
+                ));
+            }
+        }
+        let invariants = atoms
+            .iter()
+            .map(|atom| {
+                let invariant = if options.features {
+                    feature_invariant(workspace, *atom)
+                } else {
+                    atom_invariant(workspace, *atom, in_ring.contains(atom))
+                };
+                let selector = options.selectors && workspace.graph[*atom].selector.is_some();
+                (*atom, hash(&[invariant, selector as u64]))
+            })
+            .collect();
+        Some(Self {
+            invariants,
+            neighbors,
+            atoms,
+        })
+    }
+
+    fn neighbors_of(&self, atom: NodeIndex) -> &[(NodeIndex, u32, EdgeIndex)] {
+        self.neighbors
+            .get(&atom)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Morgan algorithm, an environment covering the same bonds as an
+    /// earlier one, or no more bonds than the last iteration, is skipped.
+    fn circular(&self, radius: usize) -> Vec<u32> {
+        let mut identifiers = self.invariants.clone();
+        let mut features = self
+            .atoms
+            .iter()
+            .map(|atom| identifiers[atom])
+            .collect::<Vec<_>>();
+        let mut environments = self
+            .atoms
+            .iter()
+            .map(|atom| (*atom, HashSet::new()))
+            .collect::<HashMap<_, HashSet<EdgeIndex>>>();
+        let mut seen = HashSet::new();
+        for iteration in 1..=radius {
+            let mut next_identifiers = HashMap::new();
+            let mut next_environments = HashMap::new();
+            let mut candidates = vec![];
+            for atom in self.atoms.iter() {
+                let mut environment = environments[atom].clone();
+                let mut around = vec![];
+                for (neighbor, bond, edge) in self.neighbors_of(*atom) {
+                    around.push((*bond, identifiers[neighbor]));
+                    environment.insert(*edge);
+                    environment.extend(environments[neighbor].iter().copied());
+                }
+                around.sort();
+                let mut values = vec![iteration as u64, identifiers[atom] as u64];
+                for (bond, identifier) in around {
+                    values.push(bond as u64);
+                    values.push(identifier as u64);
+                }
+                let identifier = hash(&values);
+                if environment.len() > environments[atom].len() {
+                    let mut bonds = environment.iter().copied().collect::<Vec<_>>();
+                    bonds.sort();
+                    candidates.push((bonds, identifier));
+                }
+                next_identifiers.insert(*atom, identifier);
+                next_environments.insert(*atom, environment);
+            }
+            // the smallest identifier is kept for the same bonds, so it
+            // doesn't depend on the order of atoms
+            candidates.sort();
+            for (bonds, identifier) in candidates {
+                if seen.insert(bonds) {
+                    features.push(identifier);
+                }
+            }
+            identifiers = next_identifiers;
+            environments = next_environments;
+        }
+        features
+    }
+
+    /// Linear paths, a path is hashed the same from both of its ends.
+    fn paths(&self, max_length: usize) -> Vec<u32> {
+        let mut features = vec![];
+        let mut seen = HashSet::new();
+        for start in self.atoms.iter() {
+            // stack of paths by atoms and bonds
+            let mut stack = vec![(vec![*start], vec![])];
+            while let Some((atoms, bonds)) = stack.pop() {
+                if !bonds.is_empty() {
+                    let mut key = bonds.clone();
+                    key.sort();
+                    if seen.insert(key) {
+                        features.push(self.path_hash(&atoms, &bonds));
+                    }
+                }
+                if bonds.len() == max_length {
+                    continue;
+                }
+                let last = atoms[atoms.len() - 1];
+                for (neighbor, _, edge) in self.neighbors_of(last) {
+                    if !atoms.contains(neighbor) {
+                        let mut atoms = atoms.clone();
+                        let mut bonds = bonds.clone();
+                        atoms.push(*neighbor);
+                        bonds.push(*edge);
+                        stack.push((atoms, bonds));
+                    }
+                }
+            }
+        }
+        features
+    }
+
+    fn path_hash(&self, atoms: &[NodeIndex], bonds: &[EdgeIndex]) -> u32 {
+        let codes = bonds
+            .iter()
+            .zip(atoms.iter())
+            .map(|(edge, atom)| {
+                self.neighbors_of(*atom)
+                    .iter()
+                    .find(|(_, _, other)| other == edge)
+                    .map(|(_, bond, _)| *bond)
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let mut forward = vec![];
+        for (index, atom) in atoms.iter().enumerate() {
+            forward.push(self.invariants[atom] as u64);
+            if let Some(code) = codes.get(index) {
+                forward.push(*code as u64);
+            }
+        }
+        let backward = forward.iter().rev().copied().collect::<Vec<_>>();
+        hash(&forward.min(backward))
+    }
+}
+
+fn bond_code(bond_type: BondType) -> u32 {
+    match bond_type {
+        BondType::Double => 2,
+        BondType::Triple => 3,
+        BondType::Quad => 4,
+        BondType::Aromatic => 5,
+        _ => 1,
+    }
+}
+
+/// Daylight-like atom invariant: element, heavy degree, valence, hydrogens,
+/// charge, isotope, aromaticity and ring membership.
+fn atom_invariant(workspace: &Workspace, atom: NodeIndex, in_ring: bool) -> u64 {
+    let properties = &workspace.graph[atom];
+    let (degree, bond_orders, hydrogens) = neighbor_counts(workspace, atom);
+    hash(&[
+        properties.element as u64,
+        degree as u64,
+        (bond_orders + hydrogens) as u64,
+        hydrogens as u64,
+        properties.charge as u64,
+        properties.isotope.unwrap_or_default() as u64,
+        properties.aromatic as u64,
+        in_ring as u64,
+    ]) as u64
+}
+
+/// FCFP-like atom invariant by pharmacophoric features
+fn feature_invariant(workspace: &Workspace, atom: NodeIndex) -> u64 {
+    let properties = &workspace.graph[atom];
+    let (degree, bond_orders, hydrogens) = neighbor_counts(workspace, atom);
+    let heteroatom = matches!(properties.element, Element::N | Element::O);
+    let donor = heteroatom && hydrogens > 0;
+    let acceptor = heteroatom && properties.charge <= 0;
+    let halogen = matches!(
+        properties.element,
+        Element::F | Element::Cl | Element::Br | Element::I
+    );
+    // nitrogens next to carbonyls (amides) are not basic, hydroxyls next to
+    // them (acids) are acidic
+    let carbonyl = next_to_carbonyl(workspace, atom);
+    let basic = properties.charge > 0
+        || properties.element == Element::N
+            && !properties.aromatic
+            && bond_orders == degree
+            && !carbonyl;
+    let acidic =
+        properties.charge < 0 || properties.element == Element::O && hydrogens == 1 && carbonyl;
+    [donor, acceptor, properties.aromatic, halogen, basic, acidic]
+        .iter()
+        .enumerate()
+        .map(|(index, feature)| (*feature as u64) << index)
+        .sum()
+}
+
+/// Heavy neighbors, sum of bond orders to them and hydrogens of the atom
+fn neighbor_counts(workspace: &Workspace, atom: NodeIndex) -> (usize, usize, usize) {
+    let mut degree = 0;
+    let mut bond_orders = 0;
+    for edge in workspace
+        .graph
+        .edges_directed(atom, Incoming)
+        .chain(workspace.graph.edges_directed(atom, Outgoing))
+    {
+        let other = if edge.source() == atom {
+            edge.target()
+        } else {
+            edge.source()
+        };
+        if workspace.graph[other].element != Element::H {
+            degree += 1;
+            bond_orders += edge.weight().bond_type().valence();
+        }
+    }
+    let hydrogens = workspace.implicit_hydrogen_count(atom).unwrap_or_default()
+        + hydrogens_as_atoms(workspace, atom);
+    (degree, bond_orders, hydrogens)
+}
+
+/// If a neighbor of the atom has a double bond to oxygen
+fn next_to_carbonyl(workspace: &Workspace, atom: NodeIndex) -> bool {
+    workspace.graph.neighbors_undirected(atom).any(|neighbor| {
+        workspace.graph.neighbors_undirected(neighbor).any(|other| {
+            other != atom
+                && workspace.graph[other].element == Element::O
+                && workspace
+                    .get_edge_undirected(neighbor, other)
+                    .is_some_and(|(bond, _, _)| bond.bond_type() == BondType::Double)
+        })
+    })
+}
+
+fn hydrogens_as_atoms(workspace: &Workspace, atom: NodeIndex) -> usize {
+    workspace
+        .graph
+        .neighbors_undirected(atom)
+        .filter(|neighbor| workspace.graph[*neighbor].element == Element::H)
+        .count()
+}
+
+/// FNV-1a of values, fingerprints stay the same between runs and platforms
+fn hash(values: &[u64]) -> u32 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for value in values {
+        for byte in value.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    (hash ^ (hash >> 32)) as u32
+}
@@ -0,0 +1,267 @@
+use std::{
+    fmt::Display,
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+use crate::workspace::{structure::StructureId, Workspace};
+
+use super::{fingerprint, BitFingerprint, FingerprintOptions};
+
+/// Fingerprints with the same count of bits set, stored one after another
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Bin<Id> {
+    ids: Vec<Id>,
+    words: Vec<u64>,
+}
+
+/// An in-memory index of bit fingerprints for Tanimoto searches.
+///
+/// Fingerprints are binned by their counts of bits set, Tanimoto of
+/// fingerprints with `a` and `b` bits set is at most `min(a, b) / max(a, b)`,
+/// so bins which can't reach the threshold (or the k-th best hit) are never
+/// scanned. An index of ids which can be written and parsed, like names or
+/// numbers, is saved and loaded by `save` and `load`. With the `serde`
+/// feature any index can be serialized.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimilarityIndex<Id = StructureId> {
+    size: usize,
+    /// bins by counts of bits set, from 0 to `size`
+    bins: Vec<Bin<Id>>,
+    len: usize,
+}
+
+impl<Id: Clone> SimilarityIndex<Id> {
+    /// create an empty index of fingerprints of the given size
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            bins: (0..=size)
+                .map(|_| Bin {
+                    ids: vec![],
+                    words: vec![],
+                })
+                .collect(),
+            len: 0,
+        }
+    }
+
+    /// size of fingerprints in the index
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Add a fingerprint, it must have the same size as the index
+    pub fn insert(&mut self, id: Id, fingerprint: &BitFingerprint) -> Result<(), String> {
+        if fingerprint.size() != self.size {
+            return Err(format!(
+                "Fingerprint of {} bits can't be added into an index of {} bits",
+                fingerprint.size(),
+                self.size
+            ));
+        }
+        let bin = &mut self.bins[fingerprint.count_ones()];
+        bin.ids.push(id);
+        bin.words.extend_from_slice(fingerprint.words());
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Iterate over ids and fingerprints in the index, ordered by counts of
+    /// bits set.
+    pub fn iter(&self) -> impl Iterator<Item = (&Id, BitFingerprint)> + '_ {
+        let words = self.words_per_fingerprint();
+        self.bins.iter().flat_map(move |bin| {
+            bin.ids.iter().enumerate().map(move |(index, id)| {
+                let mut fingerprint = BitFingerprint::new(self.size);
+                fingerprint
+                    .words
+                    .copy_from_slice(&bin.words[index * words..(index + 1) * words]);
+                (id, fingerprint)
+            })
+        })
+    }
+
+    /// Find fingerprints with Tanimoto similarity to the query not less than
+    /// `threshold`, the most similar ones first. None if the query has a
+    /// different size.
+    pub fn threshold(&self, query: &BitFingerprint, threshold: f64) -> Option<Vec<(Id, f64)>> {
+        if query.size() != self.size {
+            return None;
+        }
+        let ones = query.count_ones();
+        let mut hits = vec![];
+        for (count, bin) in self.bins.iter().enumerate() {
+            if bound(ones, count) >= threshold {
+                self.scan(query, ones, count, bin, &mut hits, threshold);
+            }
+        }
+        sort_hits(&mut hits);
+        Some(hits)
+    }
+
+    /// Find `k` fingerprints most similar to the query by Tanimoto, the most
+    /// similar ones first. None if the query has a different size.
+    pub fn top_k(&self, query: &BitFingerprint, k: usize) -> Option<Vec<(Id, f64)>> {
+        if query.size() != self.size {
+            return None;
+        }
+        let ones = query.count_ones();
+        let mut counts = (0..=self.size)
+            .filter(|count| !self.bins[*count].ids.is_empty())
+            .collect::<Vec<_>>();
+        // bins which may be more similar are scanned first
+        counts.sort_by(|a, b| bound(ones, *b).total_cmp(&bound(ones, *a)));
+        let mut hits = vec![];
+        for count in counts {
+            let full = hits.len() >= k;
+            if full
+                && hits
+                    .last()
+                    .is_none_or(|(_, worst)| bound(ones, count) < *worst)
+            {
+                break;
+            }
+            self.scan(query, ones, count, &self.bins[count], &mut hits, 0.);
+            sort_hits(&mut hits);
+            hits.truncate(k);
+        }
+        Some(hits)
+    }
+
+    fn words_per_fingerprint(&self) -> usize {
+        self.size.div_ceil(64)
+    }
+
+    /// Push fingerprints in the bin not less similar than `threshold`
+    fn scan(
+        &self,
+        query: &BitFingerprint,
+        ones: usize,
+        count: usize,
+        bin: &Bin<Id>,
+        hits: &mut Vec<(Id, f64)>,
+        threshold: f64,
+    ) {
+        let words = self.words_per_fingerprint();
+        for (index, id) in bin.ids.iter().enumerate() {
+            let common = bin.words[index * words..(index + 1) * words]
+                .iter()
+                .zip(query.words())
+                .map(|(a, b)| (a & b).count_ones() as usize)
+                .sum::<usize>();
+            let union = ones + count - common;
+            let score = if union == 0 {
+                0.
+            } else {
+                common as f64 / union as f64
+            };
+            if score >= threshold {
+                hits.push((id.clone(), score));
+            }
+        }
+    }
+}
+
+/// Header of a saved index, followed by the size of fingerprints
+const HEADER: &str = "SwS similarity index";
+
+impl<Id: Clone + Display> SimilarityIndex<Id> {
+    /// Save the index as text. The first line is `SwS similarity index` and
+    /// the size, then each fingerprint is a line of its id, a tab, and its
+    /// words as 16 hex digits each, the lowest bits first. Ids shall have no
+    /// tabs or line breaks.
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{} {}", HEADER, self.size)?;
+        for (id, fingerprint) in self.iter() {
+            let id = id.to_string();
+            if id.contains(['\t', '\n', '\r']) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Id {:?} can't be saved", id),
+                ));
+            }
+            let words = fingerprint
+                .words()
+                .iter()
+                .map(|word| format!("{:016x}", word))
+                .collect::<String>();
+            writeln!(writer, "{}\t{}", id, words)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Id: Clone + FromStr> SimilarityIndex<Id> {
+    /// Load an index saved by `save`
+    pub fn load<R: BufRead>(reader: R) -> Result<Self, String> {
+        let mut lines = reader.lines();
+        let header = lines
+            .next()
+            .ok_or("Index is empty")?
+            .map_err(|err| err.to_string())?;
+        let size = header
+            .strip_prefix(HEADER)
+            .and_then(|size| size.trim().parse().ok())
+            .ok_or(format!("Invalid header {}", header))?;
+        let mut index = Self::new(size);
+        let words = index.words_per_fingerprint();
+        for (number, line) in lines.enumerate() {
+            let line = line.map_err(|err| err.to_string())?;
+            let invalid = || format!("line {}: invalid fingerprint {}", number + 2, line);
+            let (id, hex) = line.split_once('\t').ok_or_else(invalid)?;
+            let id = id.parse().map_err(|_| invalid())?;
+            if hex.len() != words * 16 || !hex.is_ascii() {
+                return Err(invalid());
+            }
+            let mut fingerprint = BitFingerprint::new(size);
+            for (word, digits) in fingerprint.words.iter_mut().zip(hex.as_bytes().chunks(16)) {
+                let digits = std::str::from_utf8(digits).map_err(|_| invalid())?;
+                *word = u64::from_str_radix(digits, 16).map_err(|_| invalid())?;
+            }
+            // bits beyond the size
+            if fingerprint.ones().count() != fingerprint.count_ones() {
+                return Err(invalid());
+            }
+            index.insert(id, &fingerprint)?;
+        }
+        Ok(index)
+    }
+}
+
+impl SimilarityIndex<StructureId> {
+    /// Index fingerprints of all structures in the workspace
+    pub fn from_workspace(workspace: &Workspace, options: &FingerprintOptions) -> Option<Self> {
+        let mut index = Self::new(options.size);
+        for id in workspace.structures() {
+            let root = workspace.structure_root(id)?;
+            index
+                .insert(id, &fingerprint(workspace, root, options)?)
+                .ok()?;
+        }
+        Some(index)
+    }
+}
+
+/// The highest Tanimoto of fingerprints with `a` and `b` bits set
+fn bound(a: usize, b: usize) -> f64 {
+    if a.max(b) == 0 {
+        0.
+    } else {
+        a.min(b) as f64 / a.max(b) as f64
+    }
+}
+
+fn sort_hits<Id>(hits: &mut [(Id, f64)]) {
+    hits.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+}
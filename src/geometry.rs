@@ -0,0 +1,83 @@
+//! Vector helpers shared by 2D layout, depiction and 3D embedding.
+
+/// A point or a vector in the plane
+pub(crate) type Point = (f64, f64);
+
+pub(crate) fn add(a: Point, b: Point) -> Point {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+pub(crate) fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+pub(crate) fn scale(a: Point, factor: f64) -> Point {
+    (a.0 * factor, a.1 * factor)
+}
+
+pub(crate) fn distance(a: Point, b: Point) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+pub(crate) fn angle(a: Point) -> f64 {
+    a.1.atan2(a.0)
+}
+
+/// The vector of length 1 in the same direction, None for a zero vector
+pub(crate) fn unit(a: Point) -> Option<Point> {
+    let length = a.0.hypot(a.1);
+    (length > 1e-6).then(|| scale(a, 1. / length))
+}
+
+/// Unit normal of the line from `from` to `to`, turned anticlockwise
+pub(crate) fn normal(from: Point, to: Point) -> Point {
+    unit((from.1 - to.1, to.0 - from.0)).unwrap_or((0., 1.))
+}
+
+/// None if there are no points
+pub(crate) fn centroid(points: &[Point]) -> Option<Point> {
+    if points.is_empty() {
+        None
+    } else {
+        let sum = points.iter().copied().fold((0., 0.), add);
+        Some(scale(sum, 1. / points.len() as f64))
+    }
+}
+
+/// Vectors in space
+pub(crate) mod space {
+    pub(crate) type Vector = [f64; 3];
+
+    pub(crate) fn sub(a: Vector, b: Vector) -> Vector {
+        [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+    }
+
+    pub(crate) fn dot(a: Vector, b: Vector) -> f64 {
+        a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+    }
+
+    pub(crate) fn norm(a: Vector) -> f64 {
+        (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt()
+    }
+
+    /// Signed volume of the neighbors of a stereo center, given by vectors
+    /// from the center in the order `@` and `@@` refer to. An implicit
+    /// hydrogen (None) is on the opposite side of the others. Looking from
+    /// the first neighbor, the others are anticlockwise (`@`) when it's
+    /// negative.
+    pub(crate) fn signed_volume(vectors: &[Option<Vector>]) -> f64 {
+        let sum = vectors.iter().flatten().fold([0.; 3], |sum, vector| {
+            [sum[0] + vector[0], sum[1] + vector[1], sum[2] + vector[2]]
+        });
+        let vectors = vectors
+            .iter()
+            .map(|vector| vector.unwrap_or([-sum[0], -sum[1], -sum[2]]))
+            .collect::<Vec<_>>();
+        if vectors.len() != 4 {
+            return 0.;
+        }
+        let [a, b, c] = [1, 2, 3].map(|index| sub(vectors[index], vectors[0]));
+        a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+            + a[2] * (b[0] * c[1] - b[1] * c[0])
+    }
+}
@@ -1,4 +1,12 @@
 mod tokenizer;
+mod geometry;
 pub mod definitions;
 pub mod workspace;
+pub mod molecule;
+pub mod batch;
+pub mod sdf;
+pub mod depict;
+pub mod export;
+pub mod fingerprint;
+pub mod cluster;
 pub use petgraph;
@@ -0,0 +1,370 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use petgraph::{stable_graph::NodeIndex, visit::EdgeRef};
+
+use crate::{
+    definitions::{
+        atom::Atom,
+        bond::Bond,
+        chirality::{ChiralityType, StereoGroup},
+        element::Element,
+    },
+    workspace::{structure::StructureId, Workspace},
+};
+
+thread_local! {
+    /// a workspace reused to parse and write molecules, so the graph is
+    /// allocated only once per thread
+    static SCRATCH: RefCell<Workspace> = RefCell::new(Workspace::new());
+}
+
+/// a selector text of a molecule, atoms with the same text share it.
+/// Texts are kept by the molecule, see `Molecule::selector_text`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Selector(u32);
+
+/// A compact copy of `Atom`, labels and coordinates are kept by `Molecule`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoleculeAtom {
+    pub element: Element,
+    pub isotope: Option<u16>,
+    pub charge: i8,
+    pub chirality_type: Option<ChiralityType>,
+    pub explicit_hydrogen: u8,
+    pub selector: Option<Selector>,
+    pub aromatic: bool,
+    pub react_id: Option<u32>,
+    pub stereo_group: Option<StereoGroup>,
+    pub radical_electrons: u8,
+}
+
+impl MoleculeAtom {
+    /// Compact the atom, returns error if some of its numbers don't fit.
+    fn new(atom: &Atom, selector: Option<Selector>) -> Result<Self, String> {
+        let narrow = |value: usize, name: &str| {
+            u8::try_from(value).map_err(|_| format!("{} {} of {} is too large", name, value, atom))
+        };
+        Ok(Self {
+            element: atom.element,
+            isotope: atom.isotope,
+            charge: i8::try_from(atom.charge)
+                .map_err(|_| format!("Charge {} of {} is too large", atom.charge, atom))?,
+            chirality_type: atom.chirality_type,
+            explicit_hydrogen: narrow(atom.explicit_hydrogen, "Hydrogen count")?,
+            selector,
+            aromatic: atom.aromatic,
+            react_id: atom
+                .react_id
+                .map(|react_id| {
+                    u32::try_from(react_id)
+                        .map_err(|_| format!("Reaction id {} of {} is too large", react_id, atom))
+                })
+                .transpose()?,
+            stereo_group: atom.stereo_group,
+            radical_electrons: narrow(atom.radical_electrons, "Radical electron count")?,
+        })
+    }
+}
+
+/// A read-only structure stored in flat arrays, for parsing and reading a
+/// large amount of SMILES without the editing machinery of `Workspace`.
+///
+/// Atoms are indexed from 0 in the order of the workspace structure they come
+/// from, bonds keep their direction and ring flags so a molecule converts back
+/// to the same trees.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Molecule {
+    atoms: Vec<MoleculeAtom>,
+    bonds: Vec<(u32, u32, Bond)>,
+    /// neighbors of atom `i` are `adjacency[offsets[i]..offsets[i + 1]]`,
+    /// stored as (neighbor, bond)
+    offsets: Vec<u32>,
+    adjacency: Vec<(u32, u32)>,
+    /// roots of components, components are separated by `.` in SMILES
+    roots: Vec<u32>,
+    /// labels and coordinates of atoms having them, sorted by atoms
+    labels: Vec<(u32, String)>,
+    coordinates: Vec<(u32, [f64; 3])>,
+    /// texts of selectors of atoms, indexed by `Selector`
+    selectors: Vec<String>,
+}
+
+impl Molecule {
+    /// Parse a SwS as a molecule
+    pub fn from_sws(sws: &str) -> Result<Self, String> {
+        SCRATCH.with(|scratch| {
+            let mut workspace = scratch.borrow_mut();
+            workspace.clear();
+            let id = workspace.add_structure(sws)?;
+            let root = workspace
+                .structure_root(id)
+                .ok_or(format!("Root of {} not found", sws))?;
+            Self::from_workspace(&workspace, root)
+        })
+    }
+
+    /// Copy the structure which the given atom belongs to, with all its
+    /// components. Returns error if the structure or some numbers of its
+    /// atoms are too large for the compact form.
+    pub fn from_workspace(
+        workspace: &Workspace,
+        structure_node: NodeIndex,
+    ) -> Result<Self, String> {
+        let not_found = || format!("Structure of {:?} not found", structure_node);
+        let roots = workspace.components(structure_node).ok_or_else(not_found)?;
+        let mut nodes = vec![];
+        for root in roots.iter() {
+            nodes.append(
+                &mut workspace
+                    .get_atoms_of_structure(*root)
+                    .ok_or_else(not_found)?,
+            );
+        }
+        if u32::try_from(nodes.len()).is_err() {
+            return Err(format!("{} atoms are too many for a molecule", nodes.len()));
+        }
+        let index_of = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (*node, index as u32))
+            .collect::<HashMap<_, _>>();
+        let mut atoms = Vec::with_capacity(nodes.len());
+        let mut labels = vec![];
+        let mut coordinates = vec![];
+        let mut selectors: Vec<String> = vec![];
+        let mut edges = vec![];
+        for (index, node) in nodes.iter().enumerate() {
+            let atom = &workspace.graph[*node];
+            let selector = atom.selector.as_ref().map(|text| {
+                let position = selectors
+                    .iter()
+                    .position(|known| known == text)
+                    .unwrap_or_else(|| {
+                        selectors.push(text.clone());
+                        selectors.len() - 1
+                    });
+                Selector(position as u32)
+            });
+            atoms.push(MoleculeAtom::new(atom, selector)?);
+            if let Some(label) = &atom.label {
+                labels.push((index as u32, label.clone()));
+            }
+            if let Some(atom_coordinates) = atom.coordinates {
+                coordinates.push((index as u32, atom_coordinates));
+            }
+            for edge in workspace.graph.edges(*node) {
+                edges.push((
+                    edge.id(),
+                    index_of[&edge.source()],
+                    *index_of.get(&edge.target()).ok_or_else(not_found)?,
+                    *edge.weight(),
+                ));
+            }
+        }
+        // keep bonds in the order they were added, it's the order of branches
+        edges.sort_by_key(|(edge, _, _, _)| *edge);
+        let bonds = edges
+            .into_iter()
+            .map(|(_, from, to, bond)| (from, to, bond))
+            .collect();
+        Ok(Self {
+            labels,
+            coordinates,
+            selectors,
+            ..Self::new(
+                atoms,
+                bonds,
+                roots.iter().map(|root| index_of[root]).collect(),
+            )
+        })
+    }
+
+    /// Add the molecule into a workspace as a new structure
+    pub fn to_workspace(&self, workspace: &mut Workspace) -> StructureId {
+        let nodes = self
+            .atoms
+            .iter()
+            .map(|atom| workspace.graph.add_node(self.to_atom(atom)))
+            .collect::<Vec<_>>();
+        for (index, label) in self.labels.iter() {
+            workspace.graph[nodes[*index as usize]].label = Some(label.clone());
+        }
+        for (index, coordinates) in self.coordinates.iter() {
+            workspace.graph[nodes[*index as usize]].coordinates = Some(*coordinates);
+        }
+        for (from, to, bond) in self.bonds.iter() {
+            workspace
+                .graph
+                .add_edge(nodes[*from as usize], nodes[*to as usize], *bond);
+        }
+        workspace.register_structure(
+            self.roots
+                .iter()
+                .map(|root| nodes[*root as usize])
+                .collect(),
+        )
+    }
+
+    /// write the molecule as `Workspace::to_sws`, None if it can't be
+    /// written, like more than 99 rings are open at the same time.
+    pub fn to_sws(&self) -> Option<String> {
+        SCRATCH.with(|scratch| {
+            let mut workspace = scratch.borrow_mut();
+            workspace.clear();
+            let id = self.to_workspace(&mut workspace);
+            workspace
+                .structure_root(id)
+                .and_then(|root| workspace.to_sws(root))
+        })
+    }
+
+    pub fn to_smiles(&self) -> Option<String> {
+        Some(Workspace::sws_to_smiles(&self.to_sws()?))
+    }
+
+    fn new(atoms: Vec<MoleculeAtom>, bonds: Vec<(u32, u32, Bond)>, roots: Vec<u32>) -> Self {
+        let mut degrees = vec![0u32; atoms.len() + 1];
+        for (from, to, _) in bonds.iter() {
+            degrees[*from as usize + 1] += 1;
+            degrees[*to as usize + 1] += 1;
+        }
+        let mut offsets = degrees;
+        for index in 1..offsets.len() {
+            offsets[index] += offsets[index - 1];
+        }
+        let mut filled = offsets.clone();
+        let mut adjacency = vec![(0, 0); bonds.len() * 2];
+        for (bond_index, (from, to, _)) in bonds.iter().enumerate() {
+            adjacency[filled[*from as usize] as usize] = (*to, bond_index as u32);
+            filled[*from as usize] += 1;
+            adjacency[filled[*to as usize] as usize] = (*from, bond_index as u32);
+            filled[*to as usize] += 1;
+        }
+        Self {
+            atoms,
+            bonds,
+            offsets,
+            adjacency,
+            roots,
+            labels: vec![],
+            coordinates: vec![],
+            selectors: vec![],
+        }
+    }
+
+    fn to_atom(&self, atom: &MoleculeAtom) -> Atom {
+        Atom {
+            element: atom.element,
+            isotope: atom.isotope,
+            charge: atom.charge as isize,
+            chirality_type: atom.chirality_type,
+            explicit_hydrogen: atom.explicit_hydrogen as usize,
+            selector: atom
+                .selector
+                .and_then(|selector| self.selector_text(selector))
+                .map(String::from),
+            aromatic: atom.aromatic,
+            react_id: atom.react_id.map(|react_id| react_id as usize),
+            stereo_group: atom.stereo_group,
+            label: None,
+            coordinates: None,
+            radical_electrons: atom.radical_electrons as usize,
+        }
+    }
+}
+
+/// Here implements accessors like `Workspace`, atoms are given by their index
+impl Molecule {
+    pub fn atom_count(&self) -> usize {
+        self.atoms.len()
+    }
+
+    pub fn bond_count(&self) -> usize {
+        self.bonds.len()
+    }
+
+    pub fn atoms(&self) -> &[MoleculeAtom] {
+        &self.atoms
+    }
+
+    /// Iterate over bonds as (from, to, bond)
+    pub fn bonds(&self) -> impl Iterator<Item = (usize, usize, &Bond)> {
+        self.bonds
+            .iter()
+            .map(|(from, to, bond)| (*from as usize, *to as usize, bond))
+    }
+
+    /// Get roots of all components, components are separated by `.` in SMILES.
+    pub fn components(&self) -> Vec<usize> {
+        self.roots.iter().map(|root| *root as usize).collect()
+    }
+
+    pub fn get_atom(&self, index: usize) -> Option<&MoleculeAtom> {
+        self.atoms.get(index)
+    }
+
+    pub fn get_label(&self, index: usize) -> Option<&str> {
+        let position = self
+            .labels
+            .binary_search_by_key(&(index as u32), |(atom, _)| *atom)
+            .ok()?;
+        Some(&self.labels[position].1)
+    }
+
+    pub fn get_coordinates(&self, index: usize) -> Option<[f64; 3]> {
+        let position = self
+            .coordinates
+            .binary_search_by_key(&(index as u32), |(atom, _)| *atom)
+            .ok()?;
+        Some(self.coordinates[position].1)
+    }
+
+    pub fn get_selector(&self, index: usize) -> Option<&str> {
+        self.selector_text(self.atoms.get(index)?.selector?)
+    }
+
+    /// Text of a selector of the molecule
+    pub fn selector_text(&self, selector: Selector) -> Option<&str> {
+        self.selectors.get(selector.0 as usize).map(String::as_str)
+    }
+
+    /// Get neighbors of an atom in both directions.
+    pub fn get_neighbors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.neighbor_entries(index)
+            .iter()
+            .map(|(neighbor, _)| *neighbor as usize)
+    }
+
+    pub fn get_edge_undirected(&self, atom_a: usize, atom_b: usize) -> Option<&Bond> {
+        self.neighbor_entries(atom_a)
+            .iter()
+            .find(|(neighbor, _)| *neighbor as usize == atom_b)
+            .map(|(_, bond)| &self.bonds[*bond as usize].2)
+    }
+
+    /// Find atoms by given conditions.
+    pub fn filter_nodes<F>(&self, find_fn: F) -> Vec<usize>
+    where
+        F: Fn(&MoleculeAtom) -> bool,
+    {
+        (0..self.atoms.len())
+            .filter(|index| find_fn(&self.atoms[*index]))
+            .collect()
+    }
+
+    /// Find first atom match given condition
+    pub fn find_node<F>(&self, find_fn: F) -> Option<usize>
+    where
+        F: Fn(&MoleculeAtom) -> bool,
+    {
+        self.atoms.iter().position(find_fn)
+    }
+
+    fn neighbor_entries(&self, index: usize) -> &[(u32, u32)] {
+        if index < self.atoms.len() {
+            &self.adjacency[self.offsets[index] as usize..self.offsets[index + 1] as usize]
+        } else {
+            &[]
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::io::{self, BufRead, Write};
+
+use petgraph::stable_graph::NodeIndex;
+
+use crate::workspace::{structure::StructureId, Workspace};
+
+/// Read records of a SD file one by one. Data fields of a record are set as
+/// properties of its structure.
+pub struct SdfReader<R> {
+    lines: io::Lines<R>,
+    /// number of lines read
+    line: usize,
+}
+
+impl<R: BufRead> SdfReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line: 0,
+        }
+    }
+
+    /// Read next record into the workspace, returns None when there is no
+    /// record left. A failed record is skipped, so reading goes on.
+    pub fn read_into(&mut self, workspace: &mut Workspace) -> Option<Result<StructureId, String>> {
+        let start = self.line + 1;
+        let mut record = vec![];
+        for line in self.lines.by_ref() {
+            self.line += 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Some(Err(format!("line {}: {}", self.line, err))),
+            };
+            if line.starts_with("$$$$") {
+                break;
+            }
+            record.push(line);
+        }
+        if record.iter().all(|line| line.trim().is_empty()) {
+            return None;
+        }
+        Some(
+            Self::parse_record(workspace, &record)
+                .map_err(|err| format!("record starts at line {}: {}", start, err)),
+        )
+    }
+
+    fn parse_record(workspace: &mut Workspace, record: &[String]) -> Result<StructureId, String> {
+        let end = record
+            .iter()
+            .position(|line| line.starts_with("M  END"))
+            .ok_or("M  END not found")?;
+        let molblock = record[..=end].join("\n");
+        let id = workspace.add_molblock(&molblock)?;
+        let mut lines = record[end + 1..].iter();
+        while let Some(line) = lines.next() {
+            let key = line.strip_prefix('>').and_then(|header| {
+                let start = header.find('<')?;
+                let end = header[start..].find('>')?;
+                Some(&header[start + 1..start + end])
+            });
+            if let Some(key) = key {
+                let value = lines
+                    .by_ref()
+                    .take_while(|line| !line.trim().is_empty())
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                workspace.set_structure_property(id, key, &value);
+            }
+        }
+        Ok(id)
+    }
+}
+
+/// Each record is read into a new workspace
+impl<R: BufRead> Iterator for SdfReader<R> {
+    type Item = Result<Workspace, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut workspace = Workspace::new();
+        let result = self.read_into(&mut workspace)?;
+        Some(result.map(|_| workspace))
+    }
+}
+
+/// Write structures as records of a SD file, properties of structures are
+/// written as data fields.
+pub struct SdfWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> SdfWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Write the structure which the given atom belongs to as a record
+    pub fn write_structure(
+        &mut self,
+        workspace: &Workspace,
+        structure_node: NodeIndex,
+    ) -> io::Result<()> {
+        let molblock = workspace.to_molblock(structure_node).ok_or(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Structure of {:?} can't be written as molblock",
+                structure_node
+            ),
+        ))?;
+        self.writer.write_all(molblock.as_bytes())?;
+        if let Some(info) = workspace
+            .structure_of(structure_node)
+            .and_then(|id| workspace.structure_info(id))
+        {
+            for (key, value) in info.properties.iter() {
+                writeln!(self.writer, "> <{}>", key)?;
+                writeln!(self.writer, "{}", value)?;
+                writeln!(self.writer)?;
+            }
+        }
+        writeln!(self.writer, "$$$$")
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
@@ -1,27 +1,55 @@
-pub mod encode;
+pub mod accessor;
+pub mod copy;
+pub mod cxsmiles;
 pub mod decode;
+pub mod descriptors;
+pub mod dot;
 pub mod editor;
-pub mod accessor;
+pub mod embed;
+pub mod encode;
+pub mod kekule;
+pub mod layout;
+pub mod molfile;
+#[cfg(feature = "rand")]
+pub mod random;
+pub mod rings;
+pub mod structure;
+pub mod topology;
 
-use petgraph::{dot::Dot, stable_graph::{StableGraph, NodeIndex}, Directed};
+use std::collections::{BTreeMap, HashMap};
 
-use crate::definitions::{bond::Bond, atom::Atom};
+use petgraph::{
+    dot::Dot,
+    stable_graph::{NodeIndex, StableGraph},
+    Directed,
+};
 
+use crate::definitions::{atom::Atom, bond::Bond};
+use structure::{StructureId, StructureInfo};
 
 /// a Workspace is a graph space that can deal with structures
-#[derive(Debug)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Workspace {
     pub graph: StableGraph<Atom, Bond, Directed>,
+    structures: BTreeMap<StructureId, StructureInfo>,
+    /// structure of the root of every registered component
+    root_structures: HashMap<NodeIndex, StructureId>,
+    next_structure_id: usize,
 }
 
 impl Workspace {
     /// create a workspace
     pub fn new() -> Self {
-        Self {
-            graph: StableGraph::new(),
-        }
+        Self::default()
+    }
+    /// remove all atoms and structures
+    pub fn clear(&mut self) {
+        self.graph.clear();
+        self.structures.clear();
+        self.root_structures.clear();
     }
-    pub fn dot_representation(&self) -> Dot<&StableGraph<Atom, Bond, Directed>> {
+    pub fn dot_representation(&self) -> Dot<'_, &StableGraph<Atom, Bond, Directed>> {
         Dot::new(&self.graph)
     }
     /// Check if some atoms are in the same structure(has same structure root)
@@ -31,14 +59,9 @@ impl Workspace {
             .map(|node_index| self.find_root_of(*node_index))
             .collect::<Vec<_>>();
         if let Some(root_index) = roots.pop() {
-            if let Some(_) = roots
+            !roots
                 .into_iter()
-                .find(|another_root| another_root != &root_index)
-            {
-                false
-            } else {
-                true
-            }
+                .any(|another_root| another_root != root_index)
         } else {
             false
         }
@@ -1,10 +1,13 @@
+use std::collections::HashSet;
+
 use petgraph::{
     stable_graph::{EdgeIndex, NodeIndex},
-    Direction::{self, Incoming},
+    visit::EdgeRef,
+    Direction::{self, Incoming, Outgoing},
 };
 
-use crate::definitions::{atom::Atom, bond::Bond};
 use super::Workspace;
+use crate::definitions::{atom::Atom, bond::Bond, element::Element};
 
 /// Here implements function that can get indexes
 impl Workspace {
@@ -28,46 +31,57 @@ impl Workspace {
                     })
                     .collect::<Vec<_>>()
                     .len();
-                if node_incoming_amount == 0 {
-                    true
-                } else {
-                    false
-                }
+                node_incoming_amount == 0
             })
             .collect()
     }
 
     /// Find the root of structure by given node
     pub fn find_root_of(&self, structure_node: NodeIndex) -> Option<NodeIndex> {
-        let incoming_edges = self
-            .graph
-            .edges_directed(structure_node, Incoming)
-            .filter(|edge| !edge.weight().is_ring_bond())
-            .collect::<Vec<_>>()
-            .len();
-        if incoming_edges == 0 {
-            Some(structure_node)
-        } else {
-            let neighbors = self
+        let mut current = structure_node;
+        // no path in a tree is longer than the amount of atoms
+        for _ in 0..=self.graph.node_count() {
+            match self
                 .graph
-                .neighbors_directed(structure_node, Incoming)
-                .filter(|neighbor_index| {
-                    self.graph
-                        .find_edge(*neighbor_index, structure_node)
-                        .and_then(|edge_index| self.graph.edge_weight(edge_index))
-                        .and_then(|bond| Some(!bond.is_ring_bond()))
-                        .unwrap_or(false)
-                })
-                .collect::<Vec<_>>();
-            let incoming = neighbors.get(0)?;
-            self.find_root_of(*incoming)
+                .edges_directed(current, Incoming)
+                .find(|edge| !edge.weight().is_ring_bond())
+            {
+                Some(edge) => current = edge.source(),
+                None => return Some(current),
+            }
+        }
+        None
+    }
+
+    /// Get roots of all components of the structure which the given atom
+    /// belongs to, components are separated by `.` in SMILES.
+    pub fn components(&self, structure_node: NodeIndex) -> Option<Vec<NodeIndex>> {
+        if let Some(id) = self.structure_of(structure_node) {
+            self.structure_roots(id)
+        } else {
+            Some(vec![self.find_root_of(structure_node)?])
         }
     }
 
+    /// Find root of the component with most heavy atoms in the structure
+    /// which the given atom belongs to, the first one wins if some of them have the same size.
+    pub fn largest_fragment(&self, structure_node: NodeIndex) -> Option<NodeIndex> {
+        let mut largest: Option<(NodeIndex, usize)> = None;
+        for root in self.components(structure_node)? {
+            let heavy_atoms = self
+                .filter_nodes_in_structure(root, |atom| atom.element != Element::H)?
+                .len();
+            if largest.is_none_or(|(_, size)| heavy_atoms > size) {
+                largest = Some((root, heavy_atoms));
+            }
+        }
+        largest.map(|(root, _)| root)
+    }
+
     /// Get all atoms' NodeIndex into one Vector.
     pub fn get_atoms_of_structure(&self, structure_node: NodeIndex) -> Option<Vec<NodeIndex>> {
         let root = self.find_root_of(structure_node)?;
-        Some([vec![root], self.search_all_atoms(root)].concat())
+        Some(self.search_all_atoms(root))
     }
 
     /// Find nodes by given conditions.
@@ -97,7 +111,7 @@ impl Workspace {
         F: Fn(&Atom) -> bool,
     {
         self.filter_nodes_in_structure(structure_root, find_fn)?
-            .get(0)
+            .first()
             .copied()
     }
 
@@ -117,7 +131,7 @@ impl Workspace {
     where
         F: Fn(&Atom) -> bool,
     {
-        self.filter_nodes(find_fn).get(0).copied()
+        self.filter_nodes(find_fn).first().copied()
     }
 }
 
@@ -131,6 +145,72 @@ impl Workspace {
         self.graph.node_weight_mut(index)
     }
 
+    /// Count hydrogens of the atom which are not added as atoms, given by
+    /// `explicit_hydrogen` or the default valence of element.
+    pub fn implicit_hydrogen_count(&self, index: NodeIndex) -> Option<usize> {
+        let atom = self.get_atom(index)?;
+        if atom.explicit_hydrogen != 0 {
+            Some(atom.explicit_hydrogen)
+        } else if atom.element.default_hydrogen() == 0 {
+            Some(0)
+        } else {
+            let bonds = self
+                .graph
+                .edges_directed(index, Incoming)
+                .chain(self.graph.edges_directed(index, Outgoing))
+                .map(|edge| edge.weight().bond_type().valence() as isize)
+                .sum::<isize>();
+            let aromatic = if atom.aromatic { 1 } else { 0 };
+            let count = atom.element.default_hydrogen() as isize + atom.charge
+                - bonds
+                - aromatic
+                - atom.radical_electrons as isize;
+            Some(count.max(0) as usize)
+        }
+    }
+
+    /// Get neighbors of an atom in the order `@` and `@@` refer to: the atom
+    /// bonded before it, the implicit hydrogen, atoms bonded by ring bonds,
+    /// then atoms bonded after it. An atom with 3 neighbors gets a `None` as
+    /// its hydrogen (or lone pair).
+    pub fn chiral_neighbors(&self, index: NodeIndex) -> Option<Vec<Option<NodeIndex>>> {
+        self.get_atom(index)?;
+        let mut parent = None;
+        let mut ring_bonds = vec![];
+        let mut children = vec![];
+        for edge in self
+            .graph
+            .edges_directed(index, Incoming)
+            .chain(self.graph.edges_directed(index, Outgoing))
+        {
+            let neighbor = if edge.source() == index {
+                edge.target()
+            } else {
+                edge.source()
+            };
+            if edge.weight().is_ring_bond() {
+                ring_bonds.push((edge.id(), neighbor));
+            } else if edge.target() == index {
+                parent = Some(neighbor);
+            } else {
+                children.push((edge.id(), neighbor));
+            }
+        }
+        ring_bonds.sort();
+        children.sort();
+        let mut neighbors = parent.into_iter().map(Some).collect::<Vec<_>>();
+        if ring_bonds.len() + children.len() + neighbors.len() == 3 {
+            neighbors.push(None);
+        }
+        neighbors.extend(
+            ring_bonds
+                .into_iter()
+                .chain(children)
+                .map(|(_, neighbor)| Some(neighbor)),
+        );
+        Some(neighbors)
+    }
+
     pub fn get_edge_undirected(
         &self,
         atom_a: NodeIndex,
@@ -141,16 +221,14 @@ impl Workspace {
             .and_then(|(edge, direction)| {
                 self.graph
                     .edge_weight(edge)
-                    .and_then(|bond| Some((bond, edge, direction)))
+                    .map(|bond| (bond, edge, direction))
             })
     }
 
     pub fn get_edge(&self, atom_from: NodeIndex, atom_to: NodeIndex) -> Option<(&Bond, EdgeIndex)> {
-        self.graph.find_edge(atom_from, atom_to).and_then(|edge| {
-            self.graph
-                .edge_weight(edge)
-                .and_then(|bond| Some((bond, edge)))
-        })
+        self.graph
+            .find_edge(atom_from, atom_to)
+            .and_then(|edge| self.graph.edge_weight(edge).map(|bond| (bond, edge)))
     }
 
     pub fn get_edge_mut(
@@ -158,41 +236,30 @@ impl Workspace {
         atom_from: NodeIndex,
         atom_to: NodeIndex,
     ) -> Option<(&mut Bond, EdgeIndex)> {
-        self.graph.find_edge(atom_from, atom_to).and_then(|edge| {
-            self.graph
-                .edge_weight_mut(edge)
-                .and_then(|bond| Some((bond, edge)))
-        })
+        self.graph
+            .find_edge(atom_from, atom_to)
+            .and_then(|edge| self.graph.edge_weight_mut(edge).map(|bond| (bond, edge)))
     }
 }
 
 /// Private functions used upon
 impl Workspace {
-    /// Search all atoms connect by bond in same structure
+    /// Search all atoms connect by bond in same structure, in depth-first
+    /// order starting from the given node
     fn search_all_atoms(&self, node: NodeIndex) -> Vec<NodeIndex> {
-        let neighbors = self
-            .get_next_neighbors(node)
-            .iter()
-            .copied()
-            .filter(|neighbor| {
-                let edge_index = self.graph.find_edge(node, *neighbor);
-                if let Some(edge_index) = edge_index {
-                    let edge = self.graph.edge_weight(edge_index);
-                    if let Some(bond) = edge {
-                        !bond.is_ring_bond()
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            })
-            .collect::<Vec<_>>();
-        let recursive_neighbors = neighbors
-            .iter()
-            .map(|neighbor| self.search_all_atoms(*neighbor))
-            .collect::<Vec<_>>()
-            .concat();
-        [neighbors, recursive_neighbors].concat()
+        let mut atoms = vec![];
+        let mut visited = HashSet::from([node]);
+        let mut stack = vec![node];
+        while let Some(current) = stack.pop() {
+            atoms.push(current);
+            let children = self
+                .graph
+                .edges(current)
+                .filter(|edge| !edge.weight().is_ring_bond() && visited.insert(edge.target()))
+                .map(|edge| edge.target())
+                .collect::<Vec<_>>();
+            stack.extend(children.into_iter().rev());
+        }
+        atoms
     }
 }
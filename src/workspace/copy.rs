@@ -0,0 +1,163 @@
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{stable_graph::NodeIndex, visit::EdgeRef};
+
+use crate::definitions::{atom::Atom, bond::Bond};
+
+use super::{
+    structure::{SGroup, StructureId},
+    Workspace,
+};
+
+type CopiedAtoms = Vec<(NodeIndex, Atom)>;
+type CopiedBonds = Vec<(NodeIndex, NodeIndex, Bond)>;
+
+/// Here implements functions copying structures within and between workspaces.
+/// All of them return id of the new structure and a map from atoms of the
+/// source to atoms of the copy.
+impl Workspace {
+    /// Copy the structure which the given atom belongs to as a new structure,
+    /// name, properties and sgroups are copied too.
+    pub fn duplicate_structure(
+        &mut self,
+        structure_node: NodeIndex,
+    ) -> Option<(StructureId, HashMap<NodeIndex, NodeIndex>)> {
+        let roots = self.components(structure_node)?;
+        let (atoms, bonds) = self.copy_components(&roots)?;
+        let info = self
+            .structure_of(structure_node)
+            .and_then(|id| self.structure_info(id))
+            .cloned();
+        let (id, mapping) = self.paste_components(atoms, bonds, &roots);
+        if let Some(info) = info {
+            let copied = self.structure_info_mut(id)?;
+            copied.name = info.name;
+            copied.properties = info.properties;
+            copied.sgroups = remap_sgroups(&info.sgroups, &mapping);
+        }
+        Some((id, mapping))
+    }
+
+    /// Copy the structure which the given atom of another workspace belongs
+    /// to into this workspace, name, properties and sgroups are copied too.
+    pub fn import_from(
+        &mut self,
+        other: &Workspace,
+        structure_node: NodeIndex,
+    ) -> Option<(StructureId, HashMap<NodeIndex, NodeIndex>)> {
+        let roots = other.components(structure_node)?;
+        let (atoms, bonds) = other.copy_components(&roots)?;
+        let (id, mapping) = self.paste_components(atoms, bonds, &roots);
+        if let Some(info) = other
+            .structure_of(structure_node)
+            .and_then(|id| other.structure_info(id))
+        {
+            let copied = self.structure_info_mut(id)?;
+            copied.name = info.name.clone();
+            copied.properties = info.properties.clone();
+            copied.sgroups = remap_sgroups(&info.sgroups, &mapping);
+        }
+        Some((id, mapping))
+    }
+
+    /// Copy a set of atoms and bonds between them as a new structure. Trees
+    /// of the copy are rebuilt, so the set is not required to be a subtree,
+    /// and it might fall into several components. Stereo centers keep their
+    /// configuration if all their neighbors are copied, others lose it.
+    pub fn extract(
+        &mut self,
+        atoms: &[NodeIndex],
+    ) -> Option<(StructureId, HashMap<NodeIndex, NodeIndex>)> {
+        let selected = atoms.iter().copied().collect::<HashSet<_>>();
+        let mut mapping = HashMap::new();
+        let mut new_atoms = vec![];
+        for atom in atoms {
+            if !mapping.contains_key(atom) {
+                let new_atom = self.graph.add_node(self.get_atom(*atom)?.clone());
+                mapping.insert(*atom, new_atom);
+                new_atoms.push(new_atom);
+            }
+        }
+        let mut bonds = vec![];
+        for atom in atoms {
+            for edge in self.graph.edges(*atom) {
+                if selected.contains(&edge.target()) {
+                    bonds.push((edge.id(), edge.source(), edge.target(), *edge.weight()));
+                }
+            }
+        }
+        bonds.sort_by_key(|(edge, _, _, _)| *edge);
+        bonds.dedup_by_key(|(edge, _, _, _)| *edge);
+        let bonds = bonds
+            .into_iter()
+            .map(|(_, from, to, bond)| (mapping[&from], mapping[&to], bond))
+            .collect::<Vec<_>>();
+        let orders = atoms
+            .iter()
+            .filter_map(|atom| {
+                let order = self
+                    .stereo_order(*atom)?
+                    .into_iter()
+                    // neighbors left out don't match any atom of the copy
+                    .map(|neighbor| neighbor.map(|node| *mapping.get(&node).unwrap_or(&node)))
+                    .collect();
+                Some((mapping[atom], order))
+            })
+            .collect::<Vec<_>>();
+        let roots = self.build_forest(&new_atoms, &bonds);
+        self.reorder_chirality(orders);
+        Some((self.register_structure(roots), mapping))
+    }
+}
+
+/// Private functions used upon
+impl Workspace {
+    /// Clone atoms of given components and bonds between them, bonds are in
+    /// the order they were added.
+    fn copy_components(&self, roots: &[NodeIndex]) -> Option<(CopiedAtoms, CopiedBonds)> {
+        let mut atoms = vec![];
+        let mut bonds = vec![];
+        for root in roots {
+            for atom in self.get_atoms_of_structure(*root)? {
+                atoms.push((atom, self.get_atom(atom)?.clone()));
+                for edge in self.graph.edges(atom) {
+                    bonds.push((edge.id(), edge.source(), edge.target(), *edge.weight()));
+                }
+            }
+        }
+        bonds.sort_by_key(|(edge, _, _, _)| *edge);
+        Some((
+            atoms,
+            bonds
+                .into_iter()
+                .map(|(_, from, to, bond)| (from, to, bond))
+                .collect(),
+        ))
+    }
+
+    /// Add copied atoms and bonds as they are and register them as a new
+    /// structure with given roots.
+    fn paste_components(
+        &mut self,
+        atoms: CopiedAtoms,
+        bonds: CopiedBonds,
+        roots: &[NodeIndex],
+    ) -> (StructureId, HashMap<NodeIndex, NodeIndex>) {
+        let mut mapping = HashMap::new();
+        for (source, atom) in atoms {
+            mapping.insert(source, self.graph.add_node(atom));
+        }
+        for (from, to, bond) in bonds {
+            self.graph.add_edge(mapping[&from], mapping[&to], bond);
+        }
+        let anchors = roots.iter().map(|root| mapping[root]).collect();
+        (self.register_structure(anchors), mapping)
+    }
+}
+
+fn remap_sgroups(sgroups: &[SGroup], mapping: &HashMap<NodeIndex, NodeIndex>) -> Vec<SGroup> {
+    sgroups
+        .iter()
+        .filter_map(|sgroup| sgroup.remap(mapping))
+        .collect()
+}
@@ -0,0 +1,267 @@
+use petgraph::stable_graph::NodeIndex;
+
+use crate::definitions::chirality::StereoGroup;
+
+use super::{encode::WriteOptions, Workspace};
+
+/// Split a CXSMILES into the SMILES and its extension block without `|`.
+/// The SMILES ends at the first whitespace out of selectors.
+pub(super) fn split_extensions(cxsmiles: &str) -> (&str, Option<&str>) {
+    let mut depth = 0;
+    for (index, char) in cxsmiles.char_indices() {
+        match char {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ if char.is_whitespace() && depth <= 0 => {
+                let extensions = cxsmiles[index..]
+                    .trim_start()
+                    .strip_prefix('|')
+                    .and_then(|rest| rest.find('|').map(|end| &rest[..end]));
+                return (&cxsmiles[..index], extensions);
+            }
+            _ => {}
+        }
+    }
+    (cxsmiles, None)
+}
+
+/// Here implements ChemAxon extended SMILES: atom labels `$...$`,
+/// coordinates `(...)`, radicals `^` and stereo groups `a`, `o` and `&`
+/// written in `|...|` after SMILES.
+impl Workspace {
+    /// write the structure as SwS with a CXSMILES extension block if there
+    /// are labels, coordinates, radicals or stereo groups.
+    pub fn to_cxsws(&self, node: NodeIndex) -> Option<String> {
+        let (sws, atoms) = self.write_sws(node, &WriteOptions::default())?;
+        Some(self.append_extensions(sws, &atoms, false))
+    }
+
+    /// write the structure as CXSMILES, selectors can be written as atom
+    /// values `$_AV:...$` so they are readable by other software. They're
+    /// read back as selectors by `add_structure`.
+    pub fn to_cxsmiles(&self, node: NodeIndex, selectors_as_values: bool) -> Option<String> {
+        let options = WriteOptions {
+            selectors: false,
+            ..Default::default()
+        };
+        let (smiles, atoms) = self.write_sws(node, &options)?;
+        Some(self.append_extensions(smiles, &atoms, selectors_as_values))
+    }
+
+    /// Apply an extension block to atoms given in the order of SMILES
+    pub(super) fn apply_extensions(
+        &mut self,
+        extensions: &str,
+        atoms: &[NodeIndex],
+    ) -> Result<(), String> {
+        let atom_at = |index: usize| {
+            atoms
+                .get(index)
+                .copied()
+                .ok_or(format!("Atom {} in CXSMILES not found", index))
+        };
+        let mut rest = extensions;
+        while !rest.is_empty() {
+            if let Some(body) = rest.strip_prefix("$_AV:") {
+                let (values, next) = take_until(body, '$')?;
+                for (index, value) in values.split(';').enumerate() {
+                    if !value.is_empty() {
+                        self.graph[atom_at(index)?].selector = Some(value.to_string());
+                    }
+                }
+                rest = next;
+            } else if let Some(body) = rest.strip_prefix('$') {
+                let (labels, next) = take_until(body, '$')?;
+                for (index, label) in labels.split(';').enumerate() {
+                    if !label.is_empty() {
+                        self.graph[atom_at(index)?].label = Some(label.to_string());
+                    }
+                }
+                rest = next;
+            } else if let Some(body) = rest.strip_prefix('(') {
+                let (coordinates, next) = take_until(body, ')')?;
+                for (index, point) in coordinates.split(';').enumerate() {
+                    if point.split(',').all(str::is_empty) {
+                        continue;
+                    }
+                    let mut values = [0.0; 3];
+                    for (value, text) in values.iter_mut().zip(point.split(',')) {
+                        if !text.is_empty() {
+                            *value = text
+                                .parse()
+                                .map_err(|_| format!("Invalid coordinates {}", point))?;
+                        }
+                    }
+                    self.graph[atom_at(index)?].coordinates = Some(values);
+                }
+                rest = next;
+            } else if let Some(body) = rest.strip_prefix('^') {
+                let (code, indexes, next) = take_group(body)?;
+                for index in indexes {
+                    self.graph[atom_at(index)?].radical_electrons = radical_electrons(code);
+                }
+                rest = next;
+            } else if rest.starts_with("a:") || rest.starts_with('o') || rest.starts_with('&') {
+                let (kind, body) = rest.split_at(1);
+                let (group, indexes, next) = take_group(body)?;
+                let stereo_group = match kind {
+                    "a" => StereoGroup::Absolute,
+                    "o" => StereoGroup::Or(group),
+                    _ => StereoGroup::And(group),
+                };
+                for index in indexes {
+                    self.graph[atom_at(index)?].stereo_group = Some(stereo_group);
+                }
+                rest = next;
+            } else {
+                rest = skip_field(rest);
+            }
+            rest = rest.strip_prefix(',').unwrap_or(rest);
+        }
+        Ok(())
+    }
+}
+
+/// Private functions used upon
+impl Workspace {
+    fn append_extensions(
+        &self,
+        mut smiles: String,
+        atoms: &[NodeIndex],
+        selectors_as_values: bool,
+    ) -> String {
+        let atoms = atoms
+            .iter()
+            .filter_map(|atom| self.get_atom(*atom))
+            .collect::<Vec<_>>();
+        let mut fields = vec![];
+        if atoms.iter().any(|atom| atom.label.is_some()) {
+            let labels = atoms
+                .iter()
+                .map(|atom| atom.label.as_deref().unwrap_or_default())
+                .collect::<Vec<_>>();
+            fields.push(format!("${}$", labels.join(";")));
+        }
+        if selectors_as_values && atoms.iter().any(|atom| atom.selector.is_some()) {
+            let values = atoms
+                .iter()
+                .map(|atom| atom.selector.as_deref().unwrap_or_default())
+                .collect::<Vec<_>>();
+            fields.push(format!("$_AV:{}$", values.join(";")));
+        }
+        if atoms.iter().any(|atom| atom.coordinates.is_some()) {
+            let coordinates = atoms
+                .iter()
+                .map(|atom| match atom.coordinates {
+                    Some([x, y, z]) => format!("{:.4},{:.4},{:.4}", x, y, z),
+                    None => ",,".to_string(),
+                })
+                .collect::<Vec<_>>();
+            fields.push(format!("({})", coordinates.join(";")));
+        }
+        let mut groups: Vec<(String, Vec<usize>)> = vec![];
+        for (index, atom) in atoms.iter().enumerate() {
+            let radical = radical_code(atom.radical_electrons).map(|code| format!("^{}", code));
+            let stereo_group = atom.stereo_group.map(|stereo_group| match stereo_group {
+                StereoGroup::Absolute => "a".to_string(),
+                StereoGroup::Or(group) => format!("o{}", group),
+                StereoGroup::And(group) => format!("&{}", group),
+            });
+            for name in radical.into_iter().chain(stereo_group) {
+                match groups.iter_mut().find(|(group, _)| *group == name) {
+                    Some((_, indexes)) => indexes.push(index),
+                    None => groups.push((name, vec![index])),
+                }
+            }
+        }
+        for (name, indexes) in groups {
+            let indexes = indexes
+                .iter()
+                .map(|index| index.to_string())
+                .collect::<Vec<_>>();
+            fields.push(format!("{}:{}", name, indexes.join(",")));
+        }
+        if !fields.is_empty() {
+            smiles.push_str(" |");
+            smiles.push_str(&fields.join(","));
+            smiles.push('|');
+        }
+        smiles
+    }
+}
+
+/// Take text until the given char, returns the text and the rest after it
+fn take_until(text: &str, end: char) -> Result<(&str, &str), String> {
+    let position = text
+        .find(end)
+        .ok_or(format!("{} not closed in CXSMILES", end))?;
+    Ok((&text[..position], &text[position + end.len_utf8()..]))
+}
+
+/// Take a field like `1:0,2,5`, returns the number before `:`, atoms
+/// and the rest after the atoms.
+fn take_group(text: &str) -> Result<(usize, Vec<usize>, &str), String> {
+    let (number, mut rest) = take_until(text, ':')?;
+    let number = if number.is_empty() {
+        0
+    } else {
+        number
+            .parse()
+            .map_err(|_| format!("Invalid group {} in CXSMILES", number))?
+    };
+    let mut indexes = vec![];
+    loop {
+        let end = rest
+            .find(|char: char| !char.is_ascii_digit())
+            .unwrap_or(rest.len());
+        indexes.push(
+            rest[..end]
+                .parse()
+                .map_err(|_| format!("Invalid atom list {} in CXSMILES", text))?,
+        );
+        rest = &rest[end..];
+        match rest.strip_prefix(',') {
+            Some(next) if next.starts_with(|char: char| char.is_ascii_digit()) => rest = next,
+            _ => break,
+        }
+    }
+    Ok((number, indexes, rest))
+}
+
+/// Skip a field not supported, it ends before a `,` not followed by digits
+fn skip_field(text: &str) -> &str {
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, char)) = chars.next() {
+        if char == ','
+            && chars
+                .peek()
+                .is_none_or(|(_, next)| !next.is_ascii_digit() && *next != '.')
+        {
+            return &text[index..];
+        }
+    }
+    ""
+}
+
+/// CXSMILES radical of given amount of unpaired electrons: 1 for
+/// monovalent, 2 for divalent and 5 for trivalent. None if there's no
+/// code for it.
+fn radical_code(electrons: usize) -> Option<usize> {
+    match electrons {
+        1 => Some(1),
+        2 => Some(2),
+        3 => Some(5),
+        _ => None,
+    }
+}
+
+/// Unpaired electrons of a CXSMILES radical: 1 is monovalent, 2 to 4 are
+/// divalent (singlet and triplet) and 5 to 7 are trivalent.
+fn radical_electrons(code: usize) -> usize {
+    match code {
+        1 => 1,
+        2..=4 => 2,
+        5..=7 => 3,
+        _ => 0,
+    }
+}
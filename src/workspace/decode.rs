@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::tokenizer::{sws_tokenize, BRANCH_RE, NOTHING_RE, RING_BOND_RE};
 use petgraph::graph::NodeIndex;
 
@@ -6,7 +8,7 @@ use crate::definitions::{
     bond::{Bond, BondType},
 };
 
-use super::Workspace;
+use super::{cxsmiles::split_extensions, structure::StructureId, Workspace};
 
 struct Status {
     branch: Vec<NodeIndex>,
@@ -33,11 +35,12 @@ impl Status {
         );
     }
 
-    fn quit_branch(&mut self) {
-        if self.branch.len() > 0 {
+    fn quit_branch(&mut self) -> Result<(), &'static str> {
+        if !self.branch.is_empty() {
             self.current = self.branch.pop();
+            Ok(())
         } else {
-            panic!("Quit a branch when there in fact on branches")
+            Err("Quit a branch when there in fact on branches")
         }
     }
 
@@ -59,20 +62,14 @@ impl RingStatus {
         if let Some(captured) = RING_BOND_RE.captures(token) {
             let id = captured
                 .name("ring_id")
-                .and_then(|m| Some(m.as_str()))
-                .and_then(|s| Some(s.strip_prefix("%").unwrap_or(s)))
-                .and_then(|s| {
-                    Some(
-                        s.parse::<u8>()
-                            .expect("Shall not return error because regex matched"),
-                    )
-                })
-                .unwrap();
+                .map(|m| m.as_str())
+                .map(|s| s.strip_prefix("%").unwrap_or(s))
+                .and_then(|s| s.parse::<u8>().ok())?;
             let bond_type = captured
                 .name("bond_type")
-                .and_then(|m| Some(m.as_str()))
-                .and_then(|s| BondType::new(s))
-                .and_then(|bond_type| Some(Bond::new(bond_type, true)));
+                .map(|m| m.as_str())
+                .and_then(BondType::new)
+                .map(|bond_type| Bond::new(bond_type, true));
             Some((bond_type, id))
         } else {
             None
@@ -96,9 +93,11 @@ impl RingStatus {
                 .waiting_to_connect
                 .get(target)
                 .expect("Never get None as target index is found by position method.");
+            // the bond is stored from the opening atom to the closing one,
+            // `/` and `\` given at the closing atom are reversed.
             if let Some(given_bond_type) = bond_type {
                 if let Some(target_bond_type) = target_ring.1 {
-                    if given_bond_type == target_bond_type {
+                    if given_bond_type.reverse() == target_bond_type {
                         let node_index = target_ring.0;
                         self.waiting_to_connect.remove(target);
                         Some((node_index, Some(target_bond_type)))
@@ -108,7 +107,7 @@ impl RingStatus {
                 } else {
                     let node_index = target_ring.0;
                     self.waiting_to_connect.remove(target);
-                    Some((node_index, Some(given_bond_type)))
+                    Some((node_index, Some(given_bond_type.reverse())))
                 }
             } else {
                 if let Some(target_bond_type) = target_ring.1 {
@@ -128,25 +127,116 @@ impl RingStatus {
     }
 }
 
+/// Find the component which the given one is joined into
+fn find_joined(joined: &[usize], component: usize) -> usize {
+    let mut component = component;
+    while joined[component] != component {
+        component = joined[component];
+    }
+    component
+}
+
 impl Workspace {
     /// add a SMILES into workspace as a structure.
-    pub fn add_structure(&mut self, smiles: &str) -> Result<NodeIndex, String> {
+    ///
+    /// Components separated by `.` are not bonded, each of them becomes a
+    /// component with its own root, and all of them are registered as one
+    /// structure with the returned id. A CXSMILES extension block `|...|`
+    /// after the SMILES is applied to atoms.
+    pub fn add_structure(&mut self, smiles: &str) -> Result<StructureId, String> {
+        Ok(self.add_structure_atoms(smiles)?.0)
+    }
+
+    /// add a SMILES into workspace as a structure, also returns atoms in the
+    /// order they're written. Nothing is left in the workspace if failed.
+    pub fn add_structure_atoms(
+        &mut self,
+        smiles: &str,
+    ) -> Result<(StructureId, Vec<NodeIndex>), String> {
+        let (smiles, extensions) = split_extensions(smiles);
+        let mut atoms = vec![];
+        match self
+            .decode_structure(smiles, &mut atoms)
+            .and_then(|components| {
+                if let Some(extensions) = extensions {
+                    self.apply_extensions(extensions, &atoms)?;
+                }
+                Ok(components)
+            }) {
+            Ok(components) => Ok((self.register_structure(components), atoms)),
+            Err(err) => {
+                for atom in atoms {
+                    self.graph.remove_node(atom);
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Private functions used upon
+impl Workspace {
+    /// Build the graph of a SMILES, added atoms are pushed into `atoms`.
+    /// Returns one atom of each component.
+    fn decode_structure(
+        &mut self,
+        smiles: &str,
+        atoms: &mut Vec<NodeIndex>,
+    ) -> Result<Vec<NodeIndex>, String> {
         let mut construct_status = Status::new();
         let mut ring_status = RingStatus::new();
         let mut bond_to_connect: Option<BondType> = None;
+        let mut disconnected = false;
         let tokens = sws_tokenize(smiles);
-        let result = if let Some(node) = Atom::new(tokens[0]) {
-            Ok(construct_status.next(self.graph.add_node(node)))
+        if tokens.iter().map(|token| token.len()).sum::<usize>() != smiles.len() {
+            return Err(format!("Unrecognized characters in {}", smiles));
+        }
+        let first_token = tokens.first().copied().unwrap_or_default();
+        let first_index = if let Some(node) = Atom::new(first_token) {
+            let node_index = self.graph.add_node(node);
+            atoms.push(node_index);
+            construct_status.next(node_index)
         } else {
-            Err(format!(
+            return Err(format!(
                 "First token must be a SMILES atom token, but got {}",
-                tokens[0]
-            ))
+                first_token
+            ));
         };
-        for token in tokens[1..].into_iter() {
+        // every `.` starts a new component, remember one atom of each of them
+        let mut components = vec![first_index];
+        let mut component_of = HashMap::from([(first_index, 0)]);
+        // components joined by ring bonds written across `.`
+        let mut joined = vec![0];
+        // neighbors of atoms in the order they're written, `@` and `@@` refer
+        // to it. Ring bonds not closed yet are None.
+        let mut written: HashMap<NodeIndex, Vec<Option<NodeIndex>>> = HashMap::new();
+        let mut ring_positions = HashMap::new();
+        for token in tokens[1..].iter() {
             let current_index = construct_status.get_index().map_err(String::from)?;
             if let Some(node) = Atom::new(token) {
                 let node_index = self.graph.add_node(node);
+                atoms.push(node_index);
+                construct_status.next(node_index);
+                if disconnected {
+                    if let Some(bond) = bond_to_connect {
+                        return Err(format!("Bond {:?} after `.` connects nothing", bond));
+                    }
+                    disconnected = false;
+                    joined.push(components.len());
+                    components.push(node_index);
+                }
+                component_of.insert(node_index, components.len() - 1);
+                if components.last() == Some(&node_index) {
+                    continue;
+                }
+                written
+                    .entry(node_index)
+                    .or_default()
+                    .push(Some(current_index));
+                written
+                    .entry(current_index)
+                    .or_default()
+                    .push(Some(node_index));
                 self.graph.add_edge(
                     current_index,
                     node_index,
@@ -169,16 +259,45 @@ impl Workspace {
                         }
                     },
                 );
-                construct_status.next(node_index);
+            } else if NOTHING_RE.is_match(token) {
+                if let Some(bond) = bond_to_connect {
+                    return Err(format!("Bond {:?} before `.` connects nothing", bond));
+                }
+                disconnected = true;
             } else if let Some(bond) = BondType::new(token) {
                 bond_to_connect = Some(bond)
-            } else if NOTHING_RE.is_match(token) {
-                bond_to_connect = Some(BondType::NoBond)
             } else if let Some((bond_type, id)) = RingStatus::identify_ring(token) {
+                let opening = !ring_status
+                    .waiting_to_connect
+                    .iter()
+                    .any(|item| item.2 == id);
                 let ring = ring_status.ring(current_index, bond_type, id);
+                let neighbors = written.entry(current_index).or_default();
+                if opening {
+                    ring_positions.insert(id, neighbors.len());
+                    neighbors.push(None);
+                }
                 if let Some((previous_index, bond)) = ring {
+                    neighbors.push(Some(previous_index));
+                    if let Some(position) = ring_positions.remove(&id) {
+                        written.entry(previous_index).or_default()[position] = Some(current_index);
+                    }
+                    // a ring bond written across `.` joins two components, so it
+                    // has to become a normal bond of the directed tree.
+                    let previous_component = find_joined(&joined, component_of[&previous_index]);
+                    let current_component = find_joined(&joined, component_of[&current_index]);
+                    let ring_key = previous_component == current_component;
+                    if !ring_key {
+                        joined[current_component] = previous_component;
+                        self.reset_root(current_index)
+                            .ok_or(format!("Failed to join components at {:?}", current_index))?;
+                    }
                     if let Some(bond) = bond {
-                        self.graph.add_edge(previous_index, current_index, bond);
+                        self.graph.add_edge(
+                            previous_index,
+                            current_index,
+                            Bond::new(bond.bond_type(), ring_key),
+                        );
                     } else {
                         self.graph.add_edge(previous_index, current_index, {
                             let current_node = self
@@ -190,9 +309,9 @@ impl Workspace {
                                 .node_weight(previous_index)
                                 .expect("Next node is inserted into graph just now.");
                             if current_node.aromatic && next_node.aromatic {
-                                Bond::new(BondType::Aromatic, true)
+                                Bond::new(BondType::Aromatic, ring_key)
                             } else {
-                                Bond::new(BondType::Single, true)
+                                Bond::new(BondType::Single, ring_key)
                             }
                         });
                     }
@@ -203,16 +322,18 @@ impl Workspace {
                         construct_status.enter_branch();
                     }
                     ")" => {
-                        construct_status.quit_branch();
+                        construct_status.quit_branch().map_err(String::from)?;
                     }
                     _ => {
                         panic!("Unknown operator catched {}", token)
                     }
                 }
+            } else {
+                return Err(format!("Invalid token {}", token));
             }
         }
 
-        if construct_status.branch.len() != 0 {
+        if !construct_status.branch.is_empty() {
             Err(format!(
                 "Uncleaned brnach stack. Some opened stack not cloused: {:?}",
                 construct_status
@@ -221,13 +342,44 @@ impl Workspace {
                     .map(|index| self.graph.node_weight(*index).unwrap())
                     .collect::<Vec<&Atom>>()
             ))
-        } else if ring_status.waiting_to_connect.len() != 0 {
+        } else if !ring_status.waiting_to_connect.is_empty() {
             Err(format!(
                 "Some rings not closed: {:?}",
                 ring_status.waiting_to_connect
             ))
+        } else if let Some(bond) = bond_to_connect {
+            Err(format!("Bond {:?} at the end connects nothing", bond))
         } else {
-            result
+            self.apply_written_chirality(atoms, &components, &written)?;
+            Ok(components)
+        }
+    }
+
+    /// `@` and `@@` are given by the order neighbors written, turn them to
+    /// the order of `chiral_neighbors`. An implicit hydrogen follows the
+    /// atom written before the center, or it's the first one if the center
+    /// starts a component.
+    fn apply_written_chirality(
+        &mut self,
+        atoms: &[NodeIndex],
+        components: &[NodeIndex],
+        written: &HashMap<NodeIndex, Vec<Option<NodeIndex>>>,
+    ) -> Result<(), String> {
+        for atom in atoms {
+            let Some(chirality) = self.graph[*atom].chirality_type else {
+                continue;
+            };
+            let stored = self
+                .chiral_neighbors(*atom)
+                .ok_or(format!("Atom {:?} not found", atom))?;
+            let mut order = written.get(atom).cloned().unwrap_or_default();
+            if stored.contains(&None) {
+                let first = components.contains(atom);
+                order.insert(if first { 0 } else { 1 }, None);
+            }
+            self.graph[*atom].chirality_type =
+                Some(chirality.reorder(&order, &stored).unwrap_or(chirality));
         }
+        Ok(())
     }
 }
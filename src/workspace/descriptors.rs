@@ -0,0 +1,562 @@
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{
+    stable_graph::NodeIndex,
+    visit::EdgeRef,
+    Direction::{Incoming, Outgoing},
+};
+
+use crate::definitions::{bond::BondType, element::Element};
+
+use super::Workspace;
+
+/// Physicochemical descriptors of a structure
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Descriptors {
+    pub heavy_atoms: usize,
+    pub molecular_weight: f64,
+    /// nitrogens and oxygens with hydrogens
+    pub h_bond_donors: usize,
+    /// nitrogens and oxygens, except cationic and amide nitrogens and
+    /// pyrrole-like aromatic nitrogens
+    pub h_bond_acceptors: usize,
+    /// single bonds not in rings between non-terminal heavy atoms, except
+    /// amide C-N bonds and bonds next to triple bonds
+    pub rotatable_bonds: usize,
+    /// rings of the smallest set of smallest rings
+    pub rings: usize,
+    pub aromatic_rings: usize,
+    /// fraction of sp3 carbons in all carbons
+    pub fraction_sp3: f64,
+    /// topological polar surface area of Ertl et al. 2000, by nitrogens and
+    /// oxygens
+    pub tpsa: f64,
+    /// logP of Wildman and Crippen 1999
+    pub clogp: f64,
+    /// molar refractivity of Wildman and Crippen 1999
+    pub molar_refractivity: f64,
+}
+
+impl Descriptors {
+    /// Count violations of Lipinski's rule of five: molecular weight over
+    /// 500, cLogP over 5, more than 5 donors and more than 10 acceptors.
+    pub fn lipinski_violations(&self) -> usize {
+        [
+            self.molecular_weight > 500.,
+            self.clogp > 5.,
+            self.h_bond_donors > 5,
+            self.h_bond_acceptors > 10,
+        ]
+        .iter()
+        .filter(|violated| **violated)
+        .count()
+    }
+
+    /// Count violations of Veber's rules: more than 10 rotatable bonds and
+    /// TPSA over 140.
+    pub fn veber_violations(&self) -> usize {
+        [self.rotatable_bonds > 10, self.tpsa > 140.]
+            .iter()
+            .filter(|violated| **violated)
+            .count()
+    }
+}
+
+/// Heavy atom with its hydrogens and heavy neighbors
+struct HeavyAtom {
+    element: Element,
+    aromatic: bool,
+    charge: isize,
+    hydrogens: usize,
+    neighbors: Vec<(NodeIndex, BondType)>,
+}
+
+impl HeavyAtom {
+    fn count_bonds(&self, bond_type: BondType) -> usize {
+        self.neighbors
+            .iter()
+            .filter(|(_, bond)| *bond == bond_type)
+            .count()
+    }
+
+    fn has_bond(&self, bond_type: BondType) -> bool {
+        self.count_bonds(bond_type) > 0
+    }
+
+    /// single bonds, including `/` and `\`
+    fn single_bonds(&self) -> usize {
+        self.neighbors
+            .iter()
+            .filter(|(_, bond)| bond.valence() == 1 && *bond != BondType::Aromatic)
+            .count()
+    }
+}
+
+/// Here implements physicochemical descriptors
+impl Workspace {
+    /// Compute descriptors of the structure which the given atom belongs to,
+    /// hydrogens may be implicit or added as atoms.
+    pub fn descriptors(&self, structure_node: NodeIndex) -> Option<Descriptors> {
+        let atoms = self.heavy_atoms(structure_node)?;
+        let rings = self.rings(structure_node)?;
+        let mut ring_bonds = HashSet::new();
+        for ring in rings.iter() {
+            for (index, atom) in ring.iter().enumerate() {
+                let next = ring[(index + 1) % ring.len()];
+                ring_bonds.insert((*atom.min(&next), *atom.max(&next)));
+            }
+        }
+        let in_ring = |a: NodeIndex, b: NodeIndex| ring_bonds.contains(&(a.min(b), a.max(b)));
+        let mut molecular_weight = 0.;
+        for root in self.components(structure_node)? {
+            for atom in self.get_atoms_of_structure(root)? {
+                let properties = &self.graph[atom];
+                molecular_weight += match properties.isotope {
+                    Some(isotope) => isotope as f64,
+                    None => properties.element.atomic_weight(),
+                };
+                molecular_weight +=
+                    Element::H.atomic_weight() * self.implicit_hydrogen_count(atom)? as f64;
+            }
+        }
+        let mut rotatable_bonds = 0;
+        for (index, atom) in atoms.iter() {
+            for (neighbor, bond) in atom.neighbors.iter() {
+                if index < neighbor
+                    && bond.valence() == 1
+                    && *bond != BondType::Aromatic
+                    && !in_ring(*index, *neighbor)
+                    && is_rotatable(&atoms, *index, *neighbor)
+                {
+                    rotatable_bonds += 1;
+                }
+            }
+        }
+        let carbons = atoms
+            .values()
+            .filter(|atom| atom.element == Element::C)
+            .collect::<Vec<_>>();
+        let sp3 = carbons
+            .iter()
+            .filter(|atom| !atom.aromatic && atom.single_bonds() == atom.neighbors.len())
+            .count();
+        // summed in the order of atoms, so results don't vary between runs
+        let mut order = atoms.keys().copied().collect::<Vec<_>>();
+        order.sort();
+        let (clogp, molar_refractivity) = order
+            .iter()
+            .map(|index| crippen(&atoms, *index))
+            .fold((0., 0.), |(logp, mr), (atom_logp, atom_mr)| {
+                (logp + atom_logp, mr + atom_mr)
+            });
+        Some(Descriptors {
+            heavy_atoms: atoms.len(),
+            molecular_weight,
+            h_bond_donors: atoms
+                .values()
+                .filter(|atom| {
+                    matches!(atom.element, Element::N | Element::O) && atom.hydrogens > 0
+                })
+                .count(),
+            h_bond_acceptors: order
+                .iter()
+                .filter(|index| is_acceptor(&atoms, **index))
+                .count(),
+            rotatable_bonds,
+            rings: rings.len(),
+            aromatic_rings: rings
+                .iter()
+                .filter(|ring| ring.iter().all(|atom| self.graph[*atom].aromatic))
+                .count(),
+            fraction_sp3: if carbons.is_empty() {
+                0.
+            } else {
+                sp3 as f64 / carbons.len() as f64
+            },
+            tpsa: order.iter().map(|index| tpsa(&atoms, *index, &rings)).sum(),
+            clogp,
+            molar_refractivity,
+        })
+    }
+}
+
+/// Private functions used upon
+impl Workspace {
+    /// Heavy atoms of the structure with their hydrogens, implicit or not
+    fn heavy_atoms(&self, structure_node: NodeIndex) -> Option<HashMap<NodeIndex, HeavyAtom>> {
+        let mut atoms = HashMap::new();
+        for root in self.components(structure_node)? {
+            for atom in self.get_atoms_of_structure(root)? {
+                let properties = &self.graph[atom];
+                if properties.element == Element::H {
+                    continue;
+                }
+                let mut hydrogens = self.implicit_hydrogen_count(atom)?;
+                let mut neighbors = vec![];
+                for edge in self
+                    .graph
+                    .edges_directed(atom, Incoming)
+                    .chain(self.graph.edges_directed(atom, Outgoing))
+                {
+                    let other = if edge.source() == atom {
+                        edge.target()
+                    } else {
+                        edge.source()
+                    };
+                    if edge.weight().is_no_bond() {
+                        continue;
+                    } else if self.graph[other].element == Element::H {
+                        hydrogens += 1;
+                    } else {
+                        neighbors.push((other, edge.weight().bond_type()));
+                    }
+                }
+                atoms.insert(
+                    atom,
+                    HeavyAtom {
+                        element: properties.element,
+                        aromatic: properties.aromatic,
+                        charge: properties.charge,
+                        hydrogens,
+                        neighbors,
+                    },
+                );
+            }
+        }
+        Some(atoms)
+    }
+}
+
+/// If the atom is a carbon with a double bond to oxygen or sulfur
+fn is_carbonyl(atoms: &HashMap<NodeIndex, HeavyAtom>, index: NodeIndex) -> bool {
+    let atom = &atoms[&index];
+    atom.element == Element::C
+        && atom.neighbors.iter().any(|(neighbor, bond)| {
+            *bond == BondType::Double && matches!(atoms[neighbor].element, Element::O | Element::S)
+        })
+}
+
+fn is_amide_nitrogen(atoms: &HashMap<NodeIndex, HeavyAtom>, index: NodeIndex) -> bool {
+    let atom = &atoms[&index];
+    atom.element == Element::N
+        && !atom.aromatic
+        && atom
+            .neighbors
+            .iter()
+            .any(|(neighbor, bond)| bond.valence() == 1 && is_carbonyl(atoms, *neighbor))
+}
+
+fn is_acceptor(atoms: &HashMap<NodeIndex, HeavyAtom>, index: NodeIndex) -> bool {
+    let atom = &atoms[&index];
+    match atom.element {
+        Element::O => true,
+        Element::N => {
+            atom.charge <= 0
+                && !(atom.aromatic && atom.hydrogens > 0)
+                && !is_amide_nitrogen(atoms, index)
+        }
+        _ => false,
+    }
+}
+
+fn is_rotatable(atoms: &HashMap<NodeIndex, HeavyAtom>, a: NodeIndex, b: NodeIndex) -> bool {
+    let (atom_a, atom_b) = (&atoms[&a], &atoms[&b]);
+    let terminal = atom_a.neighbors.len() < 2 || atom_b.neighbors.len() < 2;
+    let next_to_triple = atom_a.has_bond(BondType::Triple) || atom_b.has_bond(BondType::Triple);
+    let amide = (is_amide_nitrogen(atoms, a) && is_carbonyl(atoms, b))
+        || (is_amide_nitrogen(atoms, b) && is_carbonyl(atoms, a));
+    !terminal && !next_to_triple && !amide
+}
+
+/// Ertl contribution of a nitrogen or oxygen, others contribute nothing
+fn tpsa(atoms: &HashMap<NodeIndex, HeavyAtom>, index: NodeIndex, rings: &[Vec<NodeIndex>]) -> f64 {
+    let atom = &atoms[&index];
+    let single = atom.single_bonds();
+    let double = atom.count_bonds(BondType::Double);
+    let triple = atom.count_bonds(BondType::Triple);
+    let aromatic = atom.count_bonds(BondType::Aromatic);
+    let in_3_ring = rings
+        .iter()
+        .any(|ring| ring.len() == 3 && ring.contains(&index));
+    let contribution = match (atom.element, atom.hydrogens, atom.charge) {
+        (Element::N, 0, 0) => match (single, double, triple, aromatic) {
+            (3, 0, 0, 0) if in_3_ring => Some(3.01),
+            (3, 0, 0, 0) => Some(3.24),
+            (1, 1, 0, 0) => Some(12.36),
+            (0, 0, 1, 0) => Some(23.79),
+            (1, 2, 0, 0) => Some(11.68),
+            (0, 1, 1, 0) => Some(13.6),
+            (0, 0, 0, 2) => Some(12.89),
+            (0, 0, 0, 3) => Some(4.41),
+            (1, 0, 0, 2) => Some(4.93),
+            (0, 1, 0, 2) => Some(8.39),
+            _ => None,
+        },
+        (Element::N, 1, 0) => match (single, double, aromatic) {
+            (2, 0, 0) if in_3_ring => Some(21.94),
+            (2, 0, 0) => Some(12.03),
+            (0, 1, 0) => Some(23.85),
+            (0, 0, 2) => Some(15.79),
+            _ => None,
+        },
+        (Element::N, 2, 0) if single == 1 => Some(26.02),
+        (Element::N, 0, 1) => match (single, double, triple, aromatic) {
+            (4, 0, 0, 0) => Some(0.),
+            (2, 1, 0, 0) => Some(3.01),
+            (1, 0, 1, 0) => Some(4.36),
+            (0, 0, 0, 3) => Some(4.1),
+            (1, 0, 0, 2) => Some(3.88),
+            _ => None,
+        },
+        (Element::N, 1, 1) => match (single, double, aromatic) {
+            (3, 0, 0) => Some(4.44),
+            (1, 1, 0) => Some(13.97),
+            (0, 0, 2) => Some(14.14),
+            _ => None,
+        },
+        (Element::N, 2, 1) => match (single, double) {
+            (2, 0) => Some(16.61),
+            (0, 1) => Some(25.59),
+            _ => None,
+        },
+        (Element::N, 3, 1) if single == 1 => Some(27.64),
+        (Element::O, 0, 0) => match (single, double, aromatic) {
+            (2, 0, 0) if in_3_ring => Some(12.53),
+            (2, 0, 0) => Some(9.23),
+            (0, 1, 0) => Some(17.07),
+            (0, 0, 2) => Some(13.14),
+            _ => None,
+        },
+        (Element::O, 1, 0) if single == 1 => Some(20.23),
+        (Element::O, 0, -1) if single == 1 => Some(23.06),
+        (Element::N, _, _) | (Element::O, _, _) => None,
+        _ => Some(0.),
+    };
+    // unlisted nitrogens and oxygens are estimated by their neighbors
+    contribution.unwrap_or_else(|| {
+        let neighbors = atom.neighbors.len() as f64;
+        let hydrogens = atom.hydrogens as f64;
+        match atom.element {
+            Element::N => 30.5 - neighbors * 8.2 + hydrogens * 1.5,
+            _ => 28.5 - neighbors * 8.6 + hydrogens * 1.5,
+        }
+    })
+}
+
+/// Wildman-Crippen contributions of logP and molar refractivity of a heavy
+/// atom and its hydrogens. Atom types are matched by their neighbors instead
+/// of SMARTS, elements not typed contribute nothing.
+fn crippen(atoms: &HashMap<NodeIndex, HeavyAtom>, index: NodeIndex) -> (f64, f64) {
+    let atom = &atoms[&index];
+    let neighbors = atom
+        .neighbors
+        .iter()
+        .map(|(neighbor, bond)| (&atoms[neighbor], *bond))
+        .collect::<Vec<_>>();
+    let aliphatic_hetero = |other: &HeavyAtom| {
+        !other.aromatic
+            && matches!(
+                other.element,
+                Element::N
+                    | Element::O
+                    | Element::P
+                    | Element::S
+                    | Element::F
+                    | Element::Cl
+                    | Element::Br
+                    | Element::I
+            )
+    };
+    let all_aliphatic = neighbors.iter().all(|(other, _)| !other.aromatic);
+    let has_aromatic = !all_aliphatic;
+    let halogen =
+        |element: Element| matches!(element, Element::F | Element::Cl | Element::Br | Element::I);
+    let heavy = match (atom.element, atom.aromatic) {
+        (Element::C, true) => {
+            let exocyclic = neighbors
+                .iter()
+                .find(|(_, bond)| *bond != BondType::Aromatic);
+            match exocyclic {
+                Some((other, _)) if other.element == Element::F => (0., 3.257),
+                Some((other, _)) if other.element == Element::Cl => (0.245, 3.564),
+                Some((other, _)) if other.element == Element::Br => (0.198, 3.18),
+                Some((other, _)) if other.element == Element::I => (0., 3.104),
+                Some((other, BondType::Double))
+                    if matches!(other.element, Element::C | Element::N | Element::O) =>
+                {
+                    (-0.8186, 3.135)
+                }
+                Some((other, _)) if other.aromatic => (0.2713, 3.904),
+                Some((other, _)) => match other.element {
+                    Element::C => (0.136, 3.509),
+                    Element::N => (0.4619, 3.067),
+                    Element::O => (0.5437, 3.853),
+                    Element::S => (0.1893, 2.673),
+                    _ => (-0.5443, 4.041),
+                },
+                None if atom.hydrogens > 0 => (0.1581, 3.35),
+                None => (0.2955, 4.346),
+            }
+        }
+        (Element::C, false) if atom.charge == 0 => {
+            let double_hetero = neighbors
+                .iter()
+                .any(|(other, bond)| *bond == BondType::Double && other.element != Element::C);
+            let double_carbon = neighbors
+                .iter()
+                .any(|(other, bond)| *bond == BondType::Double && other.element == Element::C);
+            let has_hetero = neighbors.iter().any(|(other, _)| aliphatic_hetero(other));
+            let all_carbon = neighbors
+                .iter()
+                .all(|(other, _)| other.element == Element::C && !other.aromatic);
+            if double_hetero {
+                (-0.2783, 5.007)
+            } else if double_carbon && has_aromatic {
+                (0.264, 4.305)
+            } else if double_carbon {
+                (0.1551, 3.513)
+            } else if atom.has_bond(BondType::Triple) {
+                (0.0017, 3.888)
+            } else if atom.single_bonds() != atom.neighbors.len() {
+                (0.08129, 3.243)
+            } else if atom.hydrogens >= 2 {
+                if atom.hydrogens == 4 || all_carbon {
+                    (0.1441, 2.503)
+                } else if has_hetero && all_aliphatic {
+                    (-0.2035, 2.753)
+                } else if atom.hydrogens == 3 && neighbors[0].0.element == Element::C {
+                    (0.08452, 2.464)
+                } else if atom.hydrogens == 3 && has_aromatic {
+                    (-0.1444, 2.412)
+                } else if has_aromatic {
+                    (-0.0516, 2.488)
+                } else {
+                    (0.2148, 2.693)
+                }
+            } else if all_carbon {
+                (0., 2.433)
+            } else if has_hetero && all_aliphatic {
+                (-0.2051, 2.731)
+            } else if has_aromatic && atom.hydrogens == 1 {
+                (0.1193, 2.582)
+            } else if has_aromatic {
+                (-0.0967, 2.576)
+            } else {
+                (0.2148, 2.693)
+            }
+        }
+        (Element::C, _) => (0.08129, 3.243),
+        (Element::N, true) if atom.charge > 0 => (-1.119, 0.),
+        (Element::N, true) => (-0.3239, 2.202),
+        (Element::N, false) if atom.charge > 0 => {
+            if atom.hydrogens > 0 {
+                (-1.95, 0.)
+            } else if atom.has_bond(BondType::Triple) {
+                (0.2887, 3.359)
+            } else {
+                (-0.3396, 0.2604)
+            }
+        }
+        (Element::N, false) if atom.charge < 0 => (0.2887, 3.359),
+        (Element::N, false) => {
+            let double = atom.has_bond(BondType::Double);
+            match atom.hydrogens {
+                2 if has_aromatic => (-1.027, 2.827),
+                2 => (-1.019, 2.262),
+                1 if double => (0.08387, 1.757),
+                1 if has_aromatic => (-0.5188, 3.),
+                1 => (-0.7096, 2.173),
+                0 if atom.has_bond(BondType::Triple) => (0.01508, 1.725),
+                0 if double => (0.1836, 2.428),
+                0 if has_aromatic => (-0.4458, 2.819),
+                0 => (-0.3187, 1.839),
+                _ => (-0.4806, 2.134),
+            }
+        }
+        (Element::O, true) => (0.1552, 1.08),
+        (Element::O, false) if atom.charge < 0 => match neighbors.first() {
+            Some((other, _)) if other.element == Element::N => (0.0335, 3.367),
+            Some((other, _)) if other.element == Element::S => (-0.3339, 0.7774),
+            Some(_) if atom.neighbors.len() == 1 && is_carbonyl(atoms, atom.neighbors[0].0) => {
+                (-1.326, 0.)
+            }
+            _ => (-1.189, 0.),
+        },
+        (Element::O, false) if atom.charge > 0 => (-0.1188, 0.6865),
+        (Element::O, false) => match neighbors.as_slice() {
+            [(other, BondType::Double)] => match other.element {
+                Element::N | Element::O => (0.0335, 3.367),
+                Element::S => (-0.3339, 0.7774),
+                Element::C if other.aromatic => (0.1788, 3.135),
+                Element::C => {
+                    let others = other
+                        .neighbors
+                        .iter()
+                        .filter(|(neighbor, _)| *neighbor != index)
+                        .map(|(neighbor, _)| &atoms[neighbor])
+                        .collect::<Vec<_>>();
+                    if others.iter().any(|other| other.aromatic) {
+                        (0.1129, 0.2215)
+                    } else if others.len() == 2
+                        && others.iter().all(|other| other.element != Element::C)
+                    {
+                        (0.4833, 0.389)
+                    } else {
+                        (-0.1526, 0.)
+                    }
+                }
+                _ => (-0.1188, 0.6865),
+            },
+            _ if atom.hydrogens > 0 => (-0.2893, 0.8238),
+            [_, _] if has_aromatic => (-0.4195, 1.182),
+            [_, _] => (-0.0684, 1.085),
+            _ => (-0.1188, 0.6865),
+        },
+        (element, _) if halogen(element) && atom.charge != 0 => (-2.996, 0.),
+        (Element::F, _) => (0.4202, 1.108),
+        (Element::Cl, _) => (0.6895, 5.853),
+        (Element::Br, _) => (0.8456, 8.927),
+        (Element::I, _) => (0.8857, 14.02),
+        (Element::P, _) => (0.8612, 6.92),
+        (Element::S, true) => (0.6237, 6.691),
+        (Element::S, false) if atom.charge != 0 => (-0.0024, 7.365),
+        (Element::S, false) => (0.6482, 7.591),
+        _ => (0., 0.),
+    };
+    let hydrogen = match atom.element {
+        Element::C => (0.123, 1.057),
+        Element::N => (0.2142, 0.9627),
+        Element::O => {
+            let acidic = atom.neighbors.iter().any(|(neighbor, _)| {
+                let other = &atoms[neighbor];
+                matches!(other.element, Element::O | Element::S)
+                    || other.element == Element::C
+                        && other.neighbors.iter().any(|(next, bond)| {
+                            *bond == BondType::Double
+                                && matches!(
+                                    atoms[next].element,
+                                    Element::C | Element::N | Element::O | Element::S
+                                )
+                        })
+            });
+            if atom
+                .neighbors
+                .iter()
+                .any(|(neighbor, _)| atoms[neighbor].element == Element::N)
+            {
+                (0.2142, 0.9627)
+            } else if acidic {
+                (0.298, 1.805)
+            } else {
+                (-0.2677, 1.395)
+            }
+        }
+        _ => (-0.2677, 1.395),
+    };
+    let hydrogens = atom.hydrogens as f64;
+    (
+        heavy.0 + hydrogen.0 * hydrogens,
+        heavy.1 + hydrogen.1 * hydrogens,
+    )
+}
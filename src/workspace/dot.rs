@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+
+use petgraph::{stable_graph::NodeIndex, visit::EdgeRef};
+
+use super::Workspace;
+use crate::definitions::bond::{Bond, BondType};
+
+/// What `to_dot` writes
+#[derive(Debug, Clone)]
+pub struct DotOptions {
+    /// write NodeIndex of atoms
+    pub show_indexes: bool,
+    pub show_charge: bool,
+    pub show_isotope: bool,
+    /// write hydrogens which are not atoms, like `CH3`
+    pub show_hydrogens: bool,
+    pub show_chirality: bool,
+    pub show_selector: bool,
+    /// color ring-closure, aromatic and stereo bonds
+    pub color_bonds: bool,
+    /// put atoms of each registered structure in a cluster
+    pub cluster_structures: bool,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self {
+            show_indexes: true,
+            show_charge: true,
+            show_isotope: true,
+            show_hydrogens: true,
+            show_chirality: true,
+            show_selector: true,
+            color_bonds: true,
+            cluster_structures: true,
+        }
+    }
+}
+
+/// Here implements DOT (Graphviz) output of the directed trees
+impl Workspace {
+    /// write all atoms in the workspace as DOT, edges keep their directions
+    /// in the graph.
+    pub fn to_dot(&self, options: &DotOptions) -> String {
+        let atoms = self.graph.node_indices().collect::<Vec<_>>();
+        self.write_dot(&atoms, options)
+    }
+
+    /// write the structure which the given atom belongs to as DOT
+    pub fn structure_to_dot(
+        &self,
+        structure_node: NodeIndex,
+        options: &DotOptions,
+    ) -> Option<String> {
+        let mut atoms = vec![];
+        for root in self.components(structure_node)? {
+            atoms.append(&mut self.get_atoms_of_structure(root)?);
+        }
+        Some(self.write_dot(&atoms, options))
+    }
+}
+
+/// Private functions used upon
+impl Workspace {
+    fn write_dot(&self, atoms: &[NodeIndex], options: &DotOptions) -> String {
+        let included = atoms.iter().copied().collect::<HashSet<_>>();
+        let mut dot = String::from("digraph {\n    node [shape=circle];\n");
+        let mut written = HashSet::new();
+        if options.cluster_structures {
+            for (index, id) in self.structures().enumerate() {
+                let members = self
+                    .structure_atoms(id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|atom| included.contains(atom))
+                    .collect::<Vec<_>>();
+                if members.is_empty() {
+                    continue;
+                }
+                let label = match self.structure_name(id) {
+                    Some(name) => name.to_string(),
+                    None => format!("{:?}", id),
+                };
+                dot.push_str(&format!(
+                    "    subgraph cluster_{} {{\n        label=\"{}\";\n",
+                    index,
+                    escape(&label)
+                ));
+                for atom in members {
+                    dot.push_str(&format!("    {}", self.dot_node(atom, options)));
+                    written.insert(atom);
+                }
+                dot.push_str("    }\n");
+            }
+        }
+        for atom in atoms.iter().filter(|atom| !written.contains(*atom)) {
+            dot.push_str(&self.dot_node(*atom, options));
+        }
+        for atom in atoms {
+            for edge in self.graph.edges(*atom) {
+                if included.contains(&edge.target()) {
+                    dot.push_str(&format!(
+                        "    {} -> {} [{}];\n",
+                        edge.source().index(),
+                        edge.target().index(),
+                        dot_edge_attributes(edge.weight(), options)
+                    ));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn dot_node(&self, node: NodeIndex, options: &DotOptions) -> String {
+        let atom = &self.graph[node];
+        let mut label = String::new();
+        if options.show_isotope {
+            if let Some(isotope) = atom.isotope {
+                label.push_str(&isotope.to_string());
+            }
+        }
+        label.push_str(atom.element.as_ref());
+        if options.show_chirality {
+            if let Some(chirality) = atom.chirality_type {
+                label.push_str(chirality.as_str());
+            }
+        }
+        if options.show_hydrogens {
+            match self.implicit_hydrogen_count(node).unwrap_or_default() {
+                0 => {}
+                1 => label.push('H'),
+                count => label.push_str(&format!("H{}", count)),
+            }
+        }
+        if options.show_charge {
+            match atom.charge {
+                0 => {}
+                1 => label.push('+'),
+                -1 => label.push('-'),
+                charge if charge > 0 => label.push_str(&format!("+{}", charge)),
+                charge => label.push_str(&charge.to_string()),
+            }
+        }
+        if options.show_selector {
+            if let Some(selector) = &atom.selector {
+                label.push_str(&format!("\\n{{{}}}", escape(selector)));
+            }
+        }
+        if options.show_indexes {
+            label.push_str(&format!("\\n#{}", node.index()));
+        }
+        let style = if atom.aromatic { ", style=dashed" } else { "" };
+        format!("    {} [label=\"{}\"{}];\n", node.index(), label, style)
+    }
+}
+
+fn dot_edge_attributes(bond: &Bond, options: &DotOptions) -> String {
+    let mut attributes = vec![];
+    let symbol = match bond.bond_type() {
+        BondType::Single => "",
+        _ => bond.as_str(),
+    };
+    if !symbol.is_empty() {
+        attributes.push(format!("label=\"{}\"", escape(symbol)));
+    }
+    if options.color_bonds {
+        let color = if bond.is_ring_bond() {
+            Some("blue")
+        } else {
+            match bond.bond_type() {
+                BondType::Aromatic => Some("purple"),
+                BondType::UpSingle | BondType::DownSingle => Some("darkgreen"),
+                _ => None,
+            }
+        };
+        if let Some(color) = color {
+            attributes.push(format!("color={}", color));
+            attributes.push(format!("fontcolor={}", color));
+        }
+    }
+    if bond.is_ring_bond() {
+        attributes.push("style=dashed".to_string());
+        // ring-closure bonds are not a part of the tree
+        attributes.push("constraint=false".to_string());
+    }
+    match bond.bond_type() {
+        BondType::Double => attributes.push("penwidth=2".to_string()),
+        BondType::Triple | BondType::Quad => attributes.push("penwidth=3".to_string()),
+        _ => {}
+    }
+    attributes.join(", ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -1,3 +1,5 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
 use petgraph::{
     stable_graph::{EdgeIndex, NodeIndex},
     Direction::Incoming,
@@ -6,14 +8,42 @@ use petgraph::{
 use crate::definitions::{
     atom::Atom,
     bond::{Bond, BondType},
+    chirality::ChiralityType,
+    element::Element,
 };
 
 use super::Workspace;
 
+/// How to fill the valence left by a broken bond
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capping {
+    /// add hydrogens to both atoms as explicit hydrogen counts, or as `[H]`
+    /// atoms if the atom has some
+    Hydrogen,
+    /// bond a `*` atom to each side with the same bond type
+    Dummy,
+}
+
+/// Result of breaking a bond
+#[derive(Debug)]
+pub struct Cleavage {
+    /// if the structure falls into two components
+    pub split: bool,
+    /// roots of both sides, they're the same if not split
+    pub roots: (NodeIndex, NodeIndex),
+    /// `*` atoms added by capping
+    pub caps: Vec<NodeIndex>,
+}
+
 impl Workspace {
     /// reset the root of a structure.
     pub fn reset_root(&mut self, root_index: NodeIndex) -> Option<()> {
-        self.reverse_recursive(root_index, None)
+        let old_root = self.find_root_of(root_index)?;
+        self.reverse_path(root_index)?;
+        if let Some(id) = self.root_structures.remove(&old_root) {
+            self.root_structures.insert(root_index, id);
+        }
+        Some(())
     }
 
     pub fn add_hydrogen_to_structure(&mut self, node: NodeIndex) -> Option<()> {
@@ -32,17 +62,273 @@ impl Workspace {
         bond_type: BondType,
     ) -> Option<EdgeIndex> {
         let in_same_structure = self.in_same_structure(&[outgoing_from, incoming_to]);
-        self.reset_root(incoming_to)?;
+        if !in_same_structure {
+            self.leave_structure(incoming_to);
+            self.reset_root(incoming_to)?;
+        }
         Some(self.graph.add_edge(
             outgoing_from,
             incoming_to,
             Bond::new(bond_type, in_same_structure),
         ))
     }
+
+    /// Parse a fragment given in SwS and bond it to the site, `attach_atom` is
+    /// the position of the bonding atom in the fragment SMILES.
+    ///
+    /// The new bond takes the place of hydrogens on both sides: if the site or
+    /// the bonding atom is a hydrogen atom, it's removed and its neighbor is
+    /// bonded instead, otherwise their hydrogen count is used. Valence of both
+    /// sides is checked before anything is changed. Returns the bonded atom of
+    /// the fragment.
+    pub fn substitute(
+        &mut self,
+        site: NodeIndex,
+        fragment_sws: &str,
+        attach_atom: usize,
+        bond_type: BondType,
+    ) -> Result<NodeIndex, String> {
+        let valence = bond_type.valence();
+        let (site, site_hydrogen) = self.find_substitution_site(site, valence)?;
+        let (fragment, fragment_atoms) = self.add_structure_atoms(fragment_sws)?;
+        let checked = if self.structure_roots(fragment).map(|roots| roots.len()) != Some(1) {
+            Err(format!(
+                "Fragment {} shall have exactly one component",
+                fragment_sws
+            ))
+        } else if let Some(attach) = fragment_atoms.get(attach_atom) {
+            self.find_substitution_site(*attach, valence)
+        } else {
+            Err(format!(
+                "Fragment {} has no atom at position {}",
+                fragment_sws, attach_atom
+            ))
+        };
+        let (attach, attach_hydrogen) = match checked {
+            Ok(checked) => checked,
+            Err(err) => {
+                self.remove_structure(fragment);
+                return Err(err);
+            }
+        };
+        self.take_hydrogens(site, site_hydrogen, valence)?;
+        self.take_hydrogens(attach, attach_hydrogen, valence)?;
+        self.connect(site, attach, bond_type)
+            .ok_or(format!("Failed to connect {:?} and {:?}", site, attach))?;
+        Ok(attach)
+    }
+
+    /// Remove the bond between two atoms. If the structure falls into two
+    /// components, they're still in the same registered structure, otherwise
+    /// the bond was in a ring and a ring bond becomes a normal one to keep the
+    /// structure a tree. Stereo centers at both ends keep their configuration
+    /// with the cap in place of the lost neighbor, they lose it if not capped.
+    pub fn break_bond(
+        &mut self,
+        atom_a: NodeIndex,
+        atom_b: NodeIndex,
+        capping: Option<Capping>,
+    ) -> Option<Cleavage> {
+        let structure = self.structure_of(atom_a);
+        let (bond, edge, _) = self.get_edge_undirected(atom_a, atom_b)?;
+        let bond = *bond;
+        let (_, child) = self.graph.edge_endpoints(edge)?;
+        let hydrogens = [
+            self.implicit_hydrogen_count(atom_a)?,
+            self.implicit_hydrogen_count(atom_b)?,
+        ];
+        // stereo centers whose neighbors change, as they were before
+        let mut stereo = HashMap::new();
+        for atom in [atom_a, atom_b] {
+            if let Some(center) = self.stereo_center(atom) {
+                stereo.insert(atom, center);
+            }
+        }
+        self.graph.remove_edge(edge);
+        let split = if bond.is_ring_bond() {
+            false
+        } else {
+            let subtree = self
+                .get_atoms_of_structure(child)?
+                .into_iter()
+                .collect::<HashSet<_>>();
+            let ring_bond = subtree.iter().find_map(|inside| {
+                self.graph
+                    .neighbors_undirected(*inside)
+                    .find(|outside| !subtree.contains(outside))
+                    .map(|outside| (*inside, outside))
+            });
+            if let Some((inside, outside)) = ring_bond {
+                // the other path of ring keeps the structure connected
+                let (ring_bond, ring_edge, _) = self.get_edge_undirected(inside, outside)?;
+                let (from, _) = self.graph.edge_endpoints(ring_edge)?;
+                let ring_bond = Bond::new(ring_bond.bond_type(), false);
+                for atom in [inside, outside] {
+                    if let Entry::Vacant(entry) = stereo.entry(atom) {
+                        if let Some(center) = self.stereo_center(atom) {
+                            entry.insert(center);
+                        }
+                    }
+                }
+                self.graph.remove_edge(ring_edge);
+                self.reset_root(inside)?;
+                self.graph.add_edge(
+                    outside,
+                    inside,
+                    if from == outside {
+                        ring_bond
+                    } else {
+                        ring_bond.reverse()
+                    },
+                );
+                false
+            } else {
+                if let Some(structure) = structure {
+                    self.add_anchor(structure, atom_a);
+                    self.add_anchor(structure, atom_b);
+                }
+                true
+            }
+        };
+        let mut caps = vec![];
+        // atoms taking the place of the lost neighbor in stereo centers,
+        // None for an implicit hydrogen
+        let mut replacements = [None, None];
+        match capping {
+            Some(Capping::Hydrogen) => {
+                let valence = bond.bond_type().valence();
+                for (index, atom) in [atom_a, atom_b].into_iter().enumerate() {
+                    if self.has_hydrogen_atoms(atom) {
+                        let added = (0..valence)
+                            .map(|_| {
+                                self.connect_new_atom(
+                                    Atom::new("[H]").unwrap(),
+                                    atom,
+                                    BondType::Single,
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        replacements[index] = added.first().copied();
+                    } else {
+                        self.get_atom_mut(atom)?.explicit_hydrogen = hydrogens[index] + valence;
+                    }
+                }
+            }
+            Some(Capping::Dummy) => {
+                for (index, atom) in [atom_a, atom_b].into_iter().enumerate() {
+                    let cap =
+                        self.connect_new_atom(Atom::new("*").unwrap(), atom, bond.bond_type());
+                    replacements[index] = Some(cap);
+                    caps.push(cap);
+                }
+            }
+            None => {}
+        }
+        // without capping the ends are no longer stereo centers
+        let mut orders = vec![];
+        for (atom, (chirality_type, mut order)) in stereo {
+            for (end, lost, replacement) in [
+                (atom_a, atom_b, replacements[0]),
+                (atom_b, atom_a, replacements[1]),
+            ] {
+                if atom == end && capping.is_some() {
+                    for neighbor in order.iter_mut() {
+                        if *neighbor == Some(lost) {
+                            *neighbor = replacement;
+                        }
+                    }
+                }
+            }
+            self.get_atom_mut(atom)?.chirality_type = Some(chirality_type);
+            orders.push((atom, order));
+        }
+        self.reorder_chirality(orders);
+        Some(Cleavage {
+            split,
+            roots: (self.find_root_of(atom_a)?, self.find_root_of(atom_b)?),
+            caps,
+        })
+    }
+
+    /// Remove all components except the largest fragment from the structure
+    /// which the given atom belongs to, returns root of the kept one.
+    pub fn strip_salts(&mut self, structure_node: NodeIndex) -> Option<NodeIndex> {
+        let largest = self.largest_fragment(structure_node)?;
+        for root in self.components(structure_node)? {
+            if root != largest {
+                for atom in self.get_atoms_of_structure(root)? {
+                    self.graph.remove_node(atom);
+                }
+            }
+        }
+        self.prune_structures();
+        Some(largest)
+    }
 }
 
 /// Implement private functions used upon
 impl Workspace {
+    /// Connect given atoms by given bonds as directed trees, each tree grows
+    /// from the first unvisited atom in order by a depth-first search, bonds
+    /// not used by trees become ring bonds. Returns roots of trees.
+    pub(super) fn build_forest(
+        &mut self,
+        atoms: &[NodeIndex],
+        bonds: &[(NodeIndex, NodeIndex, Bond)],
+    ) -> Vec<NodeIndex> {
+        let mut adjacency: HashMap<NodeIndex, Vec<usize>> = HashMap::new();
+        for (index, (from, to, _)) in bonds.iter().enumerate() {
+            adjacency.entry(*from).or_default().push(index);
+            adjacency.entry(*to).or_default().push(index);
+        }
+        let mut visited_order: HashMap<NodeIndex, usize> = HashMap::new();
+        let mut tree_bonds = HashSet::new();
+        let mut roots = vec![];
+        for atom in atoms {
+            if visited_order.contains_key(atom) {
+                continue;
+            }
+            roots.push(*atom);
+            let mut stack: Vec<(NodeIndex, Option<usize>)> = vec![(*atom, None)];
+            while let Some((current, via)) = stack.pop() {
+                if visited_order.contains_key(&current) {
+                    continue;
+                }
+                visited_order.insert(current, visited_order.len());
+                if let Some(bond_index) = via {
+                    let (from, to, bond) = bonds[bond_index];
+                    tree_bonds.insert(bond_index);
+                    if to == current {
+                        self.graph
+                            .add_edge(from, to, Bond::new(bond.bond_type(), false));
+                    } else {
+                        self.graph
+                            .add_edge(to, from, Bond::new(bond.bond_type(), false).reverse());
+                    }
+                }
+                for bond_index in adjacency.get(&current).into_iter().flatten().rev() {
+                    let (from, to, _) = bonds[*bond_index];
+                    let next = if from == current { to } else { from };
+                    if !visited_order.contains_key(&next) {
+                        stack.push((next, Some(*bond_index)));
+                    }
+                }
+            }
+        }
+        for (index, (from, to, bond)) in bonds.iter().enumerate() {
+            if tree_bonds.contains(&index) {
+                continue;
+            }
+            let bond = Bond::new(bond.bond_type(), true);
+            if visited_order[from] <= visited_order[to] {
+                self.graph.add_edge(*from, *to, bond);
+            } else {
+                self.graph.add_edge(*to, *from, bond.reverse());
+            }
+        }
+        roots
+    }
+
     /// Find a chemical bond and reverse it direction in graph.
     /// This shall not influcence the true structure
     /// but the support of @ chirality is still WIP.
@@ -58,24 +344,83 @@ impl Workspace {
         }
     }
 
-    /// Reverse the direction from the given node recurvisely.
-    /// Recurse will stop if there is no incoming edge or found a ring-key.
-    fn reverse_recursive(&mut self, current: NodeIndex, last: Option<NodeIndex>) -> Option<()> {
-        let incoming_edges = self
-            .graph
-            .neighbors_directed(current, Incoming)
-            .collect::<Vec<_>>();
-        for neighbor in incoming_edges {
-            if !(Some(neighbor) == last) {
-                // 当reverse_bond函数返回true时，这个键没有形成环，则向下递归
+    /// Reverse the direction of bonds from the given node to the old root.
+    /// It stops if there is no incoming edge or found a ring-key. Stereo
+    /// centers on the path are reordered for their new parents.
+    fn reverse_path(&mut self, root: NodeIndex) -> Option<()> {
+        let mut orders = HashMap::new();
+        let mut stack = vec![(root, None)];
+        while let Some((current, last)) = stack.pop() {
+            let incoming_edges = self
+                .graph
+                .neighbors_directed(current, Incoming)
+                .collect::<Vec<_>>();
+            for neighbor in incoming_edges {
+                if Some(neighbor) == last {
+                    continue;
+                }
+                for atom in [neighbor, current] {
+                    orders
+                        .entry(atom)
+                        .or_insert_with(|| self.stereo_order(atom));
+                }
+                // 当reverse_bond函数返回true时，这个键没有形成环，则继续向上反转
                 if self.reverse_bond(neighbor, current)? {
-                    self.reverse_recursive(neighbor, Some(current))?;
+                    stack.push((neighbor, Some(current)));
                 }
             }
         }
+        self.reorder_chirality(
+            orders
+                .into_iter()
+                .filter_map(|(atom, order)| Some((atom, order?))),
+        );
         Some(())
     }
 
+    /// Neighbors of a stereo center in the order its chirality refers to,
+    /// None if the atom has no chirality.
+    pub(super) fn stereo_order(&self, atom: NodeIndex) -> Option<Vec<Option<NodeIndex>>> {
+        self.get_atom(atom)?.chirality_type?;
+        self.chiral_neighbors(atom)
+    }
+
+    /// Get chirality of a stereo center with the order of neighbors it
+    /// refers to.
+    fn stereo_center(&self, atom: NodeIndex) -> Option<(ChiralityType, Vec<Option<NodeIndex>>)> {
+        Some((
+            self.get_atom(atom)?.chirality_type?,
+            self.chiral_neighbors(atom)?,
+        ))
+    }
+
+    /// Check if an atom is bonded to hydrogens given as atoms
+    fn has_hydrogen_atoms(&self, atom: NodeIndex) -> bool {
+        self.graph
+            .neighbors_undirected(atom)
+            .any(|neighbor| self.graph[neighbor].element == Element::H)
+    }
+
+    /// Reorder chirality of stereo centers for their current neighbors,
+    /// given the orders taken by `stereo_order` before bonds were changed.
+    /// A center whose neighbors are not the same ones loses its chirality.
+    pub(super) fn reorder_chirality(
+        &mut self,
+        orders: impl IntoIterator<Item = (NodeIndex, Vec<Option<NodeIndex>>)>,
+    ) {
+        for (atom, order) in orders {
+            let neighbors = self.chiral_neighbors(atom);
+            if let Some(atom) = self.get_atom_mut(atom) {
+                atom.chirality_type =
+                    atom.chirality_type
+                        .zip(neighbors)
+                        .and_then(|(chirality_type, neighbors)| {
+                            chirality_type.reorder(&order, &neighbors)
+                        });
+            }
+        }
+    }
+
     /// Add a new atom and connect it to an existed atom.
     fn connect_new_atom(
         &mut self,
@@ -84,35 +429,74 @@ impl Workspace {
         bond_type: BondType,
     ) -> NodeIndex {
         let new_node = self.graph.add_node(atom);
-        self.connect(connect_to, new_node, bond_type);
+        self.graph
+            .add_edge(connect_to, new_node, Bond::new(bond_type, false));
         new_node
     }
 
-    /// Add hydrogen atoms to an existed atom.
-    fn add_hydrogen_to_atom(&mut self, atom: NodeIndex) -> Option<Vec<NodeIndex>> {
-        let node = self.get_atom_mut(atom)?;
-        let explicit_hydrogen = node.explicit_hydrogen;
-        node.explicit_hydrogen = 0;
-        let hydrogens_to_add = if explicit_hydrogen != 0 {
-            explicit_hydrogen
-        } else if node.element.default_hydrogen() == 0 {
-            0
-        } else {
-            let default_hydrogens = node.element.default_hydrogen();
-            let aromatic = if node.aromatic { 1 } else { 0 };
-            let charge = node.charge;
-            let neighbors = self
-                .graph
-                .neighbors_undirected(atom)
-                .collect::<Vec<_>>()
-                .len() as isize;
-            let need_to_add = (default_hydrogens as isize) + charge - neighbors - aromatic;
-            if need_to_add >= 0 {
-                need_to_add as usize
+    /// Find the atom to bond for a substitution at the site and the hydrogen
+    /// atom to be replaced, and check if there are enough hydrogens.
+    fn find_substitution_site(
+        &self,
+        site: NodeIndex,
+        valence: usize,
+    ) -> Result<(NodeIndex, Option<NodeIndex>), String> {
+        let atom = self
+            .get_atom(site)
+            .ok_or(format!("Atom {:?} not found", site))?;
+        let (heavy, hydrogen) = if atom.element == Element::H {
+            let neighbors = self.graph.neighbors_undirected(site).collect::<Vec<_>>();
+            if let [heavy] = neighbors[..] {
+                (heavy, Some(site))
             } else {
-                0
+                return Err(format!(
+                    "Hydrogen atom {:?} to replace shall have exactly one neighbor",
+                    site
+                ));
             }
+        } else {
+            (site, None)
         };
+        let available = self
+            .implicit_hydrogen_count(heavy)
+            .ok_or(format!("Atom {:?} not found", heavy))?
+            + if hydrogen.is_some() { 1 } else { 0 };
+        if available < valence {
+            Err(format!(
+                "Atom {:?} has {} hydrogens, a bond with valence {} can't be made",
+                heavy, available, valence
+            ))
+        } else {
+            Ok((heavy, hydrogen))
+        }
+    }
+
+    /// Remove the replaced hydrogen atom and reduce hydrogen count of the atom
+    /// for a new bond.
+    fn take_hydrogens(
+        &mut self,
+        atom: NodeIndex,
+        hydrogen: Option<NodeIndex>,
+        valence: usize,
+    ) -> Result<(), String> {
+        let mut valence = valence;
+        if let Some(hydrogen) = hydrogen {
+            // the hydrogen is a leaf, the tree stays connected without it
+            self.replace_anchor(hydrogen, atom);
+            self.graph.remove_node(hydrogen);
+            valence -= 1;
+        }
+        let node = self
+            .get_atom_mut(atom)
+            .ok_or(format!("Atom {:?} not found", atom))?;
+        node.explicit_hydrogen = node.explicit_hydrogen.saturating_sub(valence);
+        Ok(())
+    }
+
+    /// Add hydrogen atoms to an existed atom.
+    fn add_hydrogen_to_atom(&mut self, atom: NodeIndex) -> Option<Vec<NodeIndex>> {
+        let hydrogens_to_add = self.implicit_hydrogen_count(atom)?;
+        self.get_atom_mut(atom)?.explicit_hydrogen = 0;
         let mut added_hydrogens = vec![];
         while added_hydrogens.len() != hydrogens_to_add {
             added_hydrogens.push(self.connect_new_atom(
@@ -0,0 +1,311 @@
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{
+    stable_graph::NodeIndex,
+    Direction::{Incoming, Outgoing},
+};
+
+use super::Workspace;
+use crate::{
+    definitions::{bond::BondType, chirality::ChiralityType},
+    geometry::space::{norm, signed_volume, sub, Vector},
+};
+
+/// maximum steps of force field cleanup
+const STEPS: usize = 2000;
+
+/// Here implements 3D coordinates generation
+impl Workspace {
+    /// Embed atoms of the structure which the given atom belongs to in 3D.
+    /// Atoms start from their 2D layout, then a simple force field moves them
+    /// to fit bond lengths from covalent radii, bond angles from
+    /// hybridisation and stereo centers, and keeps atoms away from each
+    /// other. Coordinates are stored in atoms.
+    ///
+    /// Atoms only repulse atoms within a few bond lengths, found through a
+    /// grid of cells, so each of the at most 2000 steps takes time linear in
+    /// the count of atoms.
+    ///
+    /// Hydrogens not added as atoms are not placed, add them by
+    /// `add_hydrogen_to_structure` first. Stereo of double bonds is not
+    /// considered.
+    pub fn embed_3d(&mut self, structure_node: NodeIndex) -> Option<()> {
+        self.compute_2d_coords(structure_node)?;
+        let mut atoms = vec![];
+        for root in self.components(structure_node)? {
+            atoms.append(&mut self.get_atoms_of_structure(root)?);
+        }
+        let field = ForceField::new(self, &atoms)?;
+        let mut seed = 0x2545f491u32;
+        let mut positions = atoms
+            .iter()
+            .map(|atom| {
+                let [x, y, _] = self.graph[*atom].coordinates?;
+                // a little out of plane so atoms can get into 3D
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                let z = (seed >> 8) as f64 / (1 << 24) as f64 - 0.5;
+                Some([x, y, z * 0.6])
+            })
+            .collect::<Option<Vec<_>>>()?;
+        field.minimize(&mut positions);
+        for (atom, position) in atoms.iter().zip(positions) {
+            self.graph[*atom].coordinates = Some(position);
+        }
+        Some(())
+    }
+
+    /// Angle between bonds of an atom by its hybridisation, in radian
+    fn ideal_angle(&self, node: NodeIndex, degree: usize) -> f64 {
+        let bonds = self
+            .graph
+            .edges_directed(node, Incoming)
+            .chain(self.graph.edges_directed(node, Outgoing))
+            .map(|edge| edge.weight().bond_type())
+            .collect::<Vec<_>>();
+        let doubles = bonds
+            .iter()
+            .filter(|bond| **bond == BondType::Double)
+            .count();
+        let degrees = if degree > 4 {
+            90.
+        } else if bonds.contains(&BondType::Triple) || doubles == 2 && degree == 2 {
+            180.
+        } else if doubles > 0 || self.graph[node].aromatic {
+            120.
+        } else {
+            109.47
+        };
+        f64::to_radians(degrees)
+    }
+}
+
+struct ForceField {
+    /// atom pairs with their target distance and force constant, from bonds
+    /// and angles
+    distances: Vec<(usize, usize, f64, f64)>,
+    /// pairs not in `distances` repulse each other within the distance
+    contacts: Vec<f64>,
+    /// the longest distance of repulsion, size of cells of the grid
+    cutoff: f64,
+    excluded: HashSet<(usize, usize)>,
+    /// stereo centers with their neighbors in order and sign of the volume
+    centers: Vec<(usize, Vec<Option<usize>>, f64)>,
+}
+
+impl ForceField {
+    fn new(workspace: &Workspace, atoms: &[NodeIndex]) -> Option<Self> {
+        let index_of = atoms
+            .iter()
+            .enumerate()
+            .map(|(index, atom)| (*atom, index))
+            .collect::<HashMap<_, _>>();
+        let radius = |index: usize| workspace.graph[atoms[index]].element.covalent_radius();
+        let mut lengths = HashMap::new();
+        let mut neighbors_of = vec![];
+        for (index, atom) in atoms.iter().enumerate() {
+            let mut neighbors = vec![];
+            for neighbor in workspace.graph.neighbors_undirected(*atom) {
+                let other = index_of[&neighbor];
+                if neighbors.contains(&other) {
+                    continue;
+                }
+                neighbors.push(other);
+                let (bond, _, _) = workspace.get_edge_undirected(*atom, neighbor)?;
+                let factor = match bond.bond_type() {
+                    BondType::Aromatic => 0.93,
+                    BondType::Double => 0.88,
+                    BondType::Triple | BondType::Quad => 0.8,
+                    _ => 1.,
+                };
+                lengths.insert((index, other), (radius(index) + radius(other)) * factor);
+            }
+            neighbors_of.push(neighbors);
+        }
+        let mut distances = vec![];
+        let mut excluded = HashSet::new();
+        let mut centers = vec![];
+        for (index, atom) in atoms.iter().enumerate() {
+            let neighbors = &neighbors_of[index];
+            for other in neighbors.iter().filter(|other| index < **other) {
+                distances.push((index, *other, lengths[&(index, *other)], 100.));
+                excluded.insert((index, *other));
+            }
+            // angles are kept by distances between neighbors
+            let angle = workspace.ideal_angle(*atom, neighbors.len());
+            for (position, first) in neighbors.iter().enumerate() {
+                for second in neighbors[position + 1..].iter() {
+                    let a = lengths[&(index, *first)];
+                    let b = lengths[&(index, *second)];
+                    let length = (a * a + b * b - 2. * a * b * angle.cos()).sqrt();
+                    let pair = (*first.min(second), *first.max(second));
+                    distances.push((pair.0, pair.1, length, 30.));
+                    excluded.insert(pair);
+                }
+            }
+            if let Some(chirality) = workspace.graph[*atom].chirality_type {
+                let order = workspace
+                    .chiral_neighbors(*atom)?
+                    .into_iter()
+                    .map(|neighbor| neighbor.map(|neighbor| index_of[&neighbor]))
+                    .collect::<Vec<_>>();
+                if order.len() == 4 {
+                    // see `signed_volume`
+                    let sign = match chirality {
+                        ChiralityType::Counter => -1.,
+                        ChiralityType::Clockwise => 1.,
+                    };
+                    centers.push((index, order, sign));
+                }
+            }
+        }
+        let contacts = (0..atoms.len())
+            .map(|index| radius(index).max(0.5))
+            .collect::<Vec<_>>();
+        let cutoff = contact_distance(
+            contacts.iter().copied().fold(0., f64::max),
+            contacts.iter().copied().fold(0., f64::max),
+        );
+        Some(Self {
+            distances,
+            contacts,
+            cutoff,
+            excluded,
+            centers,
+        })
+    }
+
+    /// Move atoms down the gradient, the step grows when energy goes down
+    /// and shrinks when it goes up.
+    fn minimize(&self, positions: &mut [Vector]) {
+        let mut gradient = vec![[0.; 3]; positions.len()];
+        let mut energy = self.evaluate(positions, &mut gradient);
+        let mut step = 0.01;
+        let mut trial = positions.to_vec();
+        let mut trial_gradient = gradient.clone();
+        for _ in 0..STEPS {
+            for (moved, (position, force)) in
+                trial.iter_mut().zip(positions.iter().zip(gradient.iter()))
+            {
+                for axis in 0..3 {
+                    moved[axis] = position[axis] - (step * force[axis]).clamp(-0.3, 0.3);
+                }
+            }
+            let trial_energy = self.evaluate(&trial, &mut trial_gradient);
+            if trial_energy < energy {
+                positions.copy_from_slice(&trial);
+                std::mem::swap(&mut gradient, &mut trial_gradient);
+                energy = trial_energy;
+                step *= 1.2;
+            } else {
+                step *= 0.5;
+                if step < 1e-9 {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Energy of positions, gradient is written
+    fn evaluate(&self, positions: &[Vector], gradient: &mut [Vector]) -> f64 {
+        gradient.iter_mut().for_each(|force| *force = [0.; 3]);
+        let mut energy = 0.;
+        let mut stretch = |from: usize, to: usize, target: f64, constant: f64| {
+            let delta = sub(positions[from], positions[to]);
+            let length = norm(delta).max(1e-6);
+            let diff = length - target;
+            let factor = 2. * constant * diff / length;
+            for axis in 0..3 {
+                gradient[from][axis] += factor * delta[axis];
+                gradient[to][axis] -= factor * delta[axis];
+            }
+            constant * diff * diff
+        };
+        for (from, to, target, constant) in self.distances.iter() {
+            energy += stretch(*from, *to, *target, *constant);
+        }
+        for (from, to) in self.close_pairs(positions) {
+            if self.excluded.contains(&(from, to)) {
+                continue;
+            }
+            let contact = contact_distance(self.contacts[from], self.contacts[to]);
+            if norm(sub(positions[from], positions[to])) < contact {
+                energy += stretch(from, to, contact, 10.);
+            }
+        }
+        for (center, neighbors, sign) in self.centers.iter() {
+            energy += self.chirality(positions, gradient, *center, neighbors, *sign);
+        }
+        energy
+    }
+
+    /// Pairs of atoms in the same or adjacent cells of a grid, the others
+    /// are too far to repulse each other. Smaller index is the first.
+    fn close_pairs(&self, positions: &[Vector]) -> Vec<(usize, usize)> {
+        let cell_of =
+            |position: &Vector| position.map(|value| (value / self.cutoff).floor() as i64);
+        let mut cells: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        for (index, position) in positions.iter().enumerate() {
+            cells.entry(cell_of(position)).or_default().push(index);
+        }
+        let mut pairs = vec![];
+        for (from, position) in positions.iter().enumerate() {
+            let [x, y, z] = cell_of(position);
+            for cell in (-1..=1).flat_map(|dx| {
+                (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| [x + dx, y + dy, z + dz]))
+            }) {
+                for to in cells.get(&cell).into_iter().flatten() {
+                    if from < *to {
+                        pairs.push((from, *to));
+                    }
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Penalty of a stereo center with a volume of wrong sign or too flat,
+    /// its gradient is computed numerically.
+    fn chirality(
+        &self,
+        positions: &[Vector],
+        gradient: &mut [Vector],
+        center: usize,
+        neighbors: &[Option<usize>],
+        sign: f64,
+    ) -> f64 {
+        let penalty = |positions: &[Vector]| {
+            let vectors = neighbors
+                .iter()
+                .map(|neighbor| {
+                    neighbor.map(|neighbor| sub(positions[neighbor], positions[center]))
+                })
+                .collect::<Vec<_>>();
+            let volume = signed_volume(&vectors);
+            let short = (sign * volume - 1.).min(0.);
+            50. * short * short
+        };
+        let energy = penalty(positions);
+        if energy == 0. {
+            return 0.;
+        }
+        let mut moved = positions.to_vec();
+        let delta = 1e-5;
+        for atom in neighbors.iter().flatten().copied().chain([center]) {
+            for axis in 0..3 {
+                moved[atom][axis] += delta;
+                let forward = penalty(&moved);
+                moved[atom][axis] -= 2. * delta;
+                let backward = penalty(&moved);
+                moved[atom][axis] += delta;
+                gradient[atom][axis] += (forward - backward) / (2. * delta);
+            }
+        }
+        energy
+    }
+}
+
+/// distance within which two atoms not bonded and not in an angle repulse
+/// each other
+fn contact_distance(a: f64, b: f64) -> f64 {
+    1.6 * (a + b).max(1.25)
+}
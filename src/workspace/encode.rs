@@ -1,6 +1,14 @@
-use petgraph::{graph::NodeIndex, stable_graph::EdgeIndex};
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{
+    graph::NodeIndex,
+    stable_graph::EdgeIndex,
+    visit::EdgeRef,
+    Direction::{Incoming, Outgoing},
+};
 
 use super::Workspace;
+use crate::definitions::bond::{Bond, BondType};
 
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -9,12 +17,62 @@ lazy_static! {
     static ref SELECTOR_RE: Regex = Regex::new(r"\{.*?\}").unwrap();
 }
 
+/// How `to_sws_with` writes a structure
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    /// give ids of closed rings to the rings opened later, so ring ids
+    /// stay small in polycyclic structures. Without it rings are numbered
+    /// in the order they're opened and at most 99 rings can be written.
+    pub reuse_ring_ids: bool,
+    /// write the child with the longest chain as the main chain and the
+    /// others as branches before it
+    pub longest_chain_last: bool,
+    /// write `-` and `:` bonds even where they can be implied
+    pub explicit_bonds: bool,
+    /// write aromatic rings as alternating single and double bonds
+    pub kekule: bool,
+    /// write chirality, isotopes and `/` `\` bonds, turn it off to get
+    /// non-isomeric SMILES
+    pub isomeric: bool,
+    /// write selectors of atoms. Without them the output is plain SMILES:
+    /// bracket atoms get their hydrogen counts and atoms bracketed only for
+    /// selectors are written without brackets.
+    pub selectors: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            reuse_ring_ids: true,
+            longest_chain_last: false,
+            explicit_bonds: false,
+            kekule: false,
+            isomeric: true,
+            selectors: true,
+        }
+    }
+}
+
 struct SmilesGenerator<'a> {
     workspace: &'a Workspace,
+    options: &'a WriteOptions,
+    /// bond types of aromatic bonds in kekulé form
+    kekule: &'a HashMap<EdgeIndex, BondType>,
+    /// atoms written in uppercase in kekulé form
+    kekule_atoms: HashSet<NodeIndex>,
+    /// atoms count of the longest chain starting from an atom
+    chain_lengths: HashMap<NodeIndex, usize>,
+    /// atoms written before atoms in the tree, the tree is the directed tree
+    /// with its root moved to the first atom written
+    parents: HashMap<NodeIndex, NodeIndex>,
+    /// order of branches given by atoms, smaller ones are written first
+    ranks: Option<&'a HashMap<NodeIndex, u64>>,
     previous_node: Option<NodeIndex>,
     current_node: Option<NodeIndex>,
     branch_stack: Vec<(NodeIndex, Vec<NodeIndex>)>,
-    ring_bonds: Vec<EdgeIndex>,
+    /// ids of ring bonds opened and not closed yet
+    ring_bonds: HashMap<EdgeIndex, usize>,
+    next_ring_id: usize,
 }
 
 impl<'a> Iterator for SmilesGenerator<'a> {
@@ -24,164 +82,356 @@ impl<'a> Iterator for SmilesGenerator<'a> {
         let current_node = self.current_node?;
         let mut fragment = String::new();
 
-        let atom = self
-            .workspace
-            .get_atom(current_node)
-            .expect("should always find an atom");
-
         if let Some(previous_node) = self.previous_node {
-            let (bond, _) = self.workspace.get_edge(previous_node, current_node).expect(
-                format!(
-                    "No connect between {:?} {:?}",
-                    previous_node, self.current_node
-                )
-                .as_str(),
-            );
-            if !(self.workspace.get_atom(previous_node).unwrap().aromatic
-                && atom.aromatic
-                && bond.is_aromatic())
-                && !(bond.is_normal_single())
-            {
-                fragment.push_str(bond.as_str())
+            let (_, edge, _) = self
+                .workspace
+                .get_edge_undirected(previous_node, current_node)?;
+            fragment.push_str(self.bond_token(edge, previous_node, current_node));
+        }
+
+        let ring_bonds = self.ring_bonds_of(current_node);
+        let children = self.children_of(current_node);
+        fragment.push_str(&self.atom_token(current_node, &ring_bonds, &children)?);
+
+        for (edge, neighbor) in ring_bonds {
+            if let Some(ring_id) = self.ring_bonds.remove(&edge) {
+                fragment.push_str(&ring_id_token(ring_id));
+            } else {
+                let ring_id = self.open_ring_id()?;
+                self.ring_bonds.insert(edge, ring_id);
+                fragment.push_str(self.bond_token(edge, current_node, neighbor));
+                fragment.push_str(&ring_id_token(ring_id));
             }
-            let atom_token = atom.to_token();
-            fragment.push_str(&atom_token);
-        } else {
-            fragment.push_str(&atom.to_token());
         }
 
-        let all_nexts = self.workspace.get_next_neighbors(current_node);
+        let mut nexts = children;
+        self.current_node = if !nexts.is_empty() {
+            self.previous_node = self.current_node;
+            // branches are popped from the end
+            nexts.reverse();
+            let next = nexts.pop();
+            if !nexts.is_empty() {
+                fragment.push('(');
+                self.branch_stack.push((current_node, nexts));
+            }
+            next
+        } else if let Some((prev, mut branches)) = self.branch_stack.pop() {
+            self.previous_node = Some(prev);
+            fragment.push(')');
+            let next = branches.pop();
+            if !branches.is_empty() {
+                fragment.push('(');
+                self.branch_stack.push((prev, branches));
+            }
+            next
+        } else {
+            None
+        };
+        Some(fragment)
+    }
+}
+
+impl<'a> SmilesGenerator<'a> {
+    /// Write the component from the given atom, it's the root if the
+    /// component is written as stored.
+    fn new(
+        workspace: &'a Workspace,
+        start: NodeIndex,
+        options: &'a WriteOptions,
+        kekule: &'a HashMap<EdgeIndex, BondType>,
+        ranks: Option<&'a HashMap<NodeIndex, u64>>,
+    ) -> Option<Self> {
+        workspace.get_atom(start)?;
+        let mut kekule_atoms = HashSet::new();
+        for edge in kekule.keys() {
+            let (from, to) = workspace.graph.edge_endpoints(*edge)?;
+            kekule_atoms.insert(from);
+            kekule_atoms.insert(to);
+        }
+        let mut generator = Self {
+            workspace,
+            options,
+            kekule,
+            kekule_atoms,
+            chain_lengths: HashMap::new(),
+            parents: HashMap::new(),
+            ranks,
+            previous_node: None,
+            current_node: Some(start),
+            branch_stack: vec![],
+            ring_bonds: HashMap::new(),
+            next_ring_id: 1,
+        };
+        let mut atoms = vec![];
+        let mut stack = vec![start];
+        while let Some(atom) = stack.pop() {
+            atoms.push(atom);
+            for child in generator.tree_children(atom) {
+                generator.parents.insert(child, atom);
+                stack.push(child);
+            }
+        }
+        if options.longest_chain_last {
+            // children are always after their parent, count from the end
+            for atom in atoms.into_iter().rev() {
+                let length = generator
+                    .tree_children(atom)
+                    .iter()
+                    .map(|child| generator.chain_lengths[child])
+                    .max()
+                    .unwrap_or_default()
+                    + 1;
+                generator.chain_lengths.insert(atom, length);
+            }
+        }
+        Some(generator)
+    }
 
-        let mut atom_ring_bonds = self
+    /// Children of the atom in the tree, in the order bonds added. Parents
+    /// must be found before.
+    fn tree_children(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        let parent = self.parents.get(&node);
+        let mut children = self
             .workspace
             .graph
-            .neighbors_undirected(current_node)
-            .filter(|node_index| {
-                let (bond, _, _) = self
-                    .workspace
-                    .get_edge_undirected(current_node, *node_index)
-                    .unwrap();
-                bond.is_ring_bond()
+            .edges_directed(node, Incoming)
+            .chain(self.workspace.graph.edges_directed(node, Outgoing))
+            .filter(|edge| !edge.weight().is_ring_bond())
+            .map(|edge| {
+                let neighbor = if edge.source() == node {
+                    edge.target()
+                } else {
+                    edge.source()
+                };
+                (edge.id(), neighbor)
             })
+            .filter(|(_, neighbor)| Some(neighbor) != parent)
             .collect::<Vec<_>>();
+        children.sort();
+        children.into_iter().map(|(_, child)| child).collect()
+    }
 
-        while let Some(ring_bond_neighbor) = atom_ring_bonds.pop() {
-            let (bond, edge, _) = self
-                .workspace
-                .get_edge_undirected(current_node, ring_bond_neighbor)
-                .unwrap();
-
-            let bond_token = if (self
-                .workspace
-                .get_atom(ring_bond_neighbor)
-                .unwrap()
-                .aromatic
-                && atom.aromatic
-                && bond.is_aromatic())
-                || bond.is_normal_single()
-                || self.ring_bonds.contains(&edge)
-            {
-                ""
-            } else {
-                bond.as_str()
-            };
-
-            let ring_id = if let Some(position) = self
-                .ring_bonds
-                .iter()
-                .position(|edge_index| edge == *edge_index)
-            {
-                position + 1
-            } else {
-                self.ring_bonds.push(edge);
-                self.ring_bonds.len()
-            };
-
-            let ring_id_token = if ring_id >= 10 {
-                format!("%{}", ring_id)
-            } else {
-                ring_id.to_string()
-            };
-
-            let full_token = if atom_ring_bonds.len() >= 1 && bond_token != "" {
-                format!("({}{})", bond_token, ring_id_token)
-            } else {
-                format!("{}{}", bond_token, ring_id_token)
-            };
-
-            fragment.push_str(&full_token);
+    /// Children of the atom in the order they're written, the last one is
+    /// the main chain.
+    fn children_of(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        let mut children = self.tree_children(node);
+        if let Some(ranks) = self.ranks {
+            children.sort_by_key(|child| ranks.get(child).copied().unwrap_or_default());
+        }
+        if self.options.longest_chain_last {
+            children.sort_by_key(|child| self.chain_lengths[child]);
         }
+        children
+    }
 
-        let mut nexts = all_nexts
-            .iter()
-            .copied()
-            .filter(|neighbor_index| {
-                let (bond, _) = self
-                    .workspace
-                    .get_edge(current_node, *neighbor_index)
-                    .unwrap();
-                !bond.is_ring_bond()
+    /// Ring bonds of the atom with the atoms on the other side, in the
+    /// order they're written.
+    fn ring_bonds_of(&self, node: NodeIndex) -> Vec<(EdgeIndex, NodeIndex)> {
+        let mut ring_bonds = self
+            .workspace
+            .graph
+            .edges_directed(node, Incoming)
+            .chain(self.workspace.graph.edges_directed(node, Outgoing))
+            .filter(|edge| edge.weight().is_ring_bond())
+            .map(|edge| {
+                let neighbor = if edge.source() == node {
+                    edge.target()
+                } else {
+                    edge.source()
+                };
+                (edge.id(), neighbor)
             })
             .collect::<Vec<_>>();
+        ring_bonds.sort();
+        ring_bonds
+    }
 
-        self.current_node = if nexts.len() > 0 {
-            self.previous_node = self.current_node;
-            if nexts.len() > 1 {
-                fragment.push_str("(");
-                let next = nexts.pop().unwrap();
-                self.branch_stack.push((self.previous_node.unwrap(), nexts));
-                Some(next)
-            } else {
-                nexts.pop()
-            }
+    /// The id of a ring opened, None if it would be over 99
+    fn open_ring_id(&mut self) -> Option<usize> {
+        if self.options.reuse_ring_ids {
+            (1..100).find(|ring_id| !self.ring_bonds.values().any(|used| used == ring_id))
+        } else if self.next_ring_id < 100 {
+            self.next_ring_id += 1;
+            Some(self.next_ring_id - 1)
         } else {
-            if let Some((prev, mut branches)) = self.branch_stack.pop() {
-                self.previous_node = Some(prev);
-                fragment.push_str(")");
-                if branches.len() > 1 {
-                    fragment.push_str("(");
-                }
-                let next = branches.pop().unwrap();
-                if branches.len() >= 1 {
-                    self.branch_stack.push((prev, branches));
-                }
-                Some(next)
-            } else {
-                None
+            None
+        }
+    }
+
+    fn is_written_aromatic(&self, node: NodeIndex) -> bool {
+        self.workspace.graph[node].aromatic && !self.kekule_atoms.contains(&node)
+    }
+
+    /// Token of the atom, chirality is given by the order its neighbors
+    /// are written.
+    fn atom_token(
+        &self,
+        node: NodeIndex,
+        ring_bonds: &[(EdgeIndex, NodeIndex)],
+        children: &[NodeIndex],
+    ) -> Option<String> {
+        let mut atom = self.workspace.get_atom(node)?.clone();
+        if self.kekule_atoms.contains(&node) {
+            atom.aromatic = false;
+        }
+        if !self.options.isomeric {
+            atom.isotope = None;
+            atom.chirality_type = None;
+        }
+        if !self.options.selectors {
+            atom.selector = None;
+            // other readers don't fill hydrogens of bracket atoms
+            if atom.needs_bracket() && atom.explicit_hydrogen == 0 {
+                atom.explicit_hydrogen = self.workspace.implicit_hydrogen_count(node)?;
             }
+        }
+        if let Some(chirality) = atom.chirality_type {
+            let stored = self.workspace.chiral_neighbors(node)?;
+            let mut written = self.previous_node.map(Some).into_iter().collect::<Vec<_>>();
+            if stored.contains(&None) {
+                written.push(None);
+            }
+            written.extend(ring_bonds.iter().map(|(_, neighbor)| Some(*neighbor)));
+            written.extend(children.iter().copied().map(Some));
+            atom.chirality_type = Some(chirality.reorder(&stored, &written).unwrap_or(chirality));
+        }
+        Some(atom.to_token())
+    }
+
+    /// Token of the bond written from `from` to `to`, empty if it can be
+    /// implied.
+    fn bond_token(&self, edge: EdgeIndex, from: NodeIndex, to: NodeIndex) -> &'static str {
+        let bond = self.workspace.graph[edge];
+        let bond = match self.workspace.graph.edge_endpoints(edge) {
+            Some((source, _)) if source != from => bond.reverse(),
+            _ => bond,
         };
-        Some(fragment)
+        let bond_type = match self.kekule.get(&edge) {
+            Some(bond_type) => *bond_type,
+            None => bond.bond_type(),
+        };
+        let bond_type = match bond_type {
+            BondType::UpSingle | BondType::DownSingle if !self.options.isomeric => BondType::Single,
+            bond_type => bond_type,
+        };
+        let aromatic_atoms = self.is_written_aromatic(from) && self.is_written_aromatic(to);
+        let implied = match bond_type {
+            BondType::Single => !aromatic_atoms,
+            BondType::Aromatic => aromatic_atoms,
+            _ => false,
+        };
+        if implied && !self.options.explicit_bonds {
+            ""
+        } else {
+            Bond::new(bond_type, false).as_str()
+        }
     }
 }
 
-impl<'a> SmilesGenerator<'a> {
-    fn new(workspace: &'a Workspace, structure_node: NodeIndex) -> Option<Self> {
-        Some(Self {
-            workspace,
-            previous_node: None,
-            current_node: workspace.find_root_of(structure_node),
-            branch_stack: vec![],
-            ring_bonds: vec![],
-        })
+fn ring_id_token(ring_id: usize) -> String {
+    if ring_id >= 10 {
+        format!("%{}", ring_id)
+    } else {
+        ring_id.to_string()
     }
 }
 
 impl Workspace {
+    /// write the structure with all components in its group,
+    /// components are separated by `.`. Returns None if more than 99 rings
+    /// are open at the same time.
     pub fn to_sws(&self, node: NodeIndex) -> Option<String> {
-        let generator = SmilesGenerator::new(self, node)?;
-        let mut smiles = String::new();
-        for fragment in generator {
-            smiles.push_str(&fragment);
-        }
-        Some(smiles)
+        self.to_sws_with(node, &WriteOptions::default())
+    }
+
+    /// write the structure as `to_sws` with given options. Returns None if
+    /// kekulé form is asked but aromatic rings can't be kekulized, or ring
+    /// ids would be over 99.
+    pub fn to_sws_with(&self, node: NodeIndex, options: &WriteOptions) -> Option<String> {
+        Some(self.write_sws(node, options)?.0)
     }
 
     pub fn to_smiles(&self, node: NodeIndex) -> Option<String> {
-        let sws = self.to_sws(node)?;
-        Some(Self::sws_to_smiles(&sws))
+        self.to_smiles_with(node, &WriteOptions::default())
+    }
+
+    /// write the structure as `to_sws_with` without selectors
+    pub fn to_smiles_with(&self, node: NodeIndex, options: &WriteOptions) -> Option<String> {
+        let options = WriteOptions {
+            selectors: false,
+            ..options.clone()
+        };
+        self.to_sws_with(node, &options)
     }
 
     pub fn sws_to_smiles(sws: &str) -> String {
-        SELECTOR_RE.replace_all(&sws, "").to_string()
+        SELECTOR_RE.replace_all(sws, "").to_string()
+    }
+
+    /// write the structure as `to_sws_with`, also returns atoms in the order
+    /// they're written.
+    pub fn write_sws(
+        &self,
+        node: NodeIndex,
+        options: &WriteOptions,
+    ) -> Option<(String, Vec<NodeIndex>)> {
+        self.write_components(&self.components(node)?, options, None)
+    }
+
+    /// write the structure from the given atom, like writing a `Replacer`
+    /// site first. Other components are written after it from their roots.
+    /// Unlike `reset_root`, the workspace is not changed.
+    pub fn to_sws_from(&self, start: NodeIndex) -> Option<String> {
+        self.to_sws_from_with(start, &WriteOptions::default())
+    }
+
+    pub fn to_sws_from_with(&self, start: NodeIndex, options: &WriteOptions) -> Option<String> {
+        Some(self.write_sws_from(start, options)?.0)
+    }
+
+    /// write the structure as `to_sws_from_with`, also returns atoms in the
+    /// order they're written.
+    pub fn write_sws_from(
+        &self,
+        start: NodeIndex,
+        options: &WriteOptions,
+    ) -> Option<(String, Vec<NodeIndex>)> {
+        let root = self.find_root_of(start)?;
+        let mut starts = vec![start];
+        starts.extend(
+            self.components(start)?
+                .into_iter()
+                .filter(|component| *component != root),
+        );
+        self.write_components(&starts, options, None)
+    }
+
+    /// Write components starting from the given atoms, one of each
+    /// component. Branches are written in the order of `ranks` if given,
+    /// the workspace is not changed.
+    pub(super) fn write_components(
+        &self,
+        starts: &[NodeIndex],
+        options: &WriteOptions,
+        ranks: Option<&HashMap<NodeIndex, u64>>,
+    ) -> Option<(String, Vec<NodeIndex>)> {
+        let kekule = if options.kekule {
+            self.kekulize(*starts.first()?)?
+        } else {
+            HashMap::new()
+        };
+        let mut smiles = String::new();
+        let mut atoms = vec![];
+        for (index, start) in starts.iter().enumerate() {
+            if index > 0 {
+                smiles.push('.');
+            }
+            let mut generator = SmilesGenerator::new(self, *start, options, &kekule, ranks)?;
+            while let Some(atom) = generator.current_node {
+                atoms.push(atom);
+                smiles.push_str(&generator.next()?);
+            }
+        }
+        Some((smiles, atoms))
     }
 }
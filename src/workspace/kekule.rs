@@ -0,0 +1,114 @@
+use std::collections::{BTreeSet, HashMap};
+
+use petgraph::{
+    stable_graph::{EdgeIndex, NodeIndex},
+    Direction::{Incoming, Outgoing},
+};
+
+use super::Workspace;
+use crate::definitions::bond::BondType;
+
+/// Here implements kekulization of aromatic bonds
+impl Workspace {
+    /// Assign single and double bonds to aromatic ring bonds of the structure
+    /// which the given atom belongs to. Bonds not given in the result are
+    /// not changed. Returns None if there's no such assignment.
+    pub fn kekulize(&self, structure_node: NodeIndex) -> Option<HashMap<EdgeIndex, BondType>> {
+        let ring_bonds = self
+            .rings(structure_node)?
+            .iter()
+            .flat_map(|ring| {
+                ring.iter()
+                    .enumerate()
+                    .map(|(index, atom)| (*atom, ring[(index + 1) % ring.len()]))
+                    .collect::<Vec<_>>()
+            })
+            .filter_map(|(from, to)| self.get_edge_undirected(from, to))
+            .filter(|(bond, _, _)| bond.is_aromatic())
+            .map(|(_, edge, _)| edge)
+            .collect::<BTreeSet<_>>();
+        let mut result = ring_bonds
+            .iter()
+            .map(|edge| (*edge, BondType::Single))
+            .collect::<HashMap<_, _>>();
+        // atoms need a double bond and their candidate bonds, in order of
+        // bonds so the same assignment is found every time
+        let mut candidates: HashMap<NodeIndex, Vec<(EdgeIndex, NodeIndex)>> = HashMap::new();
+        for edge in ring_bonds.iter() {
+            let (from, to) = self.graph.edge_endpoints(*edge)?;
+            if self.needs_double_bond(from)? && self.needs_double_bond(to)? {
+                candidates.entry(from).or_default().push((*edge, to));
+                candidates.entry(to).or_default().push((*edge, from));
+            }
+        }
+        for edge in ring_bonds.iter() {
+            let (from, to) = self.graph.edge_endpoints(*edge)?;
+            for atom in [from, to] {
+                if self.needs_double_bond(atom)? && !candidates.contains_key(&atom) {
+                    return None;
+                }
+            }
+        }
+        let mut matched = HashMap::new();
+        if !match_atoms(&candidates, &mut matched) {
+            return None;
+        }
+        for (_, edge) in matched.values() {
+            result.insert(*edge, BondType::Double);
+        }
+        Some(result)
+    }
+}
+
+/// Private functions used upon
+impl Workspace {
+    /// An aromatic atom needs a double bond if it has valence left after
+    /// its bonds, hydrogens and radicals, like `c` and `n` but not `[nH]`,
+    /// `o` or `s`.
+    fn needs_double_bond(&self, node: NodeIndex) -> Option<bool> {
+        let atom = self.get_atom(node)?;
+        let valence = atom.element.default_hydrogen() as isize + atom.charge;
+        let bonds = self
+            .graph
+            .edges_directed(node, Incoming)
+            .chain(self.graph.edges_directed(node, Outgoing))
+            .map(|edge| edge.weight().bond_type().valence() as isize)
+            .sum::<isize>();
+        let used =
+            bonds + self.implicit_hydrogen_count(node)? as isize + atom.radical_electrons as isize;
+        Some(atom.aromatic && valence - used >= 1)
+    }
+}
+
+/// Match every atom with one of its candidates by backtracking, the atom
+/// with fewest choices goes first.
+fn match_atoms(
+    candidates: &HashMap<NodeIndex, Vec<(EdgeIndex, NodeIndex)>>,
+    matched: &mut HashMap<NodeIndex, (NodeIndex, EdgeIndex)>,
+) -> bool {
+    let next = candidates
+        .iter()
+        .filter(|(atom, _)| !matched.contains_key(atom))
+        .map(|(atom, choices)| {
+            let choices = choices
+                .iter()
+                .filter(|(_, other)| !matched.contains_key(other))
+                .copied()
+                .collect::<Vec<_>>();
+            (*atom, choices)
+        })
+        .min_by_key(|(atom, choices)| (choices.len(), *atom));
+    let Some((atom, choices)) = next else {
+        return true;
+    };
+    for (edge, other) in choices {
+        matched.insert(atom, (other, edge));
+        matched.insert(other, (atom, edge));
+        if match_atoms(candidates, matched) {
+            return true;
+        }
+        matched.remove(&atom);
+        matched.remove(&other);
+    }
+    false
+}
@@ -0,0 +1,376 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    f64::consts::PI,
+};
+
+use petgraph::{
+    stable_graph::{EdgeIndex, NodeIndex},
+    Direction::{Incoming, Outgoing},
+};
+
+use super::Workspace;
+use crate::{
+    definitions::{bond::BondType, chirality::ChiralityType},
+    geometry::{add, angle, centroid, distance, scale, space::signed_volume, sub, unit, Point},
+};
+
+/// bond length of generated coordinates, in angstrom like most molfiles
+const BOND_LENGTH: f64 = 1.5;
+
+/// Here implements 2D coordinates generation
+impl Workspace {
+    /// Compute 2D coordinates of atoms in the structure which the given atom
+    /// belongs to, rings are laid out as regular polygons and chains as
+    /// zigzags. Components are placed from left to right. Coordinates are
+    /// stored in atoms with z as 0.
+    pub fn compute_2d_coords(&mut self, structure_node: NodeIndex) -> Option<()> {
+        let rings = self.rings(structure_node)?;
+        let mut offset = 0.;
+        for root in self.components(structure_node)? {
+            let positions = Layout::new(self, &rings).place(root);
+            let (mut min_x, mut max_x) = (f64::MAX, f64::MIN);
+            let (mut min_y, mut max_y) = (f64::MAX, f64::MIN);
+            for (x, y) in positions.values() {
+                min_x = min_x.min(*x);
+                max_x = max_x.max(*x);
+                min_y = min_y.min(*y);
+                max_y = max_y.max(*y);
+            }
+            let middle_y = (min_y + max_y) / 2.;
+            for (atom, (x, y)) in positions {
+                self.graph[atom].coordinates = Some([x - min_x + offset, y - middle_y, 0.]);
+            }
+            offset += max_x - min_x + 2. * BOND_LENGTH;
+        }
+        Some(())
+    }
+
+    /// Choose a single bond for each stereo center among given atoms to be
+    /// drawn as a wedge from it, by 2D positions with y going up. Returns
+    /// the center and if the wedge is hashed of each chosen bond.
+    pub(crate) fn wedges(
+        &self,
+        atoms: &[NodeIndex],
+        positions: &HashMap<NodeIndex, Point>,
+    ) -> HashMap<EdgeIndex, (NodeIndex, bool)> {
+        let ring_bonds = atoms
+            .first()
+            .and_then(|atom| self.rings(*atom))
+            .into_iter()
+            .flatten()
+            .flat_map(|ring| {
+                (0..ring.len()).map(move |index| {
+                    let (a, b) = (ring[index], ring[(index + 1) % ring.len()]);
+                    (a.min(b), a.max(b))
+                })
+            })
+            .collect::<HashSet<_>>();
+        let centers = atoms
+            .iter()
+            .copied()
+            .filter(|atom| self.graph[*atom].chirality_type.is_some())
+            .collect::<HashSet<_>>();
+        let mut wedges = HashMap::new();
+        for center in atoms.iter() {
+            let Some(chirality) = self.graph[*center].chirality_type else {
+                continue;
+            };
+            let Some(neighbors) = self.chiral_neighbors(*center) else {
+                continue;
+            };
+            if neighbors.len() != 4 {
+                continue;
+            }
+            let candidate = neighbors
+                .iter()
+                .flatten()
+                .filter_map(|neighbor| {
+                    let (bond, edge, _) = self.get_edge_undirected(*center, *neighbor)?;
+                    let single = matches!(
+                        bond.bond_type(),
+                        BondType::Single | BondType::UpSingle | BondType::DownSingle
+                    );
+                    (single && !wedges.contains_key(&edge)).then_some((*neighbor, edge))
+                })
+                .min_by_key(|(neighbor, edge)| {
+                    (
+                        centers.contains(neighbor),
+                        ring_bonds.contains(&(*center.min(neighbor), *center.max(neighbor))),
+                        self.graph.neighbors_undirected(*neighbor).count(),
+                        *edge,
+                    )
+                });
+            let Some((wedged, edge)) = candidate else {
+                continue;
+            };
+            if let Some(wedge_chirality) =
+                self.wedged_chirality(*center, &[(wedged, 1.)], positions)
+            {
+                wedges.insert(edge, (*center, wedge_chirality != chirality));
+            }
+        }
+        wedges
+    }
+
+    /// Chirality of a center drawn in 2D with given heights of its wedged
+    /// neighbors, 1 for a wedge and -1 for a hashed one. None if it can't be
+    /// told from the drawing.
+    pub(crate) fn wedged_chirality(
+        &self,
+        center: NodeIndex,
+        heights: &[(NodeIndex, f64)],
+        positions: &HashMap<NodeIndex, Point>,
+    ) -> Option<ChiralityType> {
+        let origin = *positions.get(&center)?;
+        let vectors = self
+            .chiral_neighbors(center)?
+            .into_iter()
+            .map(|neighbor| match neighbor {
+                Some(neighbor) => {
+                    let (x, y) = sub(*positions.get(&neighbor)?, origin);
+                    let height = heights
+                        .iter()
+                        .find(|(wedged, _)| *wedged == neighbor)
+                        .map_or(0., |(_, height)| *height);
+                    Some(Some([x, y, height * x.hypot(y)]))
+                }
+                None => Some(None),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let volume = signed_volume(&vectors);
+        if volume.abs() < 1e-6 {
+            None
+        } else if volume < 0. {
+            Some(ChiralityType::Counter)
+        } else {
+            Some(ChiralityType::Clockwise)
+        }
+    }
+}
+
+struct Layout<'a> {
+    workspace: &'a Workspace,
+    rings: &'a [Vec<NodeIndex>],
+    rings_of: HashMap<NodeIndex, Vec<usize>>,
+    placed_rings: Vec<bool>,
+    positions: HashMap<NodeIndex, Point>,
+    /// side of the last turn of zigzag at each chain atom
+    turns: HashMap<NodeIndex, f64>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(workspace: &'a Workspace, rings: &'a [Vec<NodeIndex>]) -> Self {
+        let mut rings_of: HashMap<NodeIndex, Vec<usize>> = HashMap::new();
+        for (index, ring) in rings.iter().enumerate() {
+            for atom in ring {
+                rings_of.entry(*atom).or_default().push(index);
+            }
+        }
+        Self {
+            workspace,
+            rings,
+            rings_of,
+            placed_rings: vec![false; rings.len()],
+            positions: HashMap::new(),
+            turns: HashMap::new(),
+        }
+    }
+
+    /// Place atoms from the root in breadth-first order, a ring is placed as
+    /// a whole when one of its atoms is reached.
+    fn place(mut self, root: NodeIndex) -> HashMap<NodeIndex, Point> {
+        self.positions.insert(root, (0., 0.));
+        let mut queue = VecDeque::from([root]);
+        while let Some(atom) = queue.pop_front() {
+            let rings = self.rings_of.get(&atom).cloned().unwrap_or_default();
+            for ring in rings {
+                if !self.placed_rings[ring] {
+                    queue.extend(self.place_ring(ring));
+                }
+            }
+            queue.extend(self.place_chain(atom));
+        }
+        self.positions
+    }
+
+    /// Place unplaced atoms of a ring as a regular polygon, next to atoms of
+    /// the ring which are already placed. Returns atoms newly placed.
+    fn place_ring(&mut self, ring_index: usize) -> Vec<NodeIndex> {
+        self.placed_rings[ring_index] = true;
+        let ring = &self.rings[ring_index];
+        let size = ring.len();
+        let radius = BOND_LENGTH / (2. * (PI / size as f64).sin());
+        let is_placed = |index: usize| self.positions.contains_key(&ring[index % size]);
+        // the longest run of placed atoms in the ring
+        let mut run: Option<(usize, usize)> = None;
+        for start in (0..size).filter(|start| is_placed(*start) && !is_placed(start + size - 1)) {
+            let length = (start..start + size)
+                .take_while(|index| is_placed(*index))
+                .count();
+            if run.is_none_or(|(_, longest)| length > longest) {
+                run = Some((start, length));
+            }
+        }
+        let Some((start, length)) = run else {
+            return vec![];
+        };
+        let first = self.positions[&ring[start]];
+        let last = self.positions[&ring[(start + length - 1) % size]];
+        let (center, from, sweep) = if length == 1 {
+            let direction = self.away_from_neighbors(ring[start]).unwrap_or((1., 0.));
+            let center = add(first, scale(direction, radius));
+            (center, angle(sub(first, center)), 2. * PI)
+        } else {
+            // put the new ring on the other side of atoms around the run
+            let mut around = vec![];
+            for index in start..start + length {
+                let atom = ring[index % size];
+                if index != start && index != start + length - 1 {
+                    around.push(self.positions[&atom]);
+                }
+                for neighbor in self.workspace.graph.neighbors_undirected(atom) {
+                    if !ring.contains(&neighbor) {
+                        if let Some(point) = self.positions.get(&neighbor) {
+                            around.push(*point);
+                        }
+                    }
+                }
+            }
+            let middle = scale(add(first, last), 0.5);
+            let half = distance(first, last) / 2.;
+            let height = (radius * radius - half * half).max(0.).sqrt();
+            let normal = unit((first.1 - last.1, last.0 - first.0)).unwrap_or((0., 1.));
+            let mut center = add(middle, scale(normal, height));
+            if let Some(centroid) = centroid(&around) {
+                let other = sub(middle, scale(normal, height));
+                if distance(other, centroid) > distance(center, centroid) {
+                    center = other;
+                }
+            }
+            let from = angle(sub(last, center));
+            let to = angle(sub(first, center));
+            let sweep = (to - from).rem_euclid(2. * PI);
+            // go the longer way around the center, away from the run
+            if sweep >= PI {
+                (center, from, sweep)
+            } else {
+                (center, from, sweep - 2. * PI)
+            }
+        };
+        let unplaced = size - length;
+        let step = sweep / (unplaced + 1) as f64;
+        let mut placed = vec![];
+        for offset in 1..=unplaced {
+            let atom = ring[(start + length - 1 + offset) % size];
+            if self.positions.contains_key(&atom) {
+                continue;
+            }
+            let turn = from + step * offset as f64;
+            self.positions.insert(
+                atom,
+                add(center, (radius * turn.cos(), radius * turn.sin())),
+            );
+            placed.push(atom);
+        }
+        placed
+    }
+
+    /// Place unplaced neighbors of an atom not in rings, a single neighbor
+    /// goes on the other side of the last turn to make a zigzag.
+    fn place_chain(&mut self, atom: NodeIndex) -> Vec<NodeIndex> {
+        let mut unplaced = vec![];
+        let mut placed = vec![];
+        for neighbor in self.workspace.graph.neighbors_undirected(atom) {
+            if self.positions.contains_key(&neighbor) {
+                if !placed.contains(&neighbor) {
+                    placed.push(neighbor);
+                }
+            } else if !unplaced.contains(&neighbor) {
+                unplaced.push(neighbor);
+            }
+        }
+        if unplaced.is_empty() {
+            return vec![];
+        }
+        let count = unplaced.len() as f64;
+        let position = self.positions[&atom];
+        let angles = if placed.is_empty() {
+            self.turns.insert(atom, 1.);
+            (0..unplaced.len())
+                .map(|index| PI / 6. + 2. * PI * index as f64 / count)
+                .collect::<Vec<_>>()
+        } else {
+            let direction = self.away_from_neighbors(atom).unwrap_or((1., 0.));
+            let base = angle(direction);
+            if self.rings_of.contains_key(&atom) || placed.len() > 1 {
+                // substituents of rings spread around the outer direction
+                (0..unplaced.len())
+                    .map(|index| base + PI / 6. * (2. * index as f64 - count + 1.))
+                    .collect()
+            } else if unplaced.len() == 1 {
+                let mut turn = -self.turns.get(&placed[0]).copied().unwrap_or(-1.);
+                // turn to the other side if it's crowded
+                let space = |turn: f64| self.space_at(position, base + turn * PI / 3.);
+                if space(turn) < BOND_LENGTH && space(-turn) > space(turn) {
+                    turn = -turn;
+                }
+                self.turns.insert(atom, turn);
+                if self.is_linear(atom) {
+                    vec![base]
+                } else {
+                    vec![base + turn * PI / 3.]
+                }
+            } else {
+                (0..unplaced.len())
+                    .map(|index| base + PI - 2. * PI * (index + 1) as f64 / (count + 1.))
+                    .collect()
+            }
+        };
+        for (neighbor, turn) in unplaced.iter().zip(angles) {
+            let point = (BOND_LENGTH * turn.cos(), BOND_LENGTH * turn.sin());
+            self.positions.insert(*neighbor, add(position, point));
+        }
+        unplaced
+    }
+
+    /// direction from placed neighbors of an atom to the atom
+    fn away_from_neighbors(&self, atom: NodeIndex) -> Option<Point> {
+        let neighbors = self
+            .workspace
+            .graph
+            .neighbors_undirected(atom)
+            .filter_map(|neighbor| self.positions.get(&neighbor).copied())
+            .collect::<Vec<_>>();
+        unit(sub(self.positions[&atom], centroid(&neighbors)?))
+    }
+
+    /// distance from a point a bond away in the direction to the nearest
+    /// placed atom
+    fn space_at(&self, position: Point, direction: f64) -> f64 {
+        let point = add(
+            position,
+            (BOND_LENGTH * direction.cos(), BOND_LENGTH * direction.sin()),
+        );
+        self.positions
+            .values()
+            .map(|other| distance(point, *other))
+            .fold(f64::MAX, f64::min)
+    }
+
+    /// An atom with a triple bond or two double bonds is linear
+    fn is_linear(&self, atom: NodeIndex) -> bool {
+        let bonds = self
+            .workspace
+            .graph
+            .edges_directed(atom, Incoming)
+            .chain(self.workspace.graph.edges_directed(atom, Outgoing))
+            .map(|edge| edge.weight().bond_type())
+            .collect::<Vec<_>>();
+        bonds.contains(&BondType::Triple)
+            || bonds
+                .iter()
+                .filter(|bond| **bond == BondType::Double)
+                .count()
+                == 2
+    }
+}
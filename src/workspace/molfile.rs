@@ -0,0 +1,559 @@
+mod v2000;
+mod v3000;
+
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use petgraph::{
+    stable_graph::{EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+};
+
+use crate::{
+    definitions::{
+        atom::Atom,
+        bond::{Bond, BondType},
+        chirality::ChiralityType,
+        element::Element,
+    },
+    geometry::space::{dot, sub, Vector},
+};
+
+use super::{
+    structure::{SGroup, StructureId},
+    Workspace,
+};
+
+/// Atoms and bonds of a molfile connection table, atoms are indexed from 0.
+#[derive(Debug, Default)]
+struct Block {
+    name: Option<String>,
+    atoms: Vec<BlockAtom>,
+    bonds: Vec<BlockBond>,
+    sgroups: Vec<BlockSGroup>,
+}
+
+#[derive(Debug)]
+struct BlockAtom {
+    atom: Atom,
+    /// 1 and 2 for the two configurations, others are unknown
+    parity: u8,
+    /// total valence, used when hydrogens are given explicitly
+    valence: Option<usize>,
+}
+
+#[derive(Debug)]
+struct BlockBond {
+    from: usize,
+    to: usize,
+    bond_type: BondType,
+    stereo: BondStereo,
+}
+
+/// Stereo flag of a bond, a wedge starts from the first atom of the bond
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BondStereo {
+    None,
+    Wedge,
+    Hash,
+    /// the configuration of the stereo center or double bond is unknown
+    Either,
+}
+
+#[derive(Debug)]
+struct BlockSGroup {
+    kind: String,
+    atoms: Vec<usize>,
+    crossing_bonds: Vec<(usize, usize)>,
+    fields: Vec<(String, String)>,
+}
+
+/// Here implements reading and writing MDL molfiles
+impl Workspace {
+    /// Create a workspace with the structure of a molblock
+    pub fn from_molblock(molblock: &str) -> Result<Self, String> {
+        let mut workspace = Self::new();
+        workspace.add_molblock(molblock)?;
+        Ok(workspace)
+    }
+
+    /// Add the structure of a molblock into workspace. The title line is
+    /// used as its name, atom aliases written as `{...}` are read as
+    /// selectors.
+    pub fn add_molblock(&mut self, molblock: &str) -> Result<StructureId, String> {
+        let lines = molblock.lines().collect::<Vec<_>>();
+        let counts = lines.get(3).ok_or("Molblock shall have at least 4 lines")?;
+        let mut block = if counts.contains("V3000") {
+            v3000::parse(&lines)?
+        } else {
+            v2000::parse(&lines)?
+        };
+        // a molfile without coordinates has all atoms at the origin
+        if block
+            .atoms
+            .iter()
+            .filter_map(|block_atom| block_atom.atom.coordinates)
+            .all(|coordinates| coordinates == [0.0; 3])
+        {
+            for block_atom in block.atoms.iter_mut() {
+                block_atom.atom.coordinates = None;
+            }
+        }
+        Ok(self.add_block(block))
+    }
+
+    /// Write the structure with all its components as a molblock. V2000 is
+    /// used unless there are more than 999 atoms or bonds, enhanced stereo
+    /// or sgroups. Returns None if there are bonds can't be written.
+    pub fn to_molblock(&self, structure_node: NodeIndex) -> Option<String> {
+        let block = self.to_block(structure_node)?;
+        if block.atoms.len() > 999
+            || block.bonds.len() > 999
+            || !block.sgroups.is_empty()
+            || block
+                .atoms
+                .iter()
+                .any(|block_atom| block_atom.atom.stereo_group.is_some())
+        {
+            v3000::write(&block)
+        } else {
+            v2000::write(&block)
+        }
+    }
+
+    /// Write the structure with all its components as a V3000 molblock.
+    pub fn to_molblock_v3000(&self, structure_node: NodeIndex) -> Option<String> {
+        v3000::write(&self.to_block(structure_node)?)
+    }
+}
+
+/// Private functions used upon
+impl Workspace {
+    fn add_block(&mut self, block: Block) -> StructureId {
+        let nodes = block
+            .atoms
+            .iter()
+            .map(|block_atom| self.graph.add_node(block_atom.atom.clone()))
+            .collect::<Vec<_>>();
+        let bonds = block
+            .bonds
+            .iter()
+            .map(|bond| {
+                (
+                    nodes[bond.from],
+                    nodes[bond.to],
+                    Bond::new(bond.bond_type, false),
+                )
+            })
+            .collect::<Vec<_>>();
+        let roots = self.build_forest(&nodes, &bonds);
+        let index_of = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (*node, index))
+            .collect::<HashMap<_, _>>();
+        for (node, block_atom) in nodes.iter().zip(block.atoms.iter()) {
+            if let Some(valence) = block_atom.valence {
+                let used = self.used_valence(*node);
+                if let Some(atom) = self.get_atom_mut(*node) {
+                    atom.explicit_hydrogen = valence.saturating_sub(used);
+                }
+            }
+            let chirality_type = match block_atom.parity {
+                1 => Some(ChiralityType::Clockwise),
+                2 => Some(ChiralityType::Counter),
+                _ => None,
+            };
+            if let Some(chirality_type) = chirality_type.and_then(|chirality_type| {
+                let neighbors = self.chiral_neighbors(*node)?;
+                chirality_type.reorder(&self.parity_order(&neighbors, &index_of), &neighbors)
+            }) {
+                if let Some(atom) = self.get_atom_mut(*node) {
+                    atom.chirality_type = Some(chirality_type);
+                }
+            }
+        }
+        let id = self.register_structure(roots);
+        if let Some(info) = self.structure_info_mut(id) {
+            info.name = block.name;
+            info.sgroups = block
+                .sgroups
+                .into_iter()
+                .map(|sgroup| SGroup {
+                    kind: sgroup.kind,
+                    atoms: sgroup.atoms.iter().map(|atom| nodes[*atom]).collect(),
+                    crossing_bonds: sgroup
+                        .crossing_bonds
+                        .iter()
+                        .map(|(a, b)| (nodes[*a], nodes[*b]))
+                        .collect(),
+                    fields: sgroup.fields,
+                })
+                .collect();
+        }
+        self.read_bond_stereo(&nodes, &block.bonds);
+        id
+    }
+
+    fn to_block(&self, structure_node: NodeIndex) -> Option<Block> {
+        let mut nodes = vec![];
+        for root in self.components(structure_node)? {
+            nodes.append(&mut self.get_atoms_of_structure(root)?);
+        }
+        let index_of = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (*node, index))
+            .collect::<HashMap<_, _>>();
+        let mut atoms = vec![];
+        let mut bonds = vec![];
+        for node in nodes.iter() {
+            let atom = self.get_atom(*node)?;
+            let parity = atom
+                .chirality_type
+                .and_then(|chirality_type| {
+                    let neighbors = self.chiral_neighbors(*node)?;
+                    chirality_type.reorder(&neighbors, &self.parity_order(&neighbors, &index_of))
+                })
+                .map_or(0, |chirality_type| match chirality_type {
+                    ChiralityType::Clockwise => 1,
+                    ChiralityType::Counter => 2,
+                });
+            let valence = if atom.explicit_hydrogen != 0 {
+                Some(atom.explicit_hydrogen + self.used_valence(*node))
+            } else {
+                None
+            };
+            atoms.push(BlockAtom {
+                atom: atom.clone(),
+                parity,
+                valence,
+            });
+            for edge in self.graph.edges(*node) {
+                bonds.push((
+                    edge.id(),
+                    BlockBond {
+                        from: index_of[&edge.source()],
+                        to: *index_of.get(&edge.target())?,
+                        bond_type: edge.weight().bond_type(),
+                        stereo: BondStereo::None,
+                    },
+                ));
+            }
+        }
+        bonds.sort_by_key(|(edge, _)| *edge);
+        self.write_bond_stereo(&nodes, &index_of, &mut bonds);
+        let info = self
+            .structure_of(structure_node)
+            .and_then(|id| self.structure_info(id));
+        let sgroups = info
+            .iter()
+            .flat_map(|info| info.sgroups.iter())
+            .filter_map(|sgroup| {
+                Some(BlockSGroup {
+                    kind: sgroup.kind.clone(),
+                    atoms: sgroup
+                        .atoms
+                        .iter()
+                        .map(|atom| index_of.get(atom).copied())
+                        .collect::<Option<_>>()?,
+                    crossing_bonds: sgroup
+                        .crossing_bonds
+                        .iter()
+                        .map(|(a, b)| Some((*index_of.get(a)?, *index_of.get(b)?)))
+                        .collect::<Option<_>>()?,
+                    fields: sgroup.fields.clone(),
+                })
+            })
+            .collect();
+        Some(Block {
+            name: info.and_then(|info| info.name.clone()),
+            atoms,
+            bonds: bonds.into_iter().map(|(_, bond)| bond).collect(),
+            sgroups,
+        })
+    }
+
+    /// Read stereo given by bonds: a wedge gives chirality to the atom it
+    /// starts from by 2D coordinates, an `Either` bond leaves the atom
+    /// without chirality, and double bonds not flagged `Either` get `/` `\`
+    /// bonds from coordinates.
+    fn read_bond_stereo(&mut self, nodes: &[NodeIndex], bonds: &[BlockBond]) -> Option<()> {
+        let coordinates = nodes
+            .iter()
+            .map(|node| Some((*node, self.get_atom(*node)?.coordinates?)))
+            .collect::<Option<HashMap<_, _>>>()?;
+        let mut heights: HashMap<NodeIndex, Vec<(NodeIndex, f64)>> = HashMap::new();
+        let mut double_bonds = vec![];
+        for bond in bonds {
+            let (from, to) = (nodes[bond.from], nodes[bond.to]);
+            match (bond.bond_type, bond.stereo) {
+                (BondType::Single, BondStereo::Wedge) => {
+                    heights.entry(from).or_default().push((to, 1.))
+                }
+                (BondType::Single, BondStereo::Hash) => {
+                    heights.entry(from).or_default().push((to, -1.))
+                }
+                (BondType::Single, BondStereo::Either) => {
+                    self.get_atom_mut(from)?.chirality_type = None;
+                }
+                (BondType::Double, BondStereo::None) => double_bonds.push((from, to)),
+                _ => {}
+            }
+        }
+        if coordinates.values().all(|[_, _, z]| *z == 0.) {
+            let positions = coordinates
+                .iter()
+                .map(|(node, [x, y, _])| (*node, (*x, *y)))
+                .collect();
+            for (center, heights) in heights {
+                if let Some(chirality) = self.wedged_chirality(center, &heights, &positions) {
+                    self.get_atom_mut(center)?.chirality_type = Some(chirality);
+                }
+            }
+        }
+        let ring_bonds = self.ring_bonds(*nodes.first()?);
+        for (a, b) in double_bonds {
+            if !ring_bonds.contains(&(a.min(b), a.max(b))) {
+                self.read_double_bond_stereo(a, b, &coordinates);
+            }
+        }
+        Some(())
+    }
+
+    /// Give `/` `\` bonds to neighbors of a double bond by which sides of
+    /// the double bond they are on. A side already given by another double
+    /// bond is kept.
+    fn read_double_bond_stereo(
+        &mut self,
+        a: NodeIndex,
+        b: NodeIndex,
+        coordinates: &HashMap<NodeIndex, Vector>,
+    ) -> Option<()> {
+        let (neighbor_a, up_a) = self.stereo_side(a, b)?;
+        let (neighbor_b, up_b) = self.stereo_side(b, a)?;
+        let axis = sub(coordinates[&b], coordinates[&a]);
+        let across = |neighbor: NodeIndex, end: NodeIndex| {
+            let vector = sub(coordinates[&neighbor], coordinates[&end]);
+            let along = dot(vector, axis) / dot(axis, axis).max(1e-12);
+            [0, 1, 2].map(|index| vector[index] - along * axis[index])
+        };
+        let product = dot(across(neighbor_a, a), across(neighbor_b, b));
+        if product.abs() < 1e-6 {
+            return None;
+        }
+        // `X/C=C/Y` is trans, it's `X/C` and `Y\C` read from neighbors
+        let cis = product > 0.;
+        match (up_a, up_b) {
+            (None, None) => {
+                self.set_stereo_bond(neighbor_a, a, true)?;
+                self.set_stereo_bond(neighbor_b, b, cis)
+            }
+            (Some(up), None) => self.set_stereo_bond(neighbor_b, b, up == cis),
+            (None, Some(up)) => self.set_stereo_bond(neighbor_a, a, up == cis),
+            (Some(_), Some(_)) => Some(()),
+        }
+    }
+
+    /// A neighbor of an end of a double bond to write `/` or `\` with, and
+    /// if it's `/` read from the neighbor when it's given already. None if
+    /// the end has no such neighbor, or it has two same terminal ones.
+    fn stereo_side(&self, end: NodeIndex, other: NodeIndex) -> Option<(NodeIndex, Option<bool>)> {
+        let sides = self
+            .graph
+            .neighbors_undirected(end)
+            .filter(|neighbor| *neighbor != other)
+            .filter_map(|neighbor| {
+                let (bond, edge, _) = self.get_edge_undirected(neighbor, end)?;
+                let (source, _) = self.graph.edge_endpoints(edge)?;
+                let up = match bond.bond_type() {
+                    BondType::Single => None,
+                    BondType::UpSingle => Some(source == neighbor),
+                    BondType::DownSingle => Some(source != neighbor),
+                    _ => return None,
+                };
+                Some((neighbor, up))
+            })
+            .collect::<Vec<_>>();
+        if let [(first, _), (second, _)] = sides[..] {
+            let terminal = |node: NodeIndex| self.graph.neighbors_undirected(node).count() == 1;
+            let (first_atom, second_atom) = (self.get_atom(first)?, self.get_atom(second)?);
+            if terminal(first)
+                && terminal(second)
+                && first_atom.element == second_atom.element
+                && first_atom.charge == second_atom.charge
+            {
+                return None;
+            }
+        }
+        sides
+            .into_iter()
+            .min_by_key(|(neighbor, up)| (up.is_none(), *neighbor))
+    }
+
+    /// Make the bond `/` read from the neighbor to the end if `up`, or `\`
+    fn set_stereo_bond(&mut self, neighbor: NodeIndex, end: NodeIndex, up: bool) -> Option<()> {
+        let (bond, edge, _) = self.get_edge_undirected(neighbor, end)?;
+        let ring = bond.is_ring_bond();
+        let (source, _) = self.graph.edge_endpoints(edge)?;
+        let bond_type = if up == (source == neighbor) {
+            BondType::UpSingle
+        } else {
+            BondType::DownSingle
+        };
+        self.graph[edge] = Bond::new(bond_type, ring);
+        Some(())
+    }
+
+    /// Write stereo of bonds: a wedge from each stereo center if atoms have
+    /// 2D coordinates, and `Either` for double bonds which could have a
+    /// configuration but have no `/` `\` bonds. Double bonds with them are
+    /// given by coordinates, which are left without configuration if there
+    /// are no coordinates.
+    fn write_bond_stereo(
+        &self,
+        nodes: &[NodeIndex],
+        index_of: &HashMap<NodeIndex, usize>,
+        bonds: &mut [(EdgeIndex, BlockBond)],
+    ) -> Option<()> {
+        let positions = nodes
+            .iter()
+            .map(|node| {
+                let [x, y, z] = self.get_atom(*node)?.coordinates?;
+                (z == 0.).then_some((*node, (x, y)))
+            })
+            .collect::<Option<HashMap<_, _>>>();
+        let wedges = positions
+            .map(|positions| self.wedges(nodes, &positions))
+            .unwrap_or_default();
+        let ring_bonds = self.ring_bonds(*nodes.first()?);
+        for (edge, bond) in bonds.iter_mut() {
+            let (from, to) = self.graph.edge_endpoints(*edge)?;
+            if let Some((center, hashed)) = wedges.get(edge) {
+                let other = if *center == from { to } else { from };
+                bond.from = index_of[center];
+                bond.to = index_of[&other];
+                bond.stereo = if *hashed {
+                    BondStereo::Hash
+                } else {
+                    BondStereo::Wedge
+                };
+            } else if bond.bond_type == BondType::Double
+                && !ring_bonds.contains(&(from.min(to), from.max(to)))
+            {
+                let sides = [self.stereo_side(from, to), self.stereo_side(to, from)];
+                if let [Some((_, None)), Some((_, None))] = sides {
+                    bond.stereo = BondStereo::Either;
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Bonds in rings of the structure, by their atoms in order
+    fn ring_bonds(&self, structure_node: NodeIndex) -> HashSet<(NodeIndex, NodeIndex)> {
+        self.rings(structure_node)
+            .into_iter()
+            .flatten()
+            .flat_map(|ring| {
+                (0..ring.len()).map(move |index| {
+                    let (a, b) = (ring[index], ring[(index + 1) % ring.len()]);
+                    (a.min(b), a.max(b))
+                })
+            })
+            .collect()
+    }
+
+    /// Neighbors of a stereo center in the order molfile parity refers to:
+    /// by their index in the molfile, hydrogens are the last ones.
+    fn parity_order(
+        &self,
+        neighbors: &[Option<NodeIndex>],
+        index_of: &HashMap<NodeIndex, usize>,
+    ) -> Vec<Option<NodeIndex>> {
+        let mut ordered = neighbors.to_vec();
+        ordered.sort_by_key(|neighbor| match neighbor {
+            Some(neighbor) => (
+                self.get_atom(*neighbor)
+                    .is_some_and(|atom| atom.element == Element::H),
+                index_of.get(neighbor).copied().unwrap_or(usize::MAX),
+            ),
+            None => (true, usize::MAX),
+        });
+        ordered
+    }
+
+    /// valence used by bonds, an aromatic atom uses one more
+    fn used_valence(&self, node: NodeIndex) -> usize {
+        let bonds = self
+            .graph
+            .neighbors_undirected(node)
+            .filter_map(|neighbor| self.get_edge_undirected(node, neighbor))
+            .map(|(bond, _, _)| bond.bond_type().valence())
+            .sum::<usize>();
+        let aromatic = self.get_atom(node).is_some_and(|atom| atom.aromatic);
+        bonds + aromatic as usize
+    }
+}
+
+/// Read an element symbol of molfile, `D` and `T` are hydrogen isotopes.
+fn parse_element(symbol: &str) -> Result<(Element, Option<u16>), String> {
+    match symbol {
+        "D" => Ok((Element::H, Some(2))),
+        "T" => Ok((Element::H, Some(3))),
+        "A" | "*" => Ok((Element::Any, None)),
+        _ => Element::from_str(symbol)
+            .map(|element| (element, None))
+            .map_err(|_| format!("Unknown element {}", symbol)),
+    }
+}
+
+/// Read an atom alias, selectors are written as `{...}` and other aliases
+/// are labels.
+fn set_alias(atom: &mut Atom, alias: &str) {
+    let alias = alias.trim();
+    match alias
+        .strip_prefix('{')
+        .and_then(|alias| alias.strip_suffix('}'))
+    {
+        Some(selector) => atom.selector = Some(selector.to_string()),
+        None if !alias.is_empty() => atom.label = Some(alias.to_string()),
+        None => {}
+    }
+}
+
+/// the second line of molblock, with the dimension of coordinates
+fn program_line(block: &Block) -> String {
+    let coordinates = block
+        .atoms
+        .iter()
+        .filter_map(|block_atom| block_atom.atom.coordinates)
+        .collect::<Vec<_>>();
+    if coordinates.is_empty() {
+        "  SwS".to_string()
+    } else if coordinates.iter().all(|[_, _, z]| *z == 0.0) {
+        format!("  {:<8}{:10}2D", "SwS", "")
+    } else {
+        format!("  {:<8}{:10}3D", "SwS", "")
+    }
+}
+
+/// Molfile radical of given amount of unpaired electrons: 2 for doublet
+/// and 3 for triplet.
+fn radical_code(electrons: usize) -> usize {
+    match electrons {
+        0 => 0,
+        1 => 2,
+        _ => 3,
+    }
+}
+
+fn radical_electrons(code: usize) -> usize {
+    match code {
+        1 | 3 => 2,
+        2 => 1,
+        _ => 0,
+    }
+}
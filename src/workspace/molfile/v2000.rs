@@ -0,0 +1,299 @@
+use std::str::FromStr;
+
+use crate::definitions::{atom::Atom, bond::BondType};
+
+use super::{
+    parse_element, program_line, radical_code, radical_electrons, set_alias, Block, BlockAtom,
+    BlockBond, BondStereo,
+};
+
+/// Read a V2000 connection table and its properties block.
+pub(super) fn parse(lines: &[&str]) -> Result<Block, String> {
+    let counts = lines.get(3).copied().unwrap_or_default();
+    let atom_count: usize = number(counts, 0, 3)?;
+    let bond_count: usize = number(counts, 3, 6)?;
+    let mut block = Block {
+        name: lines
+            .first()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .map(String::from),
+        ..Default::default()
+    };
+    let mut lines = lines.iter().skip(4);
+    for index in 0..atom_count {
+        let line = lines
+            .next()
+            .ok_or(format!("Molblock ends before atom {}", index + 1))?;
+        let (element, isotope) = parse_element(field(line, 31, 34))?;
+        let coordinates = Some([
+            number(line, 0, 10)?,
+            number(line, 10, 20)?,
+            number(line, 20, 30)?,
+        ]);
+        let charge = match number::<u8>(line, 36, 39)? {
+            1 => 3,
+            2 => 2,
+            3 => 1,
+            5 => -1,
+            6 => -2,
+            7 => -3,
+            _ => 0,
+        };
+        let valence = match number::<usize>(line, 48, 51)? {
+            0 => None,
+            15 => Some(0),
+            valence => Some(valence),
+        };
+        let react_id = match number::<usize>(line, 60, 63)? {
+            0 => None,
+            react_id => Some(react_id),
+        };
+        block.atoms.push(BlockAtom {
+            atom: Atom {
+                element,
+                isotope,
+                charge,
+                chirality_type: None,
+                explicit_hydrogen: 0,
+                selector: None,
+                aromatic: false,
+                react_id,
+                stereo_group: None,
+                label: None,
+                coordinates,
+                radical_electrons: 0,
+            },
+            parity: number(line, 39, 42)?,
+            valence,
+        });
+    }
+    for index in 0..bond_count {
+        let line = lines
+            .next()
+            .ok_or(format!("Molblock ends before bond {}", index + 1))?;
+        let from = atom_index(&block, number(line, 0, 3)?)?;
+        let to = atom_index(&block, number(line, 3, 6)?)?;
+        let bond_type = match number::<u8>(line, 6, 9)? {
+            1 => BondType::Single,
+            2 => BondType::Double,
+            3 => BondType::Triple,
+            4 => BondType::Aromatic,
+            bond_type => return Err(format!("Unsupported bond type {}", bond_type)),
+        };
+        let stereo = match (bond_type, number::<u8>(line, 9, 12)?) {
+            (BondType::Single, 1) => BondStereo::Wedge,
+            (BondType::Single, 6) => BondStereo::Hash,
+            (BondType::Single, 4) | (BondType::Double, 3) => BondStereo::Either,
+            _ => BondStereo::None,
+        };
+        if bond_type == BondType::Aromatic {
+            block.atoms[from].atom.aromatic = true;
+            block.atoms[to].atom.aromatic = true;
+        }
+        block.bonds.push(BlockBond {
+            from,
+            to,
+            bond_type,
+            stereo,
+        });
+    }
+    let mut charge_given = false;
+    while let Some(line) = lines.next() {
+        if line.starts_with("M  END") {
+            break;
+        } else if line.starts_with("M  CHG") {
+            if !charge_given {
+                charge_given = true;
+                for block_atom in block.atoms.iter_mut() {
+                    block_atom.atom.charge = 0;
+                }
+            }
+            for (index, charge) in property_pairs(line)? {
+                let index = atom_index(&block, index)?;
+                block.atoms[index].atom.charge = charge;
+            }
+        } else if line.starts_with("M  ISO") {
+            for (index, isotope) in property_pairs(line)? {
+                let index = atom_index(&block, index)?;
+                block.atoms[index].atom.isotope = Some(isotope as u16);
+            }
+        } else if line.starts_with("A  ") {
+            let index = atom_index(&block, number(line, 3, 6)?)?;
+            if let Some(alias) = lines.next() {
+                set_alias(&mut block.atoms[index].atom, alias);
+            }
+        } else if line.starts_with("M  RAD") {
+            for (index, radical) in property_pairs(line)? {
+                let index = atom_index(&block, index)?;
+                block.atoms[index].atom.radical_electrons = radical_electrons(radical as usize);
+            }
+        }
+    }
+    Ok(block)
+}
+
+/// Write a V2000 molblock, returns None if there are more than 999 atoms or
+/// bonds, or bonds V2000 can't represent.
+pub(super) fn write(block: &Block) -> Option<String> {
+    if block.atoms.len() > 999 || block.bonds.len() > 999 {
+        return None;
+    }
+    let chiral = block.atoms.iter().any(|block_atom| block_atom.parity != 0);
+    let mut lines = vec![
+        block.name.clone().unwrap_or_default(),
+        program_line(block),
+        String::new(),
+        format!(
+            "{:>3}{:>3}  0  0{:>3}  0  0  0  0  0999 V2000",
+            block.atoms.len(),
+            block.bonds.len(),
+            chiral as u8
+        ),
+    ];
+    for block_atom in block.atoms.iter() {
+        let atom = &block_atom.atom;
+        let [x, y, z] = atom.coordinates.unwrap_or_default();
+        lines.push(format!(
+            "{:>10.4}{:>10.4}{:>10.4} {:<3} 0  0{:>3}  0  0{:>3}  0  0  0{:>3}  0  0",
+            x,
+            y,
+            z,
+            atom.element.as_ref(),
+            block_atom.parity,
+            match block_atom.valence {
+                Some(0) => 15,
+                Some(valence) => valence,
+                None => 0,
+            },
+            atom.react_id.unwrap_or(0)
+        ));
+    }
+    for bond in block.bonds.iter() {
+        let bond_type = match bond.bond_type {
+            BondType::Single | BondType::UpSingle | BondType::DownSingle => 1,
+            BondType::Double => 2,
+            BondType::Triple => 3,
+            BondType::Aromatic => 4,
+            BondType::Quad | BondType::NoBond => return None,
+        };
+        let stereo = match (bond_type, bond.stereo) {
+            (1, BondStereo::Wedge) => 1,
+            (1, BondStereo::Hash) => 6,
+            (1, BondStereo::Either) => 4,
+            (2, BondStereo::Either) => 3,
+            _ => 0,
+        };
+        lines.push(format!(
+            "{:>3}{:>3}{:>3}{:>3}",
+            bond.from + 1,
+            bond.to + 1,
+            bond_type,
+            stereo
+        ));
+    }
+    let charges = block
+        .atoms
+        .iter()
+        .enumerate()
+        .filter(|(_, block_atom)| block_atom.atom.charge != 0)
+        .map(|(index, block_atom)| (index + 1, block_atom.atom.charge))
+        .collect::<Vec<_>>();
+    write_property_pairs(&mut lines, "CHG", &charges);
+    let isotopes = block
+        .atoms
+        .iter()
+        .enumerate()
+        .filter_map(|(index, block_atom)| {
+            block_atom
+                .atom
+                .isotope
+                .map(|isotope| (index + 1, isotope as isize))
+        })
+        .collect::<Vec<_>>();
+    write_property_pairs(&mut lines, "ISO", &isotopes);
+    let radicals = block
+        .atoms
+        .iter()
+        .enumerate()
+        .filter(|(_, block_atom)| block_atom.atom.radical_electrons != 0)
+        .map(|(index, block_atom)| {
+            (
+                index + 1,
+                radical_code(block_atom.atom.radical_electrons) as isize,
+            )
+        })
+        .collect::<Vec<_>>();
+    write_property_pairs(&mut lines, "RAD", &radicals);
+    for (index, block_atom) in block.atoms.iter().enumerate() {
+        let atom = &block_atom.atom;
+        let alias = match (&atom.selector, &atom.label) {
+            (Some(selector), _) => format!("{{{}}}", selector),
+            (None, Some(label)) => label.clone(),
+            (None, None) => continue,
+        };
+        lines.push(format!("A  {:>3}", index + 1));
+        lines.push(alias);
+    }
+    lines.push("M  END".to_string());
+    let mut molblock = lines.join("\n");
+    molblock.push('\n');
+    Some(molblock)
+}
+
+/// a field of fixed columns, lines might be shorter than the format
+fn field(line: &str, start: usize, end: usize) -> &str {
+    line.get(start..end.min(line.len()))
+        .unwrap_or_default()
+        .trim()
+}
+
+/// a number in fixed columns, an empty field is 0
+fn number<T: FromStr + Default>(line: &str, start: usize, end: usize) -> Result<T, String> {
+    let text = field(line, start, end);
+    if text.is_empty() {
+        Ok(T::default())
+    } else {
+        text.parse()
+            .map_err(|_| format!("Invalid number {} in line {}", text, line))
+    }
+}
+
+/// Check an atom number of molfile and get its index from 0
+fn atom_index(block: &Block, number: usize) -> Result<usize, String> {
+    if number >= 1 && number <= block.atoms.len() {
+        Ok(number - 1)
+    } else {
+        Err(format!("Atom {} not found", number))
+    }
+}
+
+/// Read (atom, value) pairs of a `M  XXX` property line
+fn property_pairs(line: &str) -> Result<Vec<(usize, isize)>, String> {
+    let values = line[6..]
+        .split_whitespace()
+        .map(|value| {
+            value
+                .parse::<isize>()
+                .map_err(|_| format!("Invalid number {} in line {}", value, line))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let count = values.first().copied().unwrap_or_default().max(0) as usize;
+    if values.len() < 1 + count * 2 {
+        return Err(format!("Incomplete property line {}", line));
+    }
+    Ok((0..count)
+        .map(|index| (values[1 + index * 2] as usize, values[2 + index * 2]))
+        .collect())
+}
+
+/// Write (atom, value) pairs as `M  XXX` property lines, 8 pairs per line
+fn write_property_pairs(lines: &mut Vec<String>, name: &str, pairs: &[(usize, isize)]) {
+    for chunk in pairs.chunks(8) {
+        let mut line = format!("M  {}{:>3}", name, chunk.len());
+        for (index, value) in chunk {
+            line.push_str(&format!(" {:>3} {:>3}", index, value));
+        }
+        lines.push(line);
+    }
+}
@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+
+use crate::definitions::{atom::Atom, bond::BondType, chirality::StereoGroup};
+
+use super::{
+    parse_element, program_line, radical_code, Block, BlockAtom, BlockBond, BlockSGroup, BondStereo,
+};
+
+/// name of data sgroups carrying selectors
+const SELECTOR_FIELD: &str = "SWS_SELECTOR";
+
+/// Read a V3000 CTAB with its sgroups and enhanced stereo collections.
+/// Sgroups with parents, attachment points or contracted bonds are rejected.
+pub(super) fn parse(lines: &[&str]) -> Result<Block, String> {
+    let mut block = Block {
+        name: lines
+            .first()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .map(String::from),
+        ..Default::default()
+    };
+    // V3000 atoms and bonds have their own ids
+    let mut atom_of = HashMap::new();
+    let mut bond_of = HashMap::new();
+    let mut section = String::new();
+    for line in joined_lines(lines) {
+        let tokens = tokenize(&line);
+        match tokens.as_slice() {
+            ["BEGIN", name, ..] => section = name.to_string(),
+            ["END", ..] => section.clear(),
+            _ if section == "ATOM" => {
+                let (id, block_atom) = parse_atom(&tokens)?;
+                atom_of.insert(id, block.atoms.len());
+                block.atoms.push(block_atom);
+            }
+            _ if section == "BOND" => {
+                let id = tokens.first().copied().unwrap_or_default();
+                let bond_type = match tokens.get(1).copied().unwrap_or_default() {
+                    "1" => BondType::Single,
+                    "2" => BondType::Double,
+                    "3" => BondType::Triple,
+                    "4" => BondType::Aromatic,
+                    bond_type => return Err(format!("Unsupported bond type {}", bond_type)),
+                };
+                let from = find_id(&atom_of, tokens.get(2))?;
+                let to = find_id(&atom_of, tokens.get(3))?;
+                if bond_type == BondType::Aromatic {
+                    block.atoms[from].atom.aromatic = true;
+                    block.atoms[to].atom.aromatic = true;
+                }
+                let stereo = match (bond_type, bond_config(&tokens)?) {
+                    (BondType::Single, 1) => BondStereo::Wedge,
+                    (BondType::Single, 3) => BondStereo::Hash,
+                    (BondType::Single | BondType::Double, 2) => BondStereo::Either,
+                    _ => BondStereo::None,
+                };
+                bond_of.insert(id.to_string(), block.bonds.len());
+                block.bonds.push(BlockBond {
+                    from,
+                    to,
+                    bond_type,
+                    stereo,
+                });
+            }
+            _ if section == "SGROUP" => {
+                let sgroup = parse_sgroup(&tokens, &atom_of, &bond_of, &block)?;
+                if sgroup.kind == "DAT"
+                    && field(&sgroup.fields, "FIELDNAME") == Some(SELECTOR_FIELD)
+                {
+                    let selector = field(&sgroup.fields, "FIELDDATA").unwrap_or_default();
+                    for atom in sgroup.atoms {
+                        block.atoms[atom].atom.selector = Some(selector.to_string());
+                    }
+                } else {
+                    block.sgroups.push(sgroup);
+                }
+            }
+            [name, list] if section == "COLLECTION" => {
+                let stereo_group = if *name == "MDLV30/STEABS" {
+                    StereoGroup::Absolute
+                } else if let Some(group) = name.strip_prefix("MDLV30/STEREL") {
+                    StereoGroup::Or(group.parse().unwrap_or_default())
+                } else if let Some(group) = name.strip_prefix("MDLV30/STERAC") {
+                    StereoGroup::And(group.parse().unwrap_or_default())
+                } else {
+                    continue;
+                };
+                for atom in parse_list(list.strip_prefix("ATOMS=").unwrap_or_default())? {
+                    let atom = find_id(&atom_of, Some(&atom))?;
+                    block.atoms[atom].atom.stereo_group = Some(stereo_group);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(block)
+}
+
+/// Write a V3000 molblock, returns None if there are bonds V3000 can't
+/// represent. Selectors are written as data sgroups.
+pub(super) fn write(block: &Block) -> Option<String> {
+    let mut lines = vec![
+        block.name.clone().unwrap_or_default(),
+        program_line(block),
+        String::new(),
+        "  0  0  0     0  0            999 V3000".to_string(),
+    ];
+    let selectors = block
+        .atoms
+        .iter()
+        .enumerate()
+        .filter_map(|(index, block_atom)| Some((index, block_atom.atom.selector.as_ref()?)))
+        .collect::<Vec<_>>();
+    let chiral = block.atoms.iter().any(|block_atom| block_atom.parity != 0);
+    push_line(&mut lines, "BEGIN CTAB");
+    push_line(
+        &mut lines,
+        &format!(
+            "COUNTS {} {} {} 0 {}",
+            block.atoms.len(),
+            block.bonds.len(),
+            block.sgroups.len() + selectors.len(),
+            chiral as u8
+        ),
+    );
+    push_line(&mut lines, "BEGIN ATOM");
+    for (index, block_atom) in block.atoms.iter().enumerate() {
+        let atom = &block_atom.atom;
+        let [x, y, z] = atom.coordinates.unwrap_or_default();
+        let mut line = format!(
+            "{} {} {:.4} {:.4} {:.4} {}",
+            index + 1,
+            atom.element.as_ref(),
+            x,
+            y,
+            z,
+            atom.react_id.unwrap_or(0)
+        );
+        if atom.charge != 0 {
+            line.push_str(&format!(" CHG={}", atom.charge));
+        }
+        if atom.radical_electrons != 0 {
+            line.push_str(&format!(" RAD={}", radical_code(atom.radical_electrons)));
+        }
+        if block_atom.parity != 0 {
+            line.push_str(&format!(" CFG={}", block_atom.parity));
+        }
+        if let Some(isotope) = atom.isotope {
+            line.push_str(&format!(" MASS={}", isotope));
+        }
+        match block_atom.valence {
+            Some(0) => line.push_str(" VAL=-1"),
+            Some(valence) => line.push_str(&format!(" VAL={}", valence)),
+            None => {}
+        }
+        push_line(&mut lines, &line);
+    }
+    push_line(&mut lines, "END ATOM");
+    let mut bond_of = HashMap::new();
+    if !block.bonds.is_empty() {
+        push_line(&mut lines, "BEGIN BOND");
+        for (index, bond) in block.bonds.iter().enumerate() {
+            let (from, to) = (&bond.from, &bond.to);
+            let bond_type = match bond.bond_type {
+                BondType::Single | BondType::UpSingle | BondType::DownSingle => 1,
+                BondType::Double => 2,
+                BondType::Triple => 3,
+                BondType::Aromatic => 4,
+                BondType::Quad | BondType::NoBond => return None,
+            };
+            bond_of.insert((*from, *to), index + 1);
+            bond_of.insert((*to, *from), index + 1);
+            let mut line = format!("{} {} {} {}", index + 1, bond_type, from + 1, to + 1);
+            let config = match (bond_type, bond.stereo) {
+                (1, BondStereo::Wedge) => 1,
+                (1 | 2, BondStereo::Either) => 2,
+                (1, BondStereo::Hash) => 3,
+                _ => 0,
+            };
+            if config != 0 {
+                line.push_str(&format!(" CFG={}", config));
+            }
+            push_line(&mut lines, &line);
+        }
+        push_line(&mut lines, "END BOND");
+    }
+    if !block.sgroups.is_empty() || !selectors.is_empty() {
+        push_line(&mut lines, "BEGIN SGROUP");
+        for (index, sgroup) in block.sgroups.iter().enumerate() {
+            let mut line = format!(
+                "{} {} 0 ATOMS={}",
+                index + 1,
+                sgroup.kind,
+                write_list(sgroup.atoms.iter().map(|atom| atom + 1))
+            );
+            if !sgroup.crossing_bonds.is_empty() {
+                let bonds = sgroup
+                    .crossing_bonds
+                    .iter()
+                    .map(|bond| bond_of.get(bond).copied())
+                    .collect::<Option<Vec<_>>>()?;
+                line.push_str(&format!(" XBONDS={}", write_list(bonds.into_iter())));
+            }
+            for (key, value) in sgroup.fields.iter() {
+                line.push_str(&format!(" {}={}", key, quote(value)));
+            }
+            push_line(&mut lines, &line);
+        }
+        for (index, (atom, selector)) in selectors.iter().enumerate() {
+            push_line(
+                &mut lines,
+                &format!(
+                    "{} DAT 0 ATOMS=(1 {}) FIELDNAME={} FIELDDATA={}",
+                    block.sgroups.len() + index + 1,
+                    atom + 1,
+                    SELECTOR_FIELD,
+                    quote(selector)
+                ),
+            );
+        }
+        push_line(&mut lines, "END SGROUP");
+    }
+    let mut collections: Vec<(StereoGroup, Vec<usize>)> = vec![];
+    for (index, block_atom) in block.atoms.iter().enumerate() {
+        if let Some(stereo_group) = block_atom.atom.stereo_group {
+            match collections
+                .iter_mut()
+                .find(|(group, _)| *group == stereo_group)
+            {
+                Some((_, atoms)) => atoms.push(index + 1),
+                None => collections.push((stereo_group, vec![index + 1])),
+            }
+        }
+    }
+    if !collections.is_empty() {
+        push_line(&mut lines, "BEGIN COLLECTION");
+        for (stereo_group, atoms) in collections {
+            let name = match stereo_group {
+                StereoGroup::Absolute => "MDLV30/STEABS".to_string(),
+                StereoGroup::Or(group) => format!("MDLV30/STEREL{}", group),
+                StereoGroup::And(group) => format!("MDLV30/STERAC{}", group),
+            };
+            push_line(
+                &mut lines,
+                &format!("{} ATOMS={}", name, write_list(atoms.into_iter())),
+            );
+        }
+        push_line(&mut lines, "END COLLECTION");
+    }
+    push_line(&mut lines, "END CTAB");
+    lines.push("M  END".to_string());
+    let mut molblock = lines.join("\n");
+    molblock.push('\n');
+    Some(molblock)
+}
+
+/// Get contents of `M  V30` lines, lines ending with `-` are continued by
+/// the next one.
+fn joined_lines(lines: &[&str]) -> Vec<String> {
+    let mut joined = vec![];
+    let mut continued: Option<String> = None;
+    for line in lines {
+        if let Some(content) = line.strip_prefix("M  V30 ") {
+            let mut current = continued.take().unwrap_or_default();
+            if let Some(content) = content.strip_suffix('-') {
+                current.push_str(content);
+                continued = Some(current);
+            } else {
+                current.push_str(content);
+                joined.push(current);
+            }
+        } else if line.starts_with("M  END") {
+            break;
+        }
+    }
+    joined
+}
+
+/// Split a line by whitespaces, except those in parentheses and quotes
+fn tokenize(line: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    let mut start = None;
+    let mut depth = 0;
+    let mut quoted = false;
+    for (index, char) in line.char_indices() {
+        match char {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            _ => {}
+        }
+        if char.is_whitespace() && !quoted && depth <= 0 {
+            if let Some(start) = start.take() {
+                tokens.push(&line[start..index]);
+            }
+        } else if start.is_none() {
+            start = Some(index);
+        }
+    }
+    if let Some(start) = start {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+fn parse_atom(tokens: &[&str]) -> Result<(String, BlockAtom), String> {
+    let id = tokens.first().copied().unwrap_or_default().to_string();
+    let (element, mut isotope) = parse_element(tokens.get(1).copied().unwrap_or_default())?;
+    let mut coordinates = [0.0; 3];
+    for (axis, value) in coordinates.iter_mut().enumerate() {
+        *value = tokens
+            .get(axis + 2)
+            .and_then(|value| value.parse().ok())
+            .ok_or(format!("Invalid coordinates of atom {}", id))?;
+    }
+    let react_id = tokens
+        .get(5)
+        .and_then(|react_id| react_id.parse::<usize>().ok())
+        .filter(|react_id| *react_id != 0);
+    let mut charge = 0;
+    let mut parity = 0;
+    let mut valence = None;
+    let mut radical_electrons = 0;
+    for (key, value) in tokens
+        .iter()
+        .skip(6)
+        .filter_map(|token| token.split_once('='))
+    {
+        if !matches!(key, "CHG" | "CFG" | "MASS" | "VAL" | "RAD") {
+            continue;
+        }
+        let number = value
+            .parse::<isize>()
+            .map_err(|_| format!("Invalid value of {} in atom {}", key, id))?;
+        match key {
+            "CHG" => charge = number,
+            "CFG" => parity = number as u8,
+            "MASS" => isotope = Some(number as u16),
+            "VAL" => valence = Some(number.max(0) as usize),
+            _ => radical_electrons = super::radical_electrons(number as usize),
+        }
+    }
+    Ok((
+        id,
+        BlockAtom {
+            atom: Atom {
+                element,
+                isotope,
+                charge,
+                chirality_type: None,
+                explicit_hydrogen: 0,
+                selector: None,
+                aromatic: false,
+                react_id,
+                stereo_group: None,
+                label: None,
+                coordinates: Some(coordinates),
+                radical_electrons,
+            },
+            parity,
+            valence,
+        },
+    ))
+}
+
+fn parse_sgroup(
+    tokens: &[&str],
+    atom_of: &HashMap<String, usize>,
+    bond_of: &HashMap<String, usize>,
+    block: &Block,
+) -> Result<BlockSGroup, String> {
+    let mut sgroup = BlockSGroup {
+        kind: tokens.get(1).copied().unwrap_or_default().to_string(),
+        atoms: vec![],
+        crossing_bonds: vec![],
+        fields: vec![],
+    };
+    for (key, value) in tokens
+        .iter()
+        .skip(3)
+        .filter_map(|token| token.split_once('='))
+    {
+        match key {
+            "ATOMS" => {
+                for atom in parse_list(value)? {
+                    sgroup.atoms.push(find_id(atom_of, Some(&atom))?);
+                }
+            }
+            "XBONDS" => {
+                for bond in parse_list(value)? {
+                    let bond = &block.bonds[find_id(bond_of, Some(&bond))?];
+                    sgroup.crossing_bonds.push((bond.from, bond.to));
+                }
+            }
+            // they refer atoms, bonds or sgroups by ids which aren't kept
+            "CBONDS" | "PATOMS" | "SAP" | "PARENT" => {
+                return Err(format!("Unsupported sgroup field {}", key))
+            }
+            _ => sgroup.fields.push((key.to_string(), unquote(value))),
+        }
+    }
+    Ok(sgroup)
+}
+
+/// Get the `CFG` of a bond, 0 if it's not given
+fn bond_config(tokens: &[&str]) -> Result<u8, String> {
+    match tokens
+        .iter()
+        .skip(4)
+        .filter_map(|token| token.split_once('='))
+        .find(|(key, _)| *key == "CFG")
+    {
+        Some((_, value)) => value
+            .parse()
+            .map_err(|_| format!("Invalid value of CFG in bond {}", tokens[0])),
+        None => Ok(0),
+    }
+}
+
+/// Read a list like `(3 1 2 3)`, the first number is its length
+fn parse_list(list: &str) -> Result<Vec<&str>, String> {
+    let items = list
+        .strip_prefix('(')
+        .and_then(|list| list.strip_suffix(')'))
+        .ok_or(format!("Invalid list {}", list))?
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    Ok(items.into_iter().skip(1).collect())
+}
+
+fn write_list(items: impl ExactSizeIterator<Item = usize>) -> String {
+    let mut list = format!("({}", items.len());
+    for item in items {
+        list.push_str(&format!(" {}", item));
+    }
+    list.push(')');
+    list
+}
+
+fn find_id<T: AsRef<str>>(ids: &HashMap<String, usize>, id: Option<T>) -> Result<usize, String> {
+    let id = id.as_ref().map(|id| id.as_ref()).unwrap_or_default();
+    ids.get(id).copied().ok_or(format!("Id {} not found", id))
+}
+
+fn field<'a>(fields: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Quote a value if it's empty, has whitespaces or quotes, or ends with `-`
+/// which continues a line. A list in parentheses is written as it is.
+fn quote(value: &str) -> String {
+    if value.starts_with('(')
+        || !(value.is_empty()
+            || value.ends_with('-')
+            || value.contains(|char: char| char.is_whitespace() || char == '"'))
+    {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    }
+}
+
+fn unquote(value: &str) -> String {
+    match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(value) => value.replace("\"\"", "\""),
+        None => value.to_string(),
+    }
+}
+
+/// Push a `M  V30` line, long lines are continued with `-`.
+fn push_line(lines: &mut Vec<String>, content: &str) {
+    let mut rest = content;
+    while rest.len() > 73 {
+        let split = rest
+            .char_indices()
+            .map(|(index, _)| index)
+            .take_while(|index| *index <= 72)
+            .last()
+            .unwrap_or(rest.len());
+        lines.push(format!("M  V30 {}-", &rest[..split]));
+        rest = &rest[split..];
+    }
+    lines.push(format!("M  V30 {}", rest));
+}
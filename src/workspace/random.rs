@@ -0,0 +1,107 @@
+use std::collections::{HashMap, HashSet};
+
+use petgraph::stable_graph::NodeIndex;
+use rand::Rng;
+
+use super::{encode::WriteOptions, Workspace};
+
+/// tries without a new SwS before `RandomSws` gives up
+const MAX_TRIES: usize = 100;
+
+/// Here implements randomized SwS, for data augmentation
+impl Workspace {
+    /// write the structure from a random atom of each component, branches are
+    /// in random order. Unlike `reset_root`, the workspace is not changed.
+    pub fn to_random_sws<R: Rng + ?Sized>(&self, node: NodeIndex, rng: &mut R) -> Option<String> {
+        self.to_random_sws_with(node, &WriteOptions::default(), rng)
+    }
+
+    /// write the structure as `to_random_sws` with given options, branches
+    /// are still sorted if `longest_chain_last` is set.
+    pub fn to_random_sws_with<R: Rng + ?Sized>(
+        &self,
+        node: NodeIndex,
+        options: &WriteOptions,
+        rng: &mut R,
+    ) -> Option<String> {
+        Some(self.write_random_sws(node, options, rng)?.0)
+    }
+
+    /// write the structure as `to_random_sws_with`, also returns atoms in
+    /// the order they're written.
+    pub fn write_random_sws<R: Rng + ?Sized>(
+        &self,
+        node: NodeIndex,
+        options: &WriteOptions,
+        rng: &mut R,
+    ) -> Option<(String, Vec<NodeIndex>)> {
+        let mut starts = vec![];
+        let mut ranks = HashMap::new();
+        for root in self.components(node)? {
+            let atoms = self.get_atoms_of_structure(root)?;
+            starts.push(atoms[rng.gen_range(0..atoms.len())]);
+            for atom in atoms {
+                ranks.insert(atom, rng.gen());
+            }
+        }
+        self.write_components(&starts, options, Some(&ranks))
+    }
+
+    /// Iterate over distinct random SwS of the structure, at most `count` of
+    /// them. It stops early if no new one is found after some tries, like
+    /// small structures which can't be written in so many ways.
+    pub fn random_sws<'a, R: Rng + ?Sized>(
+        &'a self,
+        node: NodeIndex,
+        count: usize,
+        rng: &'a mut R,
+    ) -> RandomSws<'a, R> {
+        RandomSws {
+            workspace: self,
+            node,
+            options: WriteOptions::default(),
+            rng,
+            remaining: count,
+            written: HashSet::new(),
+        }
+    }
+}
+
+/// Iterator given by `Workspace::random_sws`
+pub struct RandomSws<'a, R: Rng + ?Sized> {
+    workspace: &'a Workspace,
+    node: NodeIndex,
+    options: WriteOptions,
+    rng: &'a mut R,
+    remaining: usize,
+    written: HashSet<String>,
+}
+
+impl<'a, R: Rng + ?Sized> RandomSws<'a, R> {
+    /// write with the options instead of the default ones
+    pub fn with_options(mut self, options: WriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl<'a, R: Rng + ?Sized> Iterator for RandomSws<'a, R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        for _ in 0..MAX_TRIES {
+            let sws = self
+                .workspace
+                .to_random_sws_with(self.node, &self.options, self.rng)?;
+            if self.written.insert(sws.clone()) {
+                self.remaining -= 1;
+                return Some(sws);
+            }
+        }
+        self.remaining = 0;
+        None
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use petgraph::{
+    stable_graph::{EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+};
+
+use super::Workspace;
+
+/// Here implements ring perception
+impl Workspace {
+    /// Find the smallest set of smallest rings of the structure which the
+    /// given atom belongs to. Atoms of each ring are in the order they're
+    /// bonded, rings are sorted by their sizes.
+    pub fn rings(&self, structure_node: NodeIndex) -> Option<Vec<Vec<NodeIndex>>> {
+        let mut atoms = vec![];
+        for root in self.components(structure_node)? {
+            atoms.append(&mut self.get_atoms_of_structure(root)?);
+        }
+        let components = self.components(structure_node)?.len();
+        let mut edges = vec![];
+        let mut ring_edges = vec![];
+        for atom in atoms.iter() {
+            for edge in self.graph.edges(*atom) {
+                edges.push(edge.id());
+                if edge.weight().is_ring_bond() {
+                    ring_edges.push(edge.id());
+                }
+            }
+        }
+        // amount of independent rings
+        let ring_count = (edges.len() + components).saturating_sub(atoms.len());
+        if ring_count == 0 {
+            return Some(vec![]);
+        }
+        let column_of = edges
+            .iter()
+            .enumerate()
+            .map(|(index, edge)| (*edge, index))
+            .collect::<HashMap<_, _>>();
+        // rings through bonds marked as ring bonds are found first, then
+        // rings through all bonds of them, which covers fused ring systems
+        let mut searched = HashSet::new();
+        let mut candidates = vec![];
+        let mut queue = ring_edges;
+        while let Some(edge) = queue.pop() {
+            if !searched.insert(edge) {
+                continue;
+            }
+            if let Some(ring) = self.shortest_ring_through(edge) {
+                for (index, atom) in ring.iter().enumerate() {
+                    let next = ring[(index + 1) % ring.len()];
+                    if let Some(edge) = self.graph.find_edge_undirected(*atom, next) {
+                        queue.push(edge.0);
+                    }
+                }
+                candidates.push(ring);
+            }
+        }
+        candidates.sort_by_key(|ring| ring.len());
+        let words = edges.len().div_ceil(64);
+        let mut basis: Vec<Vec<u64>> = vec![];
+        let mut rings = vec![];
+        for ring in candidates {
+            let mut vector = vec![0u64; words];
+            for (index, atom) in ring.iter().enumerate() {
+                let next = ring[(index + 1) % ring.len()];
+                let (edge, _) = self.graph.find_edge_undirected(*atom, next)?;
+                let column = column_of[&edge];
+                vector[column / 64] |= 1 << (column % 64);
+            }
+            if reduce(&basis, &mut vector) {
+                basis.push(vector);
+                rings.push(ring);
+                if rings.len() == ring_count {
+                    break;
+                }
+            }
+        }
+        Some(rings)
+    }
+
+    /// Find the shortest ring containing the bond by a breadth-first search
+    /// between its atoms without the bond.
+    fn shortest_ring_through(&self, edge: EdgeIndex) -> Option<Vec<NodeIndex>> {
+        let (from, to) = self.graph.edge_endpoints(edge)?;
+        let mut previous = HashMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(current) = queue.pop_front() {
+            for next in self.graph.neighbors_undirected(current) {
+                if previous.contains_key(&next) || current == from && next == to {
+                    continue;
+                }
+                previous.insert(next, current);
+                if next == to {
+                    let mut ring = vec![to];
+                    let mut atom = to;
+                    while atom != from {
+                        atom = previous[&atom];
+                        ring.push(atom);
+                    }
+                    return Some(ring);
+                }
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+}
+
+/// Reduce a ring vector of bonds by the basis, returns true if it's
+/// independent of the basis.
+fn reduce(basis: &[Vec<u64>], vector: &mut [u64]) -> bool {
+    for row in basis {
+        let pivot = row
+            .iter()
+            .enumerate()
+            .find(|(_, word)| **word != 0)
+            .map(|(index, word)| index * 64 + word.trailing_zeros() as usize);
+        if let Some(pivot) = pivot {
+            if vector[pivot / 64] & (1 << (pivot % 64)) != 0 {
+                for (word, other) in vector.iter_mut().zip(row.iter()) {
+                    *word ^= other;
+                }
+            }
+        }
+    }
+    vector.iter().any(|word| *word != 0)
+}
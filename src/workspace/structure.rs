@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, HashMap};
+
+use petgraph::stable_graph::NodeIndex;
+
+use super::Workspace;
+
+/// a handle of a structure issued by `Workspace::add_structure`,
+/// it stays the same while roots of the structure change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StructureId(usize);
+
+/// Information registered for a structure in the workspace
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StructureInfo {
+    pub name: Option<String>,
+    pub properties: BTreeMap<String, String>,
+    pub sgroups: Vec<SGroup>,
+    /// one atom of every component, components are separated by `.` in SMILES
+    anchors: Vec<NodeIndex>,
+}
+
+/// A group of atoms annotated in molfiles, like a repeating unit of a
+/// polymer or an abbreviation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SGroup {
+    /// type of the group, like `SRU`, `SUP` and `DAT`
+    pub kind: String,
+    pub atoms: Vec<NodeIndex>,
+    /// bonds crossing the border of the group, given by their atoms
+    pub crossing_bonds: Vec<(NodeIndex, NodeIndex)>,
+    /// other fields as they're written, like `LABEL` and `CONNECT`
+    pub fields: Vec<(String, String)>,
+}
+
+impl SGroup {
+    /// Map atoms of the group to copied atoms, returns None if some atoms
+    /// are not copied.
+    pub(super) fn remap(&self, mapping: &HashMap<NodeIndex, NodeIndex>) -> Option<Self> {
+        Some(Self {
+            kind: self.kind.clone(),
+            atoms: self
+                .atoms
+                .iter()
+                .map(|atom| mapping.get(atom).copied())
+                .collect::<Option<_>>()?,
+            crossing_bonds: self
+                .crossing_bonds
+                .iter()
+                .map(|(a, b)| Some((*mapping.get(a)?, *mapping.get(b)?)))
+                .collect::<Option<_>>()?,
+            fields: self.fields.clone(),
+        })
+    }
+}
+
+/// Here implements the registry of structures
+impl Workspace {
+    /// Iterate over all registered structures
+    pub fn structures(&self) -> impl Iterator<Item = StructureId> + '_ {
+        self.structures.keys().copied()
+    }
+
+    /// Find the registered structure which contains the given atom
+    pub fn structure_of(&self, node: NodeIndex) -> Option<StructureId> {
+        let root = self.find_root_of(node)?;
+        self.root_structures.get(&root).copied()
+    }
+
+    /// Get current roots of all components of the structure
+    pub fn structure_roots(&self, id: StructureId) -> Option<Vec<NodeIndex>> {
+        Some(
+            self.structures
+                .get(&id)?
+                .anchors
+                .iter()
+                .filter_map(|anchor| self.find_anchor_root(*anchor))
+                .collect(),
+        )
+    }
+
+    /// Get current root of the first component of the structure
+    pub fn structure_root(&self, id: StructureId) -> Option<NodeIndex> {
+        self.structure_roots(id)?.first().copied()
+    }
+
+    /// Get all atoms of all components of the structure
+    pub fn structure_atoms(&self, id: StructureId) -> Option<Vec<NodeIndex>> {
+        let mut atoms = vec![];
+        for root in self.structure_roots(id)? {
+            atoms.append(&mut self.get_atoms_of_structure(root)?);
+        }
+        Some(atoms)
+    }
+
+    pub fn structure_info(&self, id: StructureId) -> Option<&StructureInfo> {
+        self.structures.get(&id)
+    }
+
+    pub fn structure_info_mut(&mut self, id: StructureId) -> Option<&mut StructureInfo> {
+        self.structures.get_mut(&id)
+    }
+
+    pub fn structure_name(&self, id: StructureId) -> Option<&str> {
+        self.structures.get(&id)?.name.as_deref()
+    }
+
+    pub fn set_structure_name(&mut self, id: StructureId, name: &str) -> Option<()> {
+        self.structures.get_mut(&id)?.name = Some(name.to_string());
+        Some(())
+    }
+
+    pub fn structure_property(&self, id: StructureId, key: &str) -> Option<&str> {
+        self.structures
+            .get(&id)?
+            .properties
+            .get(key)
+            .map(String::as_str)
+    }
+
+    /// Set a property of the structure, returns the replaced value if existed.
+    pub fn set_structure_property(
+        &mut self,
+        id: StructureId,
+        key: &str,
+        value: &str,
+    ) -> Option<Option<String>> {
+        Some(
+            self.structures
+                .get_mut(&id)?
+                .properties
+                .insert(key.to_string(), value.to_string()),
+        )
+    }
+
+    /// Remove the structure and all its atoms from workspace
+    pub fn remove_structure(&mut self, id: StructureId) -> Option<StructureInfo> {
+        for atom in self.structure_atoms(id)? {
+            self.graph.remove_node(atom);
+        }
+        self.root_structures.retain(|_, structure| *structure != id);
+        self.structures.remove(&id)
+    }
+}
+
+/// Registry maintenance used by other parts of workspace
+impl Workspace {
+    /// Register components given by one atom of each as a new structure.
+    /// Atoms of a component already given, like those joined by ring bonds
+    /// across `.`, are skipped.
+    pub(crate) fn register_structure(&mut self, atoms: Vec<NodeIndex>) -> StructureId {
+        let id = StructureId(self.next_structure_id);
+        self.next_structure_id += 1;
+        let mut anchors = vec![];
+        for atom in atoms {
+            if let Some(root) = self.find_root_of(atom) {
+                if self.root_structures.insert(root, id) != Some(id) {
+                    anchors.push(atom);
+                }
+            }
+        }
+        self.structures.insert(
+            id,
+            StructureInfo {
+                anchors,
+                ..Default::default()
+            },
+        );
+        id
+    }
+
+    /// Find root of a component by an atom remembered in the registry,
+    /// the atom might have been removed.
+    pub(super) fn find_anchor_root(&self, anchor: NodeIndex) -> Option<NodeIndex> {
+        if self.graph.contains_node(anchor) {
+            self.find_root_of(anchor)
+        } else {
+            None
+        }
+    }
+
+    /// Forget the component of given atom in the registry, it's going to be
+    /// a part of another structure. Structures without any component left
+    /// are dropped.
+    pub(super) fn leave_structure(&mut self, node: NodeIndex) {
+        let root = self.find_root_of(node);
+        if let Some(id) = root.and_then(|root| self.root_structures.remove(&root)) {
+            if let Some(mut info) = self.structures.remove(&id) {
+                info.anchors
+                    .retain(|anchor| self.find_anchor_root(*anchor) != root);
+                if !info.anchors.is_empty() {
+                    self.structures.insert(id, info);
+                }
+            }
+        }
+    }
+
+    /// Remember the component of `node` in the structure if it's
+    /// not remembered yet, used when a structure falls into components.
+    pub(super) fn add_anchor(&mut self, id: StructureId, node: NodeIndex) {
+        if let Some(root) = self.find_root_of(node) {
+            if self.root_structures.get(&root) != Some(&id) {
+                if let Some(info) = self.structures.get_mut(&id) {
+                    info.anchors.push(node);
+                    self.root_structures.insert(root, id);
+                }
+            }
+        }
+    }
+
+    /// Let another atom of the same component be remembered in the registry,
+    /// used before the remembered one is removed. If the old one is a root,
+    /// the new one is going to be the root without it.
+    pub(super) fn replace_anchor(&mut self, old: NodeIndex, new: NodeIndex) {
+        if let Some(id) = self.root_structures.remove(&old) {
+            self.root_structures.insert(new, id);
+        }
+        for info in self.structures.values_mut() {
+            for anchor in info.anchors.iter_mut() {
+                if *anchor == old {
+                    *anchor = new;
+                }
+            }
+        }
+    }
+
+    /// Forget removed atoms in the registry.
+    pub(super) fn prune_structures(&mut self) {
+        let graph = &self.graph;
+        for info in self.structures.values_mut() {
+            info.anchors.retain(|anchor| graph.contains_node(*anchor));
+        }
+        self.structures.retain(|_, info| !info.anchors.is_empty());
+        let structures = &self.structures;
+        self.root_structures
+            .retain(|root, id| graph.contains_node(*root) && structures.contains_key(id));
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+use petgraph::{
+    stable_graph::NodeIndex,
+    visit::EdgeRef,
+    Direction::{Incoming, Outgoing},
+};
+
+use crate::definitions::{bond::BondType, element::Element};
+
+use super::Workspace;
+
+/// Topological distances between heavy atoms of a structure, in bonds
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DistanceMatrix {
+    atoms: Vec<NodeIndex>,
+    /// None between atoms in different components
+    distances: Vec<Vec<Option<usize>>>,
+}
+
+impl DistanceMatrix {
+    /// atoms of rows and columns
+    pub fn atoms(&self) -> &[NodeIndex] {
+        &self.atoms
+    }
+
+    pub fn rows(&self) -> &[Vec<Option<usize>>] {
+        &self.distances
+    }
+
+    pub fn distance(&self, a: NodeIndex, b: NodeIndex) -> Option<usize> {
+        let a = self.atoms.iter().position(|atom| *atom == a)?;
+        let b = self.atoms.iter().position(|atom| *atom == b)?;
+        self.distances[a][b]
+    }
+}
+
+/// Graph-theoretic indices of the hydrogen-suppressed graph of a structure
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TopologicalIndices {
+    /// sum of distances of all pairs of atoms
+    pub wiener: usize,
+    pub balaban_j: f64,
+    /// Randić connectivity indices χ0 to χ3 by paths of 0 to 3 bonds
+    pub chi: [f64; 4],
+    /// Kier-Hall κ1 to κ3 shape indices, modified by α of atoms
+    pub kappa: [f64; 3],
+    /// first Zagreb index, sum of squared degrees of atoms
+    pub zagreb_m1: usize,
+    /// second Zagreb index, sum of products of degrees of bonded atoms
+    pub zagreb_m2: usize,
+}
+
+/// Here implements distances and topological indices
+impl Workspace {
+    /// Find a shortest path of bonds between two atoms, both of them are
+    /// included. Bond distance of them is the length of the path minus 1.
+    pub fn shortest_path(&self, a: NodeIndex, b: NodeIndex) -> Option<Vec<NodeIndex>> {
+        self.get_atom(a)?;
+        self.get_atom(b)?;
+        let mut previous = HashMap::from([(a, a)]);
+        let mut queue = VecDeque::from([a]);
+        while let Some(current) = queue.pop_front() {
+            if current == b {
+                let mut path = vec![b];
+                while path[path.len() - 1] != a {
+                    path.push(previous[&path[path.len() - 1]]);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbor in self.bonded_atoms(current) {
+                if let Entry::Vacant(entry) = previous.entry(neighbor) {
+                    entry.insert(current);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        None
+    }
+
+    /// Distances between heavy atoms of the structure which the given atom
+    /// belongs to.
+    pub fn distance_matrix(&self, structure_node: NodeIndex) -> Option<DistanceMatrix> {
+        let (atoms, neighbors) = self.heavy_graph(structure_node)?;
+        let position = atoms
+            .iter()
+            .enumerate()
+            .map(|(index, atom)| (*atom, index))
+            .collect::<HashMap<_, _>>();
+        let distances = atoms
+            .iter()
+            .map(|start| {
+                let mut row = vec![None; atoms.len()];
+                row[position[start]] = Some(0);
+                let mut queue = VecDeque::from([*start]);
+                while let Some(current) = queue.pop_front() {
+                    let distance = row[position[&current]].unwrap_or_default();
+                    for neighbor in neighbors[&current].iter() {
+                        if row[position[neighbor]].is_none() {
+                            row[position[neighbor]] = Some(distance + 1);
+                            queue.push_back(*neighbor);
+                        }
+                    }
+                }
+                row
+            })
+            .collect();
+        Some(DistanceMatrix { atoms, distances })
+    }
+
+    /// Compute topological indices of the structure which the given atom
+    /// belongs to, hydrogens are not counted.
+    pub fn topological_indices(&self, structure_node: NodeIndex) -> Option<TopologicalIndices> {
+        let (atoms, neighbors) = self.heavy_graph(structure_node)?;
+        let matrix = self.distance_matrix(structure_node)?;
+        let degree = |atom: &NodeIndex| neighbors[atom].len();
+        let bonds = atoms
+            .iter()
+            .flat_map(|atom| {
+                neighbors[atom]
+                    .iter()
+                    .filter(move |neighbor| atom < *neighbor)
+                    .map(move |neighbor| (*atom, *neighbor))
+            })
+            .collect::<Vec<_>>();
+        // sums of distances from each atom
+        let sums = atoms
+            .iter()
+            .zip(matrix.distances.iter())
+            .map(|(atom, row)| (*atom, row.iter().flatten().sum::<usize>()))
+            .collect::<HashMap<_, _>>();
+        let wiener = sums.values().sum::<usize>() / 2;
+        let components = self.components(structure_node)?.len();
+        // cyclomatic number, amount of independent rings
+        let rings = (bonds.len() + components).saturating_sub(atoms.len());
+        let sum_of = |atom: &NodeIndex| sums[atom] as f64;
+        let balaban_j = if bonds.is_empty() {
+            0.
+        } else {
+            bonds.len() as f64 / (rings + 1) as f64
+                * bonds
+                    .iter()
+                    .map(|(a, b)| 1. / (sum_of(a) * sum_of(b)).sqrt())
+                    .sum::<f64>()
+        };
+        // paths of 0 to 3 bonds, each of them is found from both ends
+        let mut chi = [0.; 4];
+        let mut path_counts = [0; 4];
+        for start in atoms.iter() {
+            let mut stack = vec![vec![*start]];
+            while let Some(path) = stack.pop() {
+                let length = path.len() - 1;
+                let product = path.iter().map(|atom| degree(atom) as f64).product::<f64>();
+                if product > 0. {
+                    chi[length] += product.powf(-0.5);
+                }
+                path_counts[length] += 1;
+                if length == 3 {
+                    continue;
+                }
+                for neighbor in neighbors[&path[length]].iter() {
+                    if !path.contains(neighbor) {
+                        let mut path = path.clone();
+                        path.push(*neighbor);
+                        stack.push(path);
+                    }
+                }
+            }
+        }
+        for length in 1..4 {
+            chi[length] /= 2.;
+            path_counts[length] /= 2;
+        }
+        let alpha = atoms
+            .iter()
+            .map(|atom| self.hall_kier_radius(*atom) / 0.77 - 1.)
+            .sum::<f64>();
+        let a = atoms.len() as f64 + alpha;
+        let kappa_of = |numerator: f64, paths: usize| {
+            let paths = paths as f64 + alpha;
+            if paths > 0. {
+                numerator / (paths * paths)
+            } else {
+                0.
+            }
+        };
+        let kappa = [
+            kappa_of(a * (a - 1.) * (a - 1.), path_counts[1]),
+            kappa_of((a - 1.) * (a - 2.) * (a - 2.), path_counts[2]),
+            kappa_of(
+                if atoms.len() % 2 == 1 {
+                    (a - 1.) * (a - 3.) * (a - 3.)
+                } else {
+                    (a - 3.) * (a - 2.) * (a - 2.)
+                },
+                path_counts[3],
+            ),
+        ];
+        Some(TopologicalIndices {
+            wiener,
+            balaban_j,
+            chi,
+            kappa,
+            zagreb_m1: atoms.iter().map(|atom| degree(atom) * degree(atom)).sum(),
+            zagreb_m2: bonds.iter().map(|(a, b)| degree(a) * degree(b)).sum(),
+        })
+    }
+}
+
+/// Private functions used upon
+impl Workspace {
+    /// Atoms bonded to the atom, `.` bonds are not counted
+    fn bonded_atoms(&self, atom: NodeIndex) -> Vec<NodeIndex> {
+        self.graph
+            .edges_directed(atom, Incoming)
+            .chain(self.graph.edges_directed(atom, Outgoing))
+            .filter(|edge| !edge.weight().is_no_bond())
+            .map(|edge| {
+                if edge.source() == atom {
+                    edge.target()
+                } else {
+                    edge.source()
+                }
+            })
+            .collect()
+    }
+
+    /// Heavy atoms of a structure, sorted, and their heavy neighbors
+    #[allow(clippy::type_complexity)]
+    fn heavy_graph(
+        &self,
+        structure_node: NodeIndex,
+    ) -> Option<(Vec<NodeIndex>, HashMap<NodeIndex, Vec<NodeIndex>>)> {
+        let mut atoms = vec![];
+        for root in self.components(structure_node)? {
+            atoms.extend(
+                self.get_atoms_of_structure(root)?
+                    .into_iter()
+                    .filter(|atom| self.graph[*atom].element != Element::H),
+            );
+        }
+        atoms.sort();
+        let neighbors = atoms
+            .iter()
+            .map(|atom| {
+                let mut bonded = self
+                    .bonded_atoms(*atom)
+                    .into_iter()
+                    .filter(|neighbor| self.graph[*neighbor].element != Element::H)
+                    .collect::<Vec<_>>();
+                bonded.sort();
+                (*atom, bonded)
+            })
+            .collect();
+        Some((atoms, neighbors))
+    }
+
+    /// Covalent radius of the atom by its hybridization used by Hall and
+    /// Kier, single bond covalent radius for other elements.
+    fn hall_kier_radius(&self, atom: NodeIndex) -> f64 {
+        let properties = &self.graph[atom];
+        let bonds = self
+            .graph
+            .edges_directed(atom, Incoming)
+            .chain(self.graph.edges_directed(atom, Outgoing))
+            .map(|edge| edge.weight().bond_type())
+            .collect::<Vec<_>>();
+        let triple = bonds.contains(&BondType::Triple)
+            || bonds
+                .iter()
+                .filter(|bond| **bond == BondType::Double)
+                .count()
+                > 1;
+        let double = properties.aromatic || bonds.contains(&BondType::Double);
+        match (properties.element, triple, double) {
+            (Element::C, true, _) => 0.60,
+            (Element::C, _, true) => 0.67,
+            (Element::C, _, _) => 0.77,
+            (Element::N, true, _) => 0.55,
+            (Element::N, _, true) => 0.62,
+            (Element::N, _, _) => 0.74,
+            (Element::O, _, true) => 0.62,
+            (Element::O, _, _) => 0.74,
+            (Element::F, _, _) => 0.72,
+            (Element::P, _, true) => 1.00,
+            (Element::P, _, _) => 1.10,
+            (Element::S, _, true) => 0.94,
+            (Element::S, _, _) => 1.04,
+            (Element::Cl, _, _) => 0.99,
+            (Element::Br, _, _) => 1.14,
+            (Element::I, _, _) => 1.33,
+            (element, _, _) => element.covalent_radius(),
+        }
+    }
+}
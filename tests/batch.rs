@@ -0,0 +1,79 @@
+use std::io::{self, Write};
+
+use smiles_with_selectors::{
+    batch::{parse_records, read_records, write_records, RecordErrorKind},
+    definitions::bond::BondType,
+    molecule::Molecule,
+    workspace::Workspace,
+};
+
+const FILE: &str = "# comment
+CCO ethanol 46.07
+
+C1CC1 |$_AV:;;$| cyclopropane
+C1CC broken
+c1ccccc1[N{amine}] aniline x y
+";
+
+#[test]
+fn read_parse_and_write() {
+    let records = read_records(FILE.as_bytes()).unwrap();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0].line, 2);
+    assert_eq!(records[0].columns, vec!["46.07"]);
+    assert_eq!(records[1].smiles, "C1CC1 |$_AV:;;$|");
+    assert_eq!(records[1].name.as_deref(), Some("cyclopropane"));
+
+    let results = parse_records(&records);
+    let error = results[2].as_ref().unwrap_err();
+    assert_eq!(error.kind, RecordErrorKind::Parse);
+    assert_eq!(error.line, 5);
+    assert_eq!(error.smiles, "C1CC");
+
+    let mut written = vec![];
+    assert_eq!(write_records(&mut written, &records, &results), Ok(3));
+    assert_eq!(
+        String::from_utf8(written).unwrap(),
+        "CCO ethanol 46.07\nC1CC1 cyclopropane\nc1ccccc1[N{amine}] aniline x y\n"
+    );
+}
+
+#[test]
+fn unreadable_line() {
+    let error = read_records(&b"CC\n\xff\xfe\nCO\n"[..]).unwrap_err();
+    assert_eq!(error.kind, RecordErrorKind::Io);
+    assert_eq!(error.line, 2);
+}
+
+struct Broken;
+
+impl Write for Broken {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_failures() {
+    let records = read_records("CC\n".as_bytes()).unwrap();
+    let results = parse_records(&records);
+    let error = write_records(&mut Broken, &records, &results).unwrap_err();
+    assert_eq!(error.kind, RecordErrorKind::Io);
+
+    // 100 rings open at the same atom can't be written
+    let mut ws = Workspace::new();
+    let id = ws.add_structure(&"C".repeat(102)).unwrap();
+    let root = ws.structure_root(id).unwrap();
+    for atom in ws.get_atoms_of_structure(root).unwrap() {
+        if atom != root && ws.get_edge_undirected(root, atom).is_none() {
+            ws.connect(atom, root, BondType::Single).unwrap();
+        }
+    }
+    let results = vec![Ok(Molecule::from_workspace(&ws, root).unwrap())];
+    let error = write_records(&mut vec![], &records, &results).unwrap_err();
+    assert_eq!(error.kind, RecordErrorKind::Write);
+}
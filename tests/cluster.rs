@@ -0,0 +1,67 @@
+use smiles_with_selectors::{
+    cluster::{butina, entries, max_min, workspace_entries},
+    fingerprint::FingerprintOptions,
+    workspace::Workspace,
+};
+
+const PRODUCTS: [&str; 6] = [
+    "c1ccccc1[CH2{R1}]C(=O)O",
+    "c1ccccc1[CH2{R1}]CC(=O)O",
+    "c1ccccc1[CH2{R1}]CCC(=O)O",
+    "CCCC[NH{R2}]CCCC",
+    "CCCCC[NH{R2}]CCCC",
+    "[Cl-].[Na+]",
+];
+
+#[test]
+fn butina_clusters() {
+    let products = entries(PRODUCTS.iter().enumerate(), &FingerprintOptions::default()).unwrap();
+    assert_eq!(products[0].sites, vec!["R1".to_string()]);
+    assert!(products[5].sites.is_empty());
+    let clustering = butina(&products, 0.4);
+    assert_eq!(clustering.assignments.len(), PRODUCTS.len());
+    for (index, cluster) in clustering.clusters.iter().enumerate() {
+        assert_eq!(cluster.members[0], cluster.centroid);
+        for member in cluster.members.iter() {
+            assert_eq!(clustering.assignments[*member], index);
+        }
+    }
+    let cluster_of = |product: usize| clustering.assignments[product];
+    assert_eq!(cluster_of(0), cluster_of(1));
+    assert_eq!(cluster_of(3), cluster_of(4));
+    assert_ne!(cluster_of(0), cluster_of(3));
+    assert_ne!(cluster_of(5), cluster_of(0));
+    assert_eq!(butina(&products, 1.).clusters.len(), PRODUCTS.len());
+}
+
+#[test]
+fn max_min_picks() {
+    let mut ws = Workspace::new();
+    for smiles in PRODUCTS {
+        ws.add_structure(smiles).unwrap();
+    }
+    let products = workspace_entries(&ws, &FingerprintOptions::default()).unwrap();
+    let picked = max_min(&products, 3, &[]).unwrap();
+    assert_eq!(picked.len(), 3);
+    assert_eq!(picked[0], 0);
+    // the salt shares nothing, then the amines are far from the acids
+    assert_eq!(picked[1], 5);
+    assert!(picked[2] == 3 || picked[2] == 4);
+    assert_eq!(max_min(&products, 10, &[]).unwrap().len(), PRODUCTS.len());
+    assert_eq!(&max_min(&products, 4, &picked).unwrap()[..3], &picked[..]);
+    assert!(max_min(&products, 4, &[PRODUCTS.len()]).is_err());
+    assert!(entries([(0, "C1CC")], &FingerprintOptions::default()).is_err());
+
+    // an entry of another size is as far as it can be
+    let mut mixed = entries(
+        PRODUCTS[..3].iter().enumerate(),
+        &FingerprintOptions::default(),
+    )
+    .unwrap();
+    let options = FingerprintOptions {
+        size: 512,
+        ..Default::default()
+    };
+    mixed.extend(entries([(3, PRODUCTS[1])], &options).unwrap());
+    assert_eq!(max_min(&mixed, 2, &[]), Ok(vec![0, 3]));
+}
@@ -0,0 +1,4 @@
+/// Assert values of descriptors and indices, which are given to 4 digits
+pub fn assert_close(value: f64, expected: f64) {
+    assert!((value - expected).abs() < 1e-3, "{} != {}", value, expected);
+}
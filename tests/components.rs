@@ -0,0 +1,26 @@
+use smiles_with_selectors::workspace::Workspace;
+
+#[test]
+fn bonds_across_dots_are_rejected() {
+    let mut ws = Workspace::new();
+    for smiles in ["C=.C", "C.=C", "CC="] {
+        assert!(ws.add_structure(smiles).is_err(), "{}", smiles);
+    }
+    // nothing is left from failed structures
+    assert_eq!(ws.graph.node_count(), 0);
+    let id = ws.add_structure("C=C.C").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    assert_eq!(ws.to_sws(root).unwrap(), "C=C.C");
+}
+
+#[test]
+fn strip_salts() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("[Na+].[O-]C(=O)c1ccccc1.O").unwrap();
+    let sodium = ws.structure_root(id).unwrap();
+    let kept = ws.strip_salts(sodium).unwrap();
+    assert_eq!(ws.structure_root(id), Some(kept));
+    assert_eq!(ws.structure_roots(id), Some(vec![kept]));
+    assert_eq!(ws.graph.node_count(), 9);
+    assert_eq!(ws.to_sws(kept).unwrap(), "[O-]C(=O)c1ccccc1");
+}
@@ -0,0 +1,31 @@
+use smiles_with_selectors::workspace::Workspace;
+
+#[test]
+fn extract_keeps_stereo() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("N[C@@H](C)C(=O)O").unwrap();
+    let nitrogen = ws.structure_root(id).unwrap();
+    let center = ws.graph.neighbors(nitrogen).next().unwrap();
+    let methyl = ws
+        .graph
+        .neighbors(center)
+        .find(|atom| ws.graph.neighbors(*atom).count() == 0)
+        .unwrap();
+    // the copy grows from the methyl, so the center gets another parent
+    let mut atoms = ws.structure_atoms(id).unwrap();
+    atoms.retain(|atom| *atom != methyl);
+    atoms.insert(0, methyl);
+    let (copy, mapping) = ws.extract(&atoms).unwrap();
+    assert_eq!(ws.structure_root(copy), Some(mapping[&methyl]));
+    assert_eq!(ws.to_sws(mapping[&methyl]).unwrap(), "C[C@H](N)C(=O)O");
+    // written from the same atom, it's the source again
+    assert_eq!(
+        ws.to_sws_from(mapping[&nitrogen]).unwrap(),
+        "N[C@@H](C)C(=O)O"
+    );
+
+    // a center which lost a neighbor loses its chirality
+    let (part, _) = ws.extract(&[nitrogen, center, methyl]).unwrap();
+    let root = ws.structure_root(part).unwrap();
+    assert_eq!(ws.to_sws(root).unwrap(), "N[CH]C");
+}
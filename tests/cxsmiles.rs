@@ -0,0 +1,128 @@
+use petgraph::stable_graph::NodeIndex;
+use smiles_with_selectors::{definitions::chirality::StereoGroup, workspace::Workspace};
+
+fn read(ws: &mut Workspace, cxsmiles: &str) -> Vec<NodeIndex> {
+    ws.add_structure_atoms(cxsmiles).unwrap().1
+}
+
+#[test]
+fn labels_and_atom_values() {
+    let mut ws = Workspace::new();
+    let atoms = read(&mut ws, "CCO |$R1;;OH$|");
+    assert_eq!(ws.graph[atoms[0]].label.as_deref(), Some("R1"));
+    assert_eq!(ws.graph[atoms[1]].label, None);
+    assert_eq!(ws.graph[atoms[2]].label.as_deref(), Some("OH"));
+    assert_eq!(ws.to_cxsws(atoms[0]).unwrap(), "CCO |$R1;;OH$|");
+
+    // atom values are read as selectors
+    let atoms = read(&mut ws, "CN |$_AV:amine;site$|");
+    assert_eq!(ws.graph[atoms[0]].selector.as_deref(), Some("amine"));
+    assert_eq!(ws.graph[atoms[1]].selector.as_deref(), Some("site"));
+    assert_eq!(ws.to_cxsws(atoms[0]).unwrap(), "[C{amine}][N{site}]");
+    assert_eq!(
+        ws.to_cxsmiles(atoms[0], true).unwrap(),
+        "CN |$_AV:amine;site$|"
+    );
+    assert_eq!(ws.to_cxsmiles(atoms[0], false).unwrap(), "CN");
+}
+
+#[test]
+fn atom_values_keep_hydrogen_counts() {
+    let mut ws = Workspace::new();
+    let atoms = read(
+        &mut ws,
+        "[NH2{amine}]C[C@H](O)c1cc[n{site}]cc1[P{Replacer(-,2)}].[Na+{ion}].[13C{label}]O",
+    );
+    let cxsmiles = ws.to_cxsmiles(atoms[0], true).unwrap();
+    assert_eq!(
+        cxsmiles,
+        "[NH2]C[C@H](O)c1ccncc1P.[Na+].[13CH3]O |$_AV:amine;;;;;;;site;;;Replacer(-,2);ion;label;$|"
+    );
+    // read without the selectors, as other software does
+    let (smiles, _) = cxsmiles.split_once(' ').unwrap();
+    let read_back = read(&mut ws, smiles);
+    for (atom, again) in atoms.iter().zip(read_back) {
+        assert_eq!(
+            ws.implicit_hydrogen_count(*atom),
+            ws.implicit_hydrogen_count(again)
+        );
+        // a bracket atom without H has no hydrogens in SMILES
+        let again = &ws.graph[again];
+        if again.needs_bracket() && again.explicit_hydrogen == 0 {
+            assert_eq!(ws.implicit_hydrogen_count(*atom), Some(0));
+        }
+    }
+}
+
+#[test]
+fn coordinates() {
+    let mut ws = Workspace::new();
+    let atoms = read(&mut ws, "CCO |(0,0,0;1.5,,;2,1.2,0)|");
+    assert_eq!(ws.graph[atoms[0]].coordinates, Some([0., 0., 0.]));
+    assert_eq!(ws.graph[atoms[1]].coordinates, Some([1.5, 0., 0.]));
+    assert_eq!(ws.graph[atoms[2]].coordinates, Some([2., 1.2, 0.]));
+    assert_eq!(
+        ws.to_cxsws(atoms[0]).unwrap(),
+        "CCO |(0.0000,0.0000,0.0000;1.5000,0.0000,0.0000;2.0000,1.2000,0.0000)|"
+    );
+    let atoms = read(&mut ws, "CCO |(;;1,2,3)|");
+    assert_eq!(ws.graph[atoms[0]].coordinates, None);
+    assert_eq!(
+        ws.to_cxsws(atoms[0]).unwrap(),
+        "CCO |(,,;,,;1.0000,2.0000,3.0000)|"
+    );
+}
+
+#[test]
+fn radicals() {
+    let mut ws = Workspace::new();
+    for (code, electrons) in [(1, 1), (2, 2), (3, 2), (4, 2), (5, 3), (6, 3), (7, 3)] {
+        let atoms = read(&mut ws, &format!("[C]C |^{}:0|", code));
+        assert_eq!(ws.graph[atoms[0]].radical_electrons, electrons, "^{}", code);
+        assert_eq!(ws.graph[atoms[1]].radical_electrons, 0);
+    }
+    for (electrons, code) in [(1, 1), (2, 2), (3, 5)] {
+        let atoms = read(&mut ws, "[CH][CH]");
+        ws.graph[atoms[0]].radical_electrons = electrons;
+        ws.graph[atoms[1]].radical_electrons = electrons;
+        assert_eq!(
+            ws.to_cxsws(atoms[0]).unwrap(),
+            format!("[CH][CH] |^{}:0,1|", code)
+        );
+    }
+}
+
+#[test]
+fn stereo_groups() {
+    let mut ws = Workspace::new();
+    let atoms = read(&mut ws, "C[C@H](O)C(C)[C@@H](F)Cl |a:1,o1:5|");
+    assert_eq!(ws.graph[atoms[1]].stereo_group, Some(StereoGroup::Absolute));
+    assert_eq!(ws.graph[atoms[5]].stereo_group, Some(StereoGroup::Or(1)));
+    assert_eq!(
+        ws.to_cxsws(atoms[0]).unwrap(),
+        "C[C@H](O)C(C)[C@@H](F)Cl |a:1,o1:5|"
+    );
+    let atoms = read(&mut ws, "C[C@H](O)[C@H](C)F |&1:1,3|");
+    assert_eq!(ws.graph[atoms[1]].stereo_group, Some(StereoGroup::And(1)));
+    assert_eq!(ws.graph[atoms[3]].stereo_group, Some(StereoGroup::And(1)));
+    assert_eq!(
+        ws.to_cxsws(atoms[0]).unwrap(),
+        "C[C@H](O)[C@H](C)F |&1:1,3|"
+    );
+}
+
+#[test]
+fn fields_together_and_unknown_ones() {
+    let mut ws = Workspace::new();
+    let atoms = read(
+        &mut ws,
+        "C[CH](O)[C@H](C)F |$;;OH$,^1:1,&1:3,c:1,SgD:0:note:text|",
+    );
+    assert_eq!(ws.graph[atoms[1]].radical_electrons, 1);
+    assert_eq!(ws.graph[atoms[2]].label.as_deref(), Some("OH"));
+    assert_eq!(ws.graph[atoms[3]].stereo_group, Some(StereoGroup::And(1)));
+    assert_eq!(
+        ws.to_cxsws(atoms[0]).unwrap(),
+        "C[CH](O)[C@H](C)F |$;;OH;;;$,^1:1,&1:3|"
+    );
+}
@@ -0,0 +1,120 @@
+use petgraph::stable_graph::NodeIndex;
+use smiles_with_selectors::{
+    depict::{to_svg, DepictOptions},
+    export::to_xyz,
+    workspace::Workspace,
+};
+
+fn distance(ws: &Workspace, a: NodeIndex, b: NodeIndex) -> f64 {
+    let a = ws.graph[a].coordinates.unwrap();
+    let b = ws.graph[b].coordinates.unwrap();
+    (0..3)
+        .map(|axis| (a[axis] - b[axis]).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// bonded pairs and other pairs of atoms of the structure
+#[allow(clippy::type_complexity)]
+fn pairs(
+    ws: &Workspace,
+    root: NodeIndex,
+) -> (Vec<(NodeIndex, NodeIndex)>, Vec<(NodeIndex, NodeIndex)>) {
+    let atoms = ws.get_atoms_of_structure(root).unwrap();
+    let mut bonded = vec![];
+    let mut others = vec![];
+    for (index, a) in atoms.iter().enumerate() {
+        for b in atoms[index + 1..].iter() {
+            if ws.get_edge_undirected(*a, *b).is_some() {
+                bonded.push((*a, *b));
+            } else {
+                others.push((*a, *b));
+            }
+        }
+    }
+    (bonded, others)
+}
+
+#[test]
+fn layout_2d() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("c1ccc2ccccc2c1CC(=O)[N{amine}]").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    ws.compute_2d_coords(root).unwrap();
+    let (bonded, others) = pairs(&ws, root);
+    for (a, b) in bonded {
+        assert!((distance(&ws, a, b) - 1.5).abs() < 0.05);
+    }
+    for (a, b) in others {
+        assert!(distance(&ws, a, b) > 1.);
+    }
+    for atom in ws.get_atoms_of_structure(root).unwrap() {
+        assert_eq!(ws.graph[atom].coordinates.unwrap()[2], 0.);
+    }
+}
+
+#[test]
+fn svg() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("c1ccccc1[N{amine}]").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let svg = to_svg(&ws, root, &DepictOptions::default()).unwrap();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    // six bonds of the ring with a circle inside, and the bond to nitrogen
+    assert_eq!(svg.matches("<line ").count(), 7);
+    assert_eq!(svg.matches("fill=\"none\"").count(), 1);
+    assert!(svg.contains(">{amine}</text>"));
+    // the workspace is not changed
+    assert_eq!(ws.graph[root].coordinates, None);
+
+    let options = DepictOptions {
+        aromatic_circles: false,
+        show_selectors: false,
+        ..Default::default()
+    };
+    let svg = to_svg(&ws, root, &options).unwrap();
+    // three of the ring bonds are double
+    assert_eq!(svg.matches("<line ").count(), 10);
+    assert!(!svg.contains("{amine}"));
+}
+
+#[test]
+fn embedding_3d() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("OCC(=O)N").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    ws.add_hydrogen_to_structure(root).unwrap();
+    ws.embed_3d(root).unwrap();
+    let (bonded, others) = pairs(&ws, root);
+    for (a, b) in bonded {
+        let expected =
+            ws.graph[a].element.covalent_radius() + ws.graph[b].element.covalent_radius();
+        let length = distance(&ws, a, b);
+        assert!(
+            (length - expected).abs() < 0.25,
+            "{} for {}",
+            length,
+            expected
+        );
+    }
+    for (a, b) in others {
+        assert!(distance(&ws, a, b) > 0.9);
+    }
+    let xyz = to_xyz(&ws, root).unwrap();
+    assert!(xyz.starts_with("10\n"));
+    assert_eq!(xyz.lines().count(), 12);
+}
+
+#[test]
+fn embedding_keeps_distant_atoms_apart() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure(&"CC(c1ccccc1)".repeat(6)).unwrap();
+    let root = ws.structure_root(id).unwrap();
+    ws.add_hydrogen_to_structure(root).unwrap();
+    ws.embed_3d(root).unwrap();
+    let (_, others) = pairs(&ws, root);
+    for (a, b) in others {
+        assert!(distance(&ws, a, b) > 0.9);
+    }
+}
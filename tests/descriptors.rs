@@ -0,0 +1,64 @@
+use smiles_with_selectors::workspace::{descriptors::Descriptors, Workspace};
+
+mod common;
+
+use common::assert_close;
+
+fn compute(smiles: &str) -> Descriptors {
+    let mut ws = Workspace::new();
+    let atoms = ws.add_structure_atoms(smiles).unwrap().1;
+    ws.descriptors(atoms[0]).unwrap()
+}
+
+#[test]
+fn reference_values() {
+    // smiles, clogp, molar refractivity, tpsa, donors, acceptors, rotatable
+    let references = [
+        ("CCO", -0.0014, 12.7598, 20.23, 1, 1, 0),
+        ("OCC[H]", -0.0014, 12.7598, 20.23, 1, 1, 0),
+        ("CC(=O)Oc1ccccc1C(=O)O", 1.3101, 44.7103, 63.6, 1, 4, 3),
+        ("CC(C)Cc1ccc(cc1)C(C)C(=O)O", 3.0732, 61.0348, 37.3, 1, 2, 4),
+        (
+            "Cn1cnc2c1c(=O)n(C)c(=O)n2C",
+            -1.0293,
+            51.196,
+            61.82,
+            0,
+            6,
+            0,
+        ),
+        ("Nc1ccccc1", 1.2688, 29.8544, 26.02, 1, 1, 0),
+        ("CC(=O)NC", -0.2477, 19.7407, 29.1, 1, 1, 0),
+    ];
+    for (smiles, clogp, mr, tpsa, donors, acceptors, rotatable) in references {
+        let result = compute(smiles);
+        assert_close(result.clogp, clogp);
+        assert_close(result.molar_refractivity, mr);
+        assert_close(result.tpsa, tpsa);
+        assert_eq!(result.h_bond_donors, donors, "{}", smiles);
+        assert_eq!(result.h_bond_acceptors, acceptors, "{}", smiles);
+        assert_eq!(result.rotatable_bonds, rotatable, "{}", smiles);
+    }
+}
+
+#[test]
+fn counts_and_rules() {
+    let aspirin = compute("CC(=O)Oc1ccccc1C(=O)O");
+    assert_eq!(aspirin.heavy_atoms, 13);
+    assert_close(aspirin.molecular_weight, 180.159);
+    assert_eq!((aspirin.rings, aspirin.aromatic_rings), (1, 1));
+    assert_close(aspirin.fraction_sp3, 1. / 9.);
+    assert_eq!(aspirin.lipinski_violations(), 0);
+    assert_eq!(aspirin.veber_violations(), 0);
+    let naphthalene = compute("c1ccc2ccccc2c1");
+    assert_eq!((naphthalene.rings, naphthalene.aromatic_rings), (2, 2));
+    let cyclohexane = compute("C1CCCCC1");
+    assert_eq!((cyclohexane.rings, cyclohexane.aromatic_rings), (1, 0));
+    assert_eq!(cyclohexane.rotatable_bonds, 0);
+    let catalyst = compute("Cl[Pd]Cl");
+    assert_eq!(catalyst.heavy_atoms, 3);
+    assert_close(catalyst.molecular_weight, 177.32);
+    let wax = compute(&"C".repeat(40));
+    assert_eq!(wax.lipinski_violations(), 2);
+    assert_eq!(wax.veber_violations(), 1);
+}
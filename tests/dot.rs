@@ -0,0 +1,59 @@
+use smiles_with_selectors::workspace::{dot::DotOptions, Workspace};
+
+#[test]
+fn workspace_to_dot() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("C[C@H](O)[N{amine}]").unwrap();
+    ws.add_structure("C1CC1").unwrap();
+    assert_eq!(
+        ws.to_dot(&DotOptions::default()),
+        r#"digraph {
+    node [shape=circle];
+    subgraph cluster_0 {
+        label="StructureId(0)";
+        0 [label="CH3\n#0"];
+        1 [label="C@H\n#1"];
+        3 [label="NH2\n{amine}\n#3"];
+        2 [label="OH\n#2"];
+    }
+    subgraph cluster_1 {
+        label="StructureId(1)";
+        4 [label="CH2\n#4"];
+        5 [label="CH2\n#5"];
+        6 [label="CH2\n#6"];
+    }
+    0 -> 1 [];
+    1 -> 3 [];
+    1 -> 2 [];
+    4 -> 6 [color=blue, fontcolor=blue, style=dashed, constraint=false];
+    4 -> 5 [];
+    5 -> 6 [];
+}
+"#
+    );
+
+    let options = DotOptions {
+        show_indexes: false,
+        show_hydrogens: false,
+        show_chirality: false,
+        show_selector: false,
+        color_bonds: false,
+        cluster_structures: false,
+        ..Default::default()
+    };
+    let root = ws.structure_root(id).unwrap();
+    assert_eq!(
+        ws.structure_to_dot(root, &options).unwrap(),
+        r#"digraph {
+    node [shape=circle];
+    0 [label="C"];
+    1 [label="C"];
+    3 [label="N"];
+    2 [label="O"];
+    0 -> 1 [];
+    1 -> 3 [];
+    1 -> 2 [];
+}
+"#
+    );
+}
@@ -0,0 +1,185 @@
+use smiles_with_selectors::{
+    definitions::{bond::BondType, element::Element},
+    workspace::{editor::Capping, Workspace},
+};
+
+#[test]
+fn substitute_leaf_hydrogen() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("OCC[H]").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let hydrogen = ws
+        .find_node_in_structure(root, |atom| atom.element == Element::H)
+        .unwrap();
+    ws.substitute(hydrogen, "N", 0, BondType::Single).unwrap();
+    // the root is kept, only the hydrogen is gone
+    assert_eq!(ws.structure_root(id), Some(root));
+    assert_eq!(ws.to_sws(root).unwrap(), "OCCN");
+
+    // a hydrogen at the root gives the root to its neighbor
+    let id = ws.add_structure("[H]CO").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let carbon = ws.graph.neighbors(root).next().unwrap();
+    ws.substitute(root, "Cl", 0, BondType::Single).unwrap();
+    assert_eq!(ws.structure_root(id), Some(carbon));
+    assert_eq!(ws.structure_of(carbon), Some(id));
+    assert_eq!(ws.to_sws(carbon).unwrap(), "C(Cl)O");
+}
+
+#[test]
+fn substitute_takes_hydrogen_counts() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("[CH3]C").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    // explicit hydrogens of the site and implicit ones of the fragment
+    let attach = ws.substitute(root, "C=O", 0, BondType::Single).unwrap();
+    assert_eq!(ws.graph[attach].element, Element::C);
+    assert_eq!(ws.to_sws(root).unwrap(), "[CH2](C)C=O");
+    assert_eq!(ws.structure_of(attach), Some(id));
+    assert_eq!(ws.structures().count(), 1);
+
+    // a double bond takes two hydrogens
+    let id = ws.add_structure("CC").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    ws.substitute(root, "O", 0, BondType::Double).unwrap();
+    assert_eq!(ws.to_sws(root).unwrap(), "C(C)=O");
+}
+
+#[test]
+fn substitute_checks_valence() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("CC(C)(C)C").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let center = ws.graph.neighbors(root).next().unwrap();
+    let count = ws.graph.node_count();
+    assert!(ws.substitute(center, "O", 0, BondType::Single).is_err());
+    // the fragment can't take a double bond
+    assert!(ws.substitute(root, "Cl", 0, BondType::Double).is_err());
+    // no atom at the position
+    assert!(ws.substitute(root, "O", 1, BondType::Single).is_err());
+    // fragments of two components
+    assert!(ws.substitute(root, "O.O", 0, BondType::Single).is_err());
+    // nothing is changed
+    assert_eq!(ws.graph.node_count(), count);
+    assert_eq!(ws.structures().count(), 1);
+    assert_eq!(ws.to_sws(root).unwrap(), "CC(C)(C)C");
+}
+
+#[test]
+fn break_bond_with_cappings() {
+    let mut ws = Workspace::new();
+    for (capping, expected) in [
+        (None, "[CH3][CH2].O"),
+        (Some(Capping::Hydrogen), "[CH3][CH3].[OH2]"),
+        (Some(Capping::Dummy), "[CH3][CH2]*.O*"),
+    ] {
+        let id = ws.add_structure("[CH3][CH2]O").unwrap();
+        let root = ws.structure_root(id).unwrap();
+        let carbon = ws.graph.neighbors(root).next().unwrap();
+        let oxygen = ws.graph.neighbors(carbon).next().unwrap();
+        let cleavage = ws.break_bond(carbon, oxygen, capping).unwrap();
+        assert!(cleavage.split);
+        assert_eq!(cleavage.roots, (root, oxygen));
+        assert_eq!(
+            cleavage.caps.len(),
+            if expected.contains('*') { 2 } else { 0 }
+        );
+        // both sides are still the same structure
+        assert_eq!(ws.structure_roots(id), Some(vec![root, oxygen]));
+        assert_eq!(ws.structure_of(oxygen), Some(id));
+        assert_eq!(ws.to_sws(root).unwrap(), expected);
+    }
+}
+
+#[test]
+fn break_ring_bond() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("C1CCCCC1").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let next = ws.graph.neighbors(root).next().unwrap();
+    let cleavage = ws.break_bond(root, next, Some(Capping::Dummy)).unwrap();
+    assert!(!cleavage.split);
+    assert_eq!(cleavage.roots, (root, root));
+    assert_eq!(ws.to_sws(root).unwrap(), "C(CCCCC*)*");
+    assert!(ws.rings(root).unwrap().is_empty());
+}
+
+#[test]
+fn break_bond_caps_stereo_centers() {
+    for (capping, expected) in [
+        (None, "FC(Cl)CCCC"),
+        (Some(Capping::Hydrogen), "F[C@@H](Cl)CCC[CH3]"),
+        (Some(Capping::Dummy), "F[C@](Cl)(*)CCCC*"),
+    ] {
+        let mut ws = Workspace::new();
+        let id = ws.add_structure("F[C@]1(Cl)CCCC1").unwrap();
+        let root = ws.structure_root(id).unwrap();
+        // in the order they're written
+        let mut atoms = ws.structure_atoms(id).unwrap();
+        atoms.sort();
+        let cleavage = ws.break_bond(atoms[1], atoms[3], capping).unwrap();
+        assert!(!cleavage.split);
+        assert_eq!(ws.to_sws(root).unwrap(), expected);
+    }
+
+    // hydrogens are added as atoms if the atom has some
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("[H]OC").unwrap();
+    let mut atoms = ws.structure_atoms(id).unwrap();
+    atoms.sort();
+    let cleavage = ws
+        .break_bond(atoms[1], atoms[2], Some(Capping::Hydrogen))
+        .unwrap();
+    assert!(cleavage.split);
+    assert_eq!(ws.to_sws(cleavage.roots.0).unwrap(), "[H]O[H].[CH4]");
+}
+
+const STEREO: [&str; 5] = [
+    "F[C@]12CCC2CCC1",
+    "C[C@H](F)Cl",
+    "N[C@@H](C)C(=O)O",
+    "C[C@@H]1CC[C@H](O)CC1",
+    "O[C@H]1[C@@H](F)C[C@]1(Cl)Br",
+];
+
+#[test]
+fn reset_root_keeps_stereo() {
+    for smiles in STEREO {
+        let mut ws = Workspace::new();
+        let id = ws.add_structure(smiles).unwrap();
+        for atom in ws.structure_atoms(id).unwrap() {
+            let mut rerooted = ws.clone();
+            let expected = rerooted.to_sws_from(atom).unwrap();
+            rerooted.reset_root(atom).unwrap();
+            assert_eq!(rerooted.structure_root(id), Some(atom));
+            assert_eq!(rerooted.to_sws(atom).unwrap(), expected, "{}", smiles);
+        }
+    }
+}
+
+#[test]
+fn connect_keeps_stereo() {
+    for smiles in STEREO {
+        let mut ws = Workspace::new();
+        let id = ws.add_structure(smiles).unwrap();
+        for atom in ws.structure_atoms(id).unwrap() {
+            let mut connected = ws.clone();
+            let expected = format!("O{}", connected.to_sws_from(atom).unwrap());
+            let water = connected.add_structure("O").unwrap();
+            let oxygen = connected.structure_root(water).unwrap();
+            connected.connect(oxygen, atom, BondType::Single).unwrap();
+            assert_eq!(connected.to_sws(oxygen).unwrap(), expected, "{}", smiles);
+        }
+    }
+
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("C[C@H](F)Cl").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let fluorine = ws
+        .find_node_in_structure(root, |atom| atom.element == Element::F)
+        .unwrap();
+    let water = ws.add_structure("O").unwrap();
+    let oxygen = ws.structure_root(water).unwrap();
+    ws.connect(oxygen, fluorine, BondType::Single).unwrap();
+    assert_eq!(ws.to_sws(oxygen).unwrap(), "OF[C@@H](C)Cl");
+}
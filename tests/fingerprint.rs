@@ -0,0 +1,209 @@
+use petgraph::stable_graph::NodeIndex;
+use smiles_with_selectors::{
+    fingerprint::{
+        count_fingerprint, fingerprint, index::SimilarityIndex, BitFingerprint, FingerprintKind,
+        FingerprintOptions,
+    },
+    workspace::Workspace,
+};
+
+const CORPUS: &str = include_str!("data/conformance.smi");
+
+fn add(ws: &mut Workspace, smiles: &str) -> NodeIndex {
+    ws.add_structure_atoms(smiles).unwrap().1[0]
+}
+
+fn all_options() -> Vec<FingerprintOptions> {
+    vec![
+        FingerprintOptions::default(),
+        FingerprintOptions {
+            features: true,
+            ..Default::default()
+        },
+        FingerprintOptions {
+            kind: FingerprintKind::Path { max_length: 7 },
+            size: 1024,
+            ..Default::default()
+        },
+    ]
+}
+
+#[test]
+fn same_structure_written_differently() {
+    let mut ws = Workspace::new();
+    let pairs = [
+        ("OC(=O)c1ccccc1N", "Nc1c(C(O)=O)cccc1"),
+        ("C1CC1C(Cl)Br", "BrC(Cl)C1CC1"),
+        ("[NH4+].[Cl-]", "[Cl-].[NH4+]"),
+        ("CCO[H]", "OCC"),
+    ];
+    for (a, b) in pairs {
+        let (a, b) = (add(&mut ws, a), add(&mut ws, b));
+        for options in all_options() {
+            let fp_a = fingerprint(&ws, a, &options).unwrap();
+            let fp_b = fingerprint(&ws, b, &options).unwrap();
+            assert_eq!(fp_a, fp_b, "{:?}", options);
+            // salts have no paths, empty fingerprints are not similar
+            let expected = if fp_a.count_ones() == 0 { 0. } else { 1. };
+            assert_eq!(fp_a.tanimoto(&fp_b), Some(expected));
+            assert_eq!(fp_a.dice(&fp_b), Some(expected));
+            let counts_a = count_fingerprint(&ws, a, &options).unwrap();
+            let counts_b = count_fingerprint(&ws, b, &options).unwrap();
+            assert_eq!(counts_a, counts_b);
+        }
+    }
+}
+
+#[test]
+fn similar_structures_score_higher() {
+    let mut ws = Workspace::new();
+    let mut roots = vec![];
+    for smiles in ["c1ccccc1CC(=O)O", "c1ccccc1CCC(=O)O", "CCCCN"] {
+        roots.push(add(&mut ws, smiles));
+    }
+    for options in all_options() {
+        let fps = roots
+            .iter()
+            .map(|root| fingerprint(&ws, *root, &options).unwrap())
+            .collect::<Vec<_>>();
+        assert!(fps[0].tanimoto(&fps[1]).unwrap() > fps[0].tanimoto(&fps[2]).unwrap());
+        let counts = roots
+            .iter()
+            .map(|root| count_fingerprint(&ws, *root, &options).unwrap())
+            .collect::<Vec<_>>();
+        assert!(counts[0].tanimoto(&counts[1]) > counts[0].tanimoto(&counts[2]));
+        assert!(counts[0].dice(&counts[1]) >= counts[0].tanimoto(&counts[1]));
+    }
+}
+
+#[test]
+fn selectors_in_invariants() {
+    let mut ws = Workspace::new();
+    let plain = add(&mut ws, "c1ccccc1N");
+    let selected = add(&mut ws, "c1ccccc1[NH2{amine}]");
+    let options = FingerprintOptions::default();
+    assert_eq!(
+        fingerprint(&ws, plain, &options),
+        fingerprint(&ws, selected, &options)
+    );
+    let options = FingerprintOptions {
+        selectors: true,
+        ..Default::default()
+    };
+    let plain = fingerprint(&ws, plain, &options).unwrap();
+    let selected = fingerprint(&ws, selected, &options).unwrap();
+    assert_ne!(plain, selected);
+    assert!(plain.tanimoto(&selected).unwrap() > 0.);
+}
+
+#[test]
+fn different_sizes_are_not_compared() {
+    let mut ws = Workspace::new();
+    let root = add(&mut ws, "c1ccccc1CC(=O)O");
+    let small = fingerprint(&ws, root, &FingerprintOptions::default()).unwrap();
+    let options = FingerprintOptions {
+        size: small.size() * 2,
+        ..Default::default()
+    };
+    let large = fingerprint(&ws, root, &options).unwrap();
+    assert_eq!(small.tanimoto(&large), None);
+    assert_eq!(small.dice(&large), None);
+    assert_eq!(large.tanimoto(&large), Some(1.));
+}
+
+#[test]
+fn index_matches_linear_scan() {
+    let mut ws = Workspace::new();
+    for smiles in CORPUS
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        ws.add_structure(smiles).unwrap();
+    }
+    for options in all_options() {
+        let index = SimilarityIndex::from_workspace(&ws, &options).unwrap();
+        assert_eq!(index.len(), ws.structures().count());
+        let all = index.iter().collect::<Vec<_>>();
+        for (_, query) in all.iter() {
+            let mut expected = all
+                .iter()
+                .map(|(id, fp)| (**id, query.tanimoto(fp).unwrap()))
+                .collect::<Vec<_>>();
+            expected.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+            let scores = |hits: &[(_, f64)]| hits.iter().map(|(_, s)| *s).collect::<Vec<_>>();
+            for k in [0, 1, 5, all.len() + 1] {
+                let hits = index.top_k(query, k).unwrap();
+                let k = k.min(all.len());
+                assert_eq!(scores(&hits), scores(&expected[..k]));
+            }
+            for threshold in [0.3, 0.7, 1.] {
+                let hits = index.threshold(query, threshold).unwrap();
+                let mut ids = hits.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+                let mut expected_ids = expected
+                    .iter()
+                    .filter(|(_, score)| *score >= threshold)
+                    .map(|(id, _)| *id)
+                    .collect::<Vec<_>>();
+                ids.sort();
+                expected_ids.sort();
+                assert_eq!(ids, expected_ids);
+            }
+        }
+    }
+}
+
+#[test]
+fn index_saved_and_loaded() {
+    let mut ws = Workspace::new();
+    let options = FingerprintOptions::default();
+    let mut index = SimilarityIndex::new(options.size);
+    for (name, smiles) in [
+        ("acid", "c1ccccc1CC(=O)O"),
+        ("longer acid", "c1ccccc1CCC(=O)O"),
+        ("amine", "CCCCN"),
+        ("salt", "[Na+].[Cl-]"),
+    ] {
+        let root = add(&mut ws, smiles);
+        let fingerprint = fingerprint(&ws, root, &options).unwrap();
+        index.insert(name.to_string(), &fingerprint).unwrap();
+    }
+    let mut saved = vec![];
+    index.save(&mut saved).unwrap();
+    let text = String::from_utf8(saved).unwrap();
+    assert!(text.starts_with("SwS similarity index 2048\n"));
+    assert_eq!(text.lines().count(), 5);
+
+    let loaded = SimilarityIndex::<String>::load(text.as_bytes()).unwrap();
+    assert_eq!(loaded.size(), index.size());
+    assert_eq!(loaded.len(), index.len());
+    assert!(loaded.iter().eq(index.iter()));
+    let root = add(&mut ws, "c1ccccc1CC(=O)O");
+    let query = fingerprint(&ws, root, &options).unwrap();
+    assert_eq!(loaded.top_k(&query, 2), index.top_k(&query, 2));
+    assert_eq!(loaded.threshold(&query, 0.3), index.threshold(&query, 0.3));
+
+    let mut saved = vec![];
+    SimilarityIndex::<String>::new(64).save(&mut saved).unwrap();
+    assert_eq!(saved, b"SwS similarity index 64\n");
+    assert!(SimilarityIndex::<usize>::load(
+        "SwS similarity index 64\n1\t00000000000000ff\n".as_bytes()
+    )
+    .is_ok());
+    for broken in [
+        "",
+        "similarity index 64\n",
+        "SwS similarity index 64\n1\t00ff\n",
+        "SwS similarity index 64\nid\t00000000000000ff\n",
+        "SwS similarity index 60\n1\tf0000000000000ff\n",
+    ] {
+        assert!(
+            SimilarityIndex::<usize>::load(broken.as_bytes()).is_err(),
+            "{}",
+            broken
+        );
+    }
+    let mut unsaved = SimilarityIndex::new(64);
+    unsaved.insert("a\tb", &BitFingerprint::new(64)).unwrap();
+    assert!(unsaved.save(&mut vec![]).is_err());
+}
@@ -0,0 +1,45 @@
+use smiles_with_selectors::{
+    definitions::{atom::Atom, element::Element},
+    molecule::Molecule,
+    workspace::Workspace,
+};
+
+#[test]
+fn selectors_kept_by_molecule() {
+    let sws = "[CH3{a}]C([OH{b}])[NH2{a}]";
+    let molecule = Molecule::from_sws(sws).unwrap();
+    assert_eq!(molecule.to_sws().unwrap(), sws);
+    let oxygen = molecule
+        .find_node(|atom| atom.element == Element::O)
+        .unwrap();
+    let nitrogen = molecule
+        .find_node(|atom| atom.element == Element::N)
+        .unwrap();
+    let carbons = molecule.filter_nodes(|atom| atom.element == Element::C);
+    assert_eq!(molecule.get_selector(oxygen), Some("b"));
+    assert_eq!(molecule.get_selector(nitrogen), Some("a"));
+    let atoms = molecule.atoms();
+    assert_eq!(atoms[nitrogen].selector, atoms[carbons[0]].selector);
+    assert_ne!(atoms[nitrogen].selector, atoms[oxygen].selector);
+    assert_eq!(molecule.get_selector(carbons[1]), None);
+    assert_eq!(Molecule::from_sws("CC").unwrap().get_selector(0), None);
+}
+
+#[test]
+fn numbers_too_large() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure("[NH4+:7]").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    assert!(Molecule::from_workspace(&ws, root).is_ok());
+    let changes: [fn(&mut Atom); 4] = [
+        |atom| atom.charge = 200,
+        |atom| atom.explicit_hydrogen = 300,
+        |atom| atom.react_id = Some(1 << 40),
+        |atom| atom.radical_electrons = 256,
+    ];
+    for change in changes {
+        let mut ws = ws.clone();
+        change(&mut ws.graph[root]);
+        assert!(Molecule::from_workspace(&ws, root).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use smiles_with_selectors::{
+    sdf::{SdfReader, SdfWriter},
+    workspace::Workspace,
+};
+
+const PHENYLETHANOL: &str = "C[C@H](O)c1ccccc1 \
+    |(0,0,0;1.5,0,0;2,1.4,0;2.2,-1.2,0;3.6,-1.2,0;4.3,-2.4,0;3.6,-3.6,0;2.2,-3.6,0;1.5,-2.4,0)|";
+
+fn read_back(molblock: &str) -> String {
+    let ws = Workspace::from_molblock(molblock).unwrap();
+    let id = ws.structures().next().unwrap();
+    ws.to_cxsws(ws.structure_root(id).unwrap()).unwrap()
+}
+
+#[test]
+fn v2000_round_trip() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure(PHENYLETHANOL).unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let molblock = ws.to_molblock(root).unwrap();
+    assert!(molblock.lines().nth(3).unwrap().ends_with("V2000"));
+    assert!(molblock.contains("\n    1.5000    0.0000    0.0000 C   0  0  1"));
+    assert_eq!(read_back(&molblock), ws.to_cxsws(root).unwrap());
+
+    // without coordinates
+    let id = ws.add_structure("[NH4+].[O-]C(=O)C[N{amine}]").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let molblock = ws.to_molblock(root).unwrap();
+    assert_eq!(read_back(&molblock), "[NH4+].[O-]C(=O)C[N{amine}]");
+}
+
+#[test]
+fn v3000_round_trip() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure(PHENYLETHANOL).unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let molblock = ws.to_molblock_v3000(root).unwrap();
+    assert!(molblock.contains("M  V30 2 C 1.5000 0.0000 0.0000 0 CFG=1 VAL=4\n"));
+    assert_eq!(read_back(&molblock), ws.to_cxsws(root).unwrap());
+
+    // enhanced stereo is written as V3000 anyway
+    let id = ws.add_structure("C[C@H](O)[C@H](C)F |&1:1,3|").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let molblock = ws.to_molblock(root).unwrap();
+    assert!(molblock.lines().nth(3).unwrap().ends_with("V3000"));
+    assert_eq!(read_back(&molblock), "C[C@H](O)[C@H](C)F |&1:1,3|");
+}
+
+#[test]
+fn sdf_round_trip() {
+    let mut ws = Workspace::new();
+    let mut writer = SdfWriter::new(vec![]);
+    for (smiles, name) in [("CCO", "ethanol"), ("c1ccccc1O", "phenol")] {
+        let id = ws.add_structure(smiles).unwrap();
+        ws.set_structure_property(id, "name", name);
+        writer
+            .write_structure(&ws, ws.structure_root(id).unwrap())
+            .unwrap();
+    }
+    let file = writer.into_inner();
+    let workspaces = SdfReader::new(file.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(workspaces.len(), 2);
+    for (read, (smiles, name)) in workspaces
+        .iter()
+        .zip([("CCO", "ethanol"), ("c1ccccc1O", "phenol")])
+    {
+        let id = read.structures().next().unwrap();
+        assert_eq!(read.structure_property(id, "name"), Some(name));
+        let root = read.structure_root(id).unwrap();
+        assert_eq!(read.to_sws(root).unwrap(), smiles);
+    }
+
+    // a broken record is reported and skipped
+    let file = String::from_utf8(file).unwrap().replacen("M  END", "", 1);
+    let results = SdfReader::new(file.as_bytes()).collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_err());
+    assert!(results[1].is_ok());
+}
+
+#[test]
+fn v2000_bond_stereo() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure(PHENYLETHANOL).unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let molblock = ws.to_molblock(root).unwrap();
+    assert!(molblock.contains("\n  2  1  1  6\n"));
+
+    // the wedges alone define the centre
+    let molblock = molblock.replace("C   0  0  1", "C   0  0  0");
+    let smiles = |molblock: &str| read_back(molblock).split(' ').next().unwrap().to_owned();
+    assert_eq!(smiles(&molblock), "C[C@H](O)c1ccccc1");
+    let wedged = molblock.replace("\n  2  1  1  6\n", "\n  2  1  1  1\n");
+    assert_eq!(smiles(&wedged), "C[C@@H](O)c1ccccc1");
+    let either = molblock.replace("\n  2  1  1  6\n", "\n  2  1  1  4\n");
+    assert_eq!(smiles(&either), "C[CH](O)c1ccccc1");
+
+    // double bonds take their configuration from the coordinates
+    for (cxsmiles, expected) in [
+        (
+            "F/C=C/F |(-0.75,1.3,0;0,0,0;1.5,0,0;2.25,-1.3,0)|",
+            "F/C=C/F",
+        ),
+        (
+            "F/C=C\\F |(-0.75,1.3,0;0,0,0;1.5,0,0;2.25,1.3,0)|",
+            "F/C=C\\F",
+        ),
+    ] {
+        let id = ws.add_structure(cxsmiles).unwrap();
+        let molblock = ws.to_molblock(ws.structure_root(id).unwrap()).unwrap();
+        assert_eq!(smiles(&molblock), expected);
+    }
+    let id = ws
+        .add_structure("FC=CF |(-0.75,1.3,0;0,0,0;1.5,0,0;2.25,1.3,0)|")
+        .unwrap();
+    let molblock = ws.to_molblock(ws.structure_root(id).unwrap()).unwrap();
+    assert!(molblock.contains("\n  2  3  2  3\n"));
+    assert_eq!(smiles(&molblock), "FC=CF");
+}
+
+#[test]
+fn v3000_bond_stereo() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure(PHENYLETHANOL).unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let molblock = ws.to_molblock_v3000(root).unwrap();
+    assert!(molblock.contains("M  V30 1 1 2 1 CFG=3\n"));
+
+    // the wedges alone define the centre
+    let molblock = molblock.replace(" CFG=1 VAL=4", " VAL=4");
+    let smiles = |molblock: &str| read_back(molblock).split(' ').next().unwrap().to_owned();
+    assert_eq!(smiles(&molblock), "C[C@H](O)c1ccccc1");
+    let wedged = molblock.replace("1 1 2 1 CFG=3\n", "1 1 2 1 CFG=1\n");
+    assert_eq!(smiles(&wedged), "C[C@@H](O)c1ccccc1");
+    let either = molblock.replace("1 1 2 1 CFG=3\n", "1 1 2 1 CFG=2\n");
+    assert_eq!(smiles(&either), "C[CH](O)c1ccccc1");
+
+    let id = ws
+        .add_structure("FC=CF |(-0.75,1.3,0;0,0,0;1.5,0,0;2.25,1.3,0)|")
+        .unwrap();
+    let molblock = ws
+        .to_molblock_v3000(ws.structure_root(id).unwrap())
+        .unwrap();
+    assert!(molblock.contains("M  V30 2 2 2 3 CFG=2\n"));
+    assert_eq!(smiles(&molblock), "FC=CF");
+
+    // sgroup fields referring other ids are rejected rather than dropped
+    let id = ws.add_structure("CC(=O)O").unwrap();
+    let molblock = ws
+        .to_molblock_v3000(ws.structure_root(id).unwrap())
+        .unwrap();
+    let superatom = "M  V30 BEGIN SGROUP\nM  V30 1 SUP 0 ATOMS=(3 2 3 4) SAP=(3 2 1 1)\n\
+        M  V30 END SGROUP\nM  V30 END CTAB";
+    let molblock = molblock.replace("M  V30 END CTAB", superatom);
+    assert_eq!(
+        Workspace::from_molblock(&molblock).err().as_deref(),
+        Some("Unsupported sgroup field SAP")
+    );
+}
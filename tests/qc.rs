@@ -0,0 +1,140 @@
+use smiles_with_selectors::{
+    export::qc::{multiplicity, to_gaussian, to_orca, to_xtb, QcSettings},
+    workspace::Workspace,
+};
+
+/// Two waters, the first oxygen is frozen, the second one gets its own
+/// basis, and each oxygen is a fragment.
+const DIMER: &str = "[O{Fragment(1);Freeze}]([H])[H].[O{Fragment(2);Basis(def2-TZVP)}]([H])[H] \
+    |(0,0,0;0.96,0,0;-0.24,0.93,0;3,0,0;3.96,0,0;2.76,0.93,0)|";
+
+fn settings(method: &str) -> QcSettings {
+    QcSettings {
+        method: method.to_string(),
+        basis: "6-31G*".to_string(),
+        keywords: vec!["Opt".to_string()],
+        title: "dimer".to_string(),
+    }
+}
+
+#[test]
+fn gaussian() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure(DIMER).unwrap();
+    let root = ws.structure_root(id).unwrap();
+    assert_eq!(
+        to_gaussian(&ws, root, &settings("B3LYP")).unwrap(),
+        "#p B3LYP/Gen Opt
+
+dimer
+
+0 1 0 1 0 1
+O(Fragment=1)    -1     0.000000     0.000000     0.000000
+H                 0    -0.240000     0.930000     0.000000
+H                 0     0.960000     0.000000     0.000000
+O(Fragment=2)     0     3.000000     0.000000     0.000000
+H                 0     2.760000     0.930000     0.000000
+H                 0     3.960000     0.000000     0.000000
+
+1 2 3 5 6 0
+6-31G*
+****
+4 0
+def2-TZVP
+****
+
+"
+    );
+}
+
+#[test]
+fn orca() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure(DIMER).unwrap();
+    let root = ws.structure_root(id).unwrap();
+    assert_eq!(
+        to_orca(&ws, root, &settings("B3LYP")).unwrap(),
+        "! B3LYP 6-31G* Opt
+# dimer
+%geom
+  Constraints
+    { C 0 C }
+  end
+end
+* xyz 0 1
+O(1)         0.000000     0.000000     0.000000
+H           -0.240000     0.930000     0.000000
+H            0.960000     0.000000     0.000000
+O(2)         3.000000     0.000000     0.000000 NewGTO \"def2-TZVP\" end
+H            2.760000     0.930000     0.000000
+H            3.960000     0.000000     0.000000
+*
+"
+    );
+}
+
+#[test]
+fn xtb() {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure(DIMER).unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let input = to_xtb(&ws, root, &settings("GFN2-xTB")).unwrap();
+    assert_eq!(
+        input.xyz,
+        "6
+dimer
+O      0.000000     0.000000     0.000000
+H     -0.240000     0.930000     0.000000
+H      0.960000     0.000000     0.000000
+O      3.000000     0.000000     0.000000
+H      2.760000     0.930000     0.000000
+H      3.960000     0.000000     0.000000
+"
+    );
+    assert_eq!(
+        input.xcontrol,
+        "$chrg 0
+$spin 0
+$gfn
+   method=2
+$fix
+   atoms: 1
+$split
+   fragment: 1,1
+   fragment: 2,4
+$end
+"
+    );
+}
+
+#[test]
+fn electron_parity() {
+    let mut ws = Workspace::new();
+    for (smiles, expected) in [
+        ("C", Some(1)),
+        ("[CH3] |^1:0|", Some(2)),
+        ("O=O |^1:0,1|", Some(3)),
+        ("[Na+]", Some(1)),
+        ("[Na]", None),
+        // a methyl radical flag on methane
+        ("C([H])([H])([H])[H] |^1:0|", None),
+    ] {
+        let id = ws.add_structure(smiles).unwrap();
+        let root = ws.structure_root(id).unwrap();
+        assert_eq!(multiplicity(&ws, root).ok(), expected, "{}", smiles);
+    }
+
+    let id = ws
+        .add_structure("C([H])([H])([H])[H] |^1:0,(0,0,0;1,0,0;0,1,0;0,0,1;-1,0,0)|")
+        .unwrap();
+    let root = ws.structure_root(id).unwrap();
+    assert!(to_gaussian(&ws, root, &settings("B3LYP"))
+        .unwrap_err()
+        .contains("electrons"));
+    assert!(to_orca(&ws, root, &settings("B3LYP"))
+        .unwrap_err()
+        .contains("electrons"));
+    assert!(to_xtb(&ws, root, &settings("GFN2"))
+        .unwrap_err()
+        .contains("electrons"));
+}
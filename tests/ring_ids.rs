@@ -0,0 +1,37 @@
+use smiles_with_selectors::{definitions::bond::BondType, workspace::Workspace};
+
+#[test]
+fn more_than_99_rings() {
+    let mut ws = Workspace::new();
+    // 150 cyclopropanes in a chain, each ring is closed before the next opens
+    let smiles = "C1CC1".repeat(150);
+    let structure = ws.add_structure(&smiles).unwrap();
+    let root = ws.structure_root(structure).unwrap();
+    let written = ws.to_sws(root).unwrap();
+    assert!(!written.contains('%'), "{}", written);
+    assert_eq!(written, smiles);
+
+    let again = ws.add_structure(&written).unwrap();
+    let again_root = ws.structure_root(again).unwrap();
+    assert_eq!(ws.get_atoms_of_structure(again_root).unwrap().len(), 450);
+    assert_eq!(ws.to_sws(again_root).unwrap(), written);
+}
+
+#[test]
+fn fails_with_100_open_rings() {
+    let mut ws = Workspace::new();
+    let structure = ws.add_structure(&"C".repeat(102)).unwrap();
+    let root = ws.structure_root(structure).unwrap();
+    let atoms = ws
+        .get_atoms_of_structure(root)
+        .unwrap()
+        .into_iter()
+        .filter(|atom| *atom != root && ws.get_edge_undirected(root, *atom).is_none())
+        .collect::<Vec<_>>();
+    assert_eq!(atoms.len(), 100);
+    // all of them are bonded to the root, so 100 rings are open at the root
+    for atom in atoms {
+        ws.connect(atom, root, BondType::Single).unwrap();
+    }
+    assert_eq!(ws.to_sws(root), None);
+}
@@ -0,0 +1,451 @@
+use std::collections::HashMap;
+
+use petgraph::stable_graph::NodeIndex;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use smiles_with_selectors::{
+    definitions::bond::BondType,
+    workspace::{encode::WriteOptions, Workspace},
+};
+
+const CORPUS: &str = include_str!("data/conformance.smi");
+
+fn corpus() -> impl Iterator<Item = &'static str> {
+    CORPUS
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn all_options() -> Vec<WriteOptions> {
+    vec![
+        WriteOptions::default(),
+        WriteOptions {
+            reuse_ring_ids: false,
+            ..Default::default()
+        },
+        WriteOptions {
+            longest_chain_last: true,
+            ..Default::default()
+        },
+        WriteOptions {
+            explicit_bonds: true,
+            ..Default::default()
+        },
+        WriteOptions {
+            kekule: true,
+            ..Default::default()
+        },
+        WriteOptions {
+            isomeric: false,
+            ..Default::default()
+        },
+    ]
+}
+
+/// Write the structure, read it back and compare atoms, bonds and stereo
+/// of both by the order atoms are written.
+fn check_round_trip(smiles: &str, options: &WriteOptions) -> Result<(), String> {
+    let mut ws = Workspace::new();
+    let id = ws.add_structure(smiles)?;
+    let root = ws.structure_root(id).ok_or("structure has no root")?;
+    let Some((written, atoms)) = ws.write_sws(root, options) else {
+        // only kekulé form is allowed to fail
+        return if options.kekule {
+            Ok(())
+        } else {
+            Err("failed to write".to_string())
+        };
+    };
+    check_written(&mut ws, &written, &atoms, options)
+}
+
+/// Read the written structure back and compare it with the atoms written
+fn check_written(
+    ws: &mut Workspace,
+    written: &str,
+    atoms: &[NodeIndex],
+    options: &WriteOptions,
+) -> Result<(), String> {
+    let (_, read) = ws
+        .add_structure_atoms(written)
+        .map_err(|err| format!("{written} can't be read: {err}"))?;
+    if atoms.len() != read.len() {
+        return Err(format!("{written} has {} atoms", read.len()));
+    }
+    let mapping = atoms
+        .iter()
+        .copied()
+        .zip(read.iter().copied())
+        .collect::<HashMap<_, _>>();
+    for (original, copied) in mapping.iter() {
+        compare_atoms(ws, *original, *copied, &mapping, options)
+            .map_err(|err| format!("{written}: {err}"))?;
+    }
+    Ok(())
+}
+
+fn compare_atoms(
+    ws: &Workspace,
+    original: NodeIndex,
+    copied: NodeIndex,
+    mapping: &HashMap<NodeIndex, NodeIndex>,
+    options: &WriteOptions,
+) -> Result<(), String> {
+    let a = ws.get_atom(original).unwrap();
+    let b = ws.get_atom(copied).unwrap();
+    let same = a.element == b.element
+        && a.charge == b.charge
+        && a.selector == b.selector
+        && a.react_id == b.react_id
+        && (options.kekule || a.aromatic == b.aromatic)
+        && (!options.isomeric || a.isotope == b.isotope)
+        && ws.implicit_hydrogen_count(original) == ws.implicit_hydrogen_count(copied);
+    if !same {
+        return Err(format!("atom {:?} is read as {:?}", a, b));
+    }
+    let mut neighbors = ws
+        .graph
+        .neighbors_undirected(original)
+        .map(|neighbor| mapping[&neighbor])
+        .collect::<Vec<_>>();
+    let mut copied_neighbors = ws.graph.neighbors_undirected(copied).collect::<Vec<_>>();
+    neighbors.sort();
+    copied_neighbors.sort();
+    if neighbors != copied_neighbors {
+        return Err(format!("neighbors of {:?} changed", original));
+    }
+    for neighbor in ws.graph.neighbors_undirected(original) {
+        let (bond, _, direction) = ws.get_edge_undirected(original, neighbor).unwrap();
+        let (copied_bond, _, copied_direction) =
+            ws.get_edge_undirected(copied, mapping[&neighbor]).unwrap();
+        let copied_bond = if direction == copied_direction {
+            *copied_bond
+        } else {
+            copied_bond.reverse()
+        };
+        let expected = match bond.bond_type() {
+            BondType::UpSingle | BondType::DownSingle if !options.isomeric => BondType::Single,
+            bond_type => bond_type,
+        };
+        if !(options.kekule && expected == BondType::Aromatic)
+            && expected != copied_bond.bond_type()
+        {
+            return Err(format!(
+                "bond {:?} of {:?} is read as {:?}",
+                bond, original, copied_bond
+            ));
+        }
+    }
+    if !options.isomeric {
+        return if b.chirality_type.is_none() {
+            Ok(())
+        } else {
+            Err(format!("chirality of {:?} is written", original))
+        };
+    }
+    match (a.chirality_type, b.chirality_type) {
+        (None, None) => Ok(()),
+        (Some(chirality), Some(copied_chirality)) => {
+            let expected = ws
+                .chiral_neighbors(original)
+                .unwrap()
+                .into_iter()
+                .map(|neighbor| neighbor.map(|neighbor| mapping[&neighbor]))
+                .collect::<Vec<_>>();
+            let order = ws.chiral_neighbors(copied).unwrap();
+            if copied_chirality.reorder(&order, &expected) == Some(chirality) {
+                Ok(())
+            } else {
+                Err(format!("chirality of {:?} is inverted", original))
+            }
+        }
+        _ => Err(format!("chirality of {:?} is lost", original)),
+    }
+}
+
+#[test]
+fn corpus_round_trip() {
+    for smiles in corpus() {
+        for options in all_options() {
+            if let Err(err) = check_round_trip(smiles, &options) {
+                panic!("{smiles} with {:?}: {err}", options);
+            }
+        }
+    }
+}
+
+#[test]
+fn corpus_is_stable() {
+    for smiles in corpus() {
+        let mut ws = Workspace::new();
+        let id = ws.add_structure(smiles).unwrap();
+        let written = ws.to_sws(ws.structure_root(id).unwrap()).unwrap();
+        let id = ws.add_structure(&written).unwrap();
+        let again = ws.to_sws(ws.structure_root(id).unwrap()).unwrap();
+        assert_eq!(written, again, "{smiles} is not written stably");
+    }
+}
+
+#[test]
+fn ring_ids_and_bonds() {
+    let write = |smiles: &str, options: &WriteOptions| {
+        let mut ws = Workspace::new();
+        let id = ws.add_structure(smiles).unwrap();
+        ws.to_sws_with(ws.structure_root(id).unwrap(), options)
+            .unwrap()
+    };
+    let default = WriteOptions::default();
+    assert_eq!(write("C1CCC1C2CCC2", &default), "C1CCC1C1CCC1");
+    let no_reuse = WriteOptions {
+        reuse_ring_ids: false,
+        ..Default::default()
+    };
+    assert_eq!(write("C1CCC1C1CCC1", &no_reuse), "C1CCC1C2CCC2");
+    assert_eq!(write("C=1CCC(CC)C=1", &default), "C=1CCC(CC)C1");
+    assert_eq!(write("c1ccccc1-c1ccccc1", &default), "c1ccccc1-c1ccccc1");
+    assert_eq!(write("C@(F)(Cl)Br", &default), "[C@](F)(Cl)Br");
+    let longest = WriteOptions {
+        longest_chain_last: true,
+        ..Default::default()
+    };
+    assert_eq!(write("N[C@@H](CCCC)C(=O)O", &longest), "N[C@H](C(=O)O)CCCC");
+}
+
+#[test]
+fn bracket_atoms_as_parsed() {
+    for smiles in [
+        "C[P{Replacer(-,2)}]",
+        "[N{amine}]CC",
+        "[C@](F)(Cl)Br",
+        "[CH2]C",
+        "[NH4+]",
+    ] {
+        let mut ws = Workspace::new();
+        let id = ws.add_structure(smiles).unwrap();
+        let root = ws.structure_root(id).unwrap();
+        assert_eq!(ws.to_sws(root).unwrap(), smiles);
+    }
+}
+
+#[test]
+fn corpus_from_every_atom() {
+    for smiles in corpus() {
+        let mut ws = Workspace::new();
+        let id = ws.add_structure(smiles).unwrap();
+        for start in ws.structure_atoms(id).unwrap() {
+            let options = WriteOptions::default();
+            let (written, atoms) = ws.write_sws_from(start, &options).unwrap();
+            assert_eq!(atoms[0], start);
+            if let Err(err) = check_written(&mut ws, &written, &atoms, &options) {
+                panic!("{smiles} written from {:?} as {written}: {err}", start);
+            }
+        }
+    }
+}
+
+#[test]
+fn replacer_first() {
+    let mut ws = Workspace::new();
+    let id = ws
+        .add_structure("c1ccccc1[C@@H](C)[N{Replacer(-,2)}].[Cl-]")
+        .unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let before = ws.to_sws(root).unwrap();
+    let site = ws
+        .find_node(|atom| atom.selector.as_deref() == Some("Replacer(-,2)"))
+        .unwrap();
+    assert_eq!(
+        ws.to_sws_from(site).unwrap(),
+        "[N{Replacer(-,2)}][C@@H](c1ccccc1)C.[Cl-]"
+    );
+    // the stored tree is not changed
+    assert_eq!(ws.find_root_of(site), Some(root));
+    assert_eq!(ws.to_sws(root).unwrap(), before);
+}
+
+#[test]
+fn ring_bonds_in_written_order() {
+    let write = |smiles: &str| {
+        let mut ws = Workspace::new();
+        let id = ws.add_structure(smiles).unwrap();
+        ws.to_sws(ws.structure_root(id).unwrap()).unwrap()
+    };
+    // the same center, ring bonds closed in another order
+    assert_eq!(write("F[C@]12CCC1CCC2"), write("F[C@@]12CCC2CCC1"));
+    assert_eq!(write("F/C=C/1.Cl1"), write("F/C=C/Cl"));
+    assert_eq!(write("F/C=C1.Cl\\1"), write("F/C=C/Cl"));
+}
+
+#[test]
+fn unrecognized_characters() {
+    let mut ws = Workspace::new();
+    assert!(ws.add_structure("CC?C").is_err());
+    assert!(ws.add_structure("C1CC(C1").is_err());
+    assert_eq!(ws.graph.node_count(), 0);
+}
+
+const ATOMS: [&str; 24] = [
+    "C",
+    "C",
+    "C",
+    "N",
+    "O",
+    "S",
+    "Cl",
+    "c",
+    "c",
+    "n",
+    "o",
+    "*",
+    "[C@H]",
+    "[C@@H]",
+    "[C@]",
+    "[C@@]",
+    "C@@",
+    "[13CH2]",
+    "[N+]",
+    "[O-]",
+    "[NH3+]",
+    "[CH2{R1}]",
+    "[C@H:3{center}]",
+    "[2H]",
+];
+
+const BONDS: [&str; 9] = ["", "", "", "-", "=", "#", ":", "/", "\\"];
+
+/// A random SMILES of chains, branches, ring bonds and components, its
+/// valence is not checked.
+fn random_smiles(rng: &mut StdRng) -> String {
+    let mut smiles = String::new();
+    for component in 0..rng.gen_range(1..=2) {
+        if component > 0 {
+            smiles.push('.');
+        }
+        let atoms = rng.gen_range(1..24);
+        random_component(rng, &mut smiles, atoms);
+    }
+    smiles
+}
+
+fn random_component(rng: &mut StdRng, smiles: &mut String, atoms: usize) {
+    // atoms where the ring bonds are opened, by ring ids
+    let mut open: HashMap<usize, usize> = HashMap::new();
+    let mut bonded = vec![];
+    // atom to bond the next atom, and atoms waiting at branches
+    let mut current = 0;
+    let mut branches = vec![];
+    for atom in 0..atoms {
+        if atom > 0 {
+            if !branches.is_empty() && rng.gen_bool(0.3) {
+                smiles.push(')');
+                current = branches.pop().unwrap();
+            }
+            if rng.gen_bool(0.25) {
+                smiles.push('(');
+                branches.push(current);
+            }
+            smiles.push_str(BONDS.choose(rng).unwrap());
+            bonded.push((current, atom));
+        }
+        smiles.push_str(ATOMS.choose(rng).unwrap());
+        let mut ids = open.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        for id in ids {
+            let other = open[&id];
+            let pair = (other.min(atom), other.max(atom));
+            if rng.gen_bool(0.4)
+                && other != atom
+                && !bonded.contains(&pair)
+                && !bonded.contains(&(pair.1, pair.0))
+            {
+                open.remove(&id);
+                bonded.push(pair);
+                smiles.push_str(&ring_token(id));
+            }
+        }
+        if atom + 2 < atoms && rng.gen_bool(0.2) {
+            let id = (1..).find(|id| !open.contains_key(id)).unwrap();
+            open.insert(id, atom);
+            smiles.push_str(BONDS.choose(rng).unwrap());
+            smiles.push_str(&ring_token(id));
+        }
+        current = atom;
+    }
+    for _ in branches {
+        smiles.push(')');
+    }
+    if !open.is_empty() {
+        // close rings at new atoms, they are not bonded to the opening ones
+        smiles.push('C');
+        let mut ids = open.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        for id in ids {
+            smiles.push('C');
+            smiles.push_str(&ring_token(id));
+        }
+    }
+}
+
+fn ring_token(id: usize) -> String {
+    if id >= 10 {
+        format!("%{id}")
+    } else {
+        id.to_string()
+    }
+}
+
+#[test]
+fn random_round_trip() {
+    let mut rng = StdRng::seed_from_u64(20240521);
+    for _ in 0..2000 {
+        let smiles = random_smiles(&mut rng);
+        let options = WriteOptions {
+            reuse_ring_ids: rng.gen_bool(0.5),
+            longest_chain_last: rng.gen_bool(0.5),
+            explicit_bonds: rng.gen_bool(0.5),
+            ..Default::default()
+        };
+        if let Err(err) = check_round_trip(&smiles, &options) {
+            panic!("{smiles} with {:?}: {err}", options);
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn random_sws_round_trip() {
+    let mut rng = StdRng::seed_from_u64(44);
+    for smiles in corpus() {
+        let mut ws = Workspace::new();
+        let id = ws.add_structure(smiles).unwrap();
+        let root = ws.structure_root(id).unwrap();
+        for _ in 0..20 {
+            let options = WriteOptions::default();
+            let (written, atoms) = ws.write_random_sws(root, &options, &mut rng).unwrap();
+            if let Err(err) = check_written(&mut ws, &written, &atoms, &options) {
+                panic!("{smiles} written randomly as {written}: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn distinct_random_sws() {
+    let mut rng = StdRng::seed_from_u64(45);
+    let mut ws = Workspace::new();
+    let id = ws
+        .add_structure("c1cc([N{Replacer(-,2)}])ccc1C(=O)O")
+        .unwrap();
+    let root = ws.structure_root(id).unwrap();
+    let written = ws.random_sws(root, 10, &mut rng).collect::<Vec<_>>();
+    assert_eq!(written.len(), 10);
+    for (index, sws) in written.iter().enumerate() {
+        assert!(sws.contains("[N{Replacer(-,2)}]"));
+        assert!(!written[index + 1..].contains(sws));
+    }
+    // methane can only be written once
+    let id = ws.add_structure("C").unwrap();
+    let root = ws.structure_root(id).unwrap();
+    assert_eq!(ws.random_sws(root, 10, &mut rng).count(), 1);
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "serde")]
+
+use smiles_with_selectors::{
+    definitions::{atom::Atom, bond::BondType},
+    workspace::Workspace,
+};
+
+#[test]
+fn workspace_round_trip() {
+    let mut ws = Workspace::new();
+    let first = ws
+        .add_structure("C[C@H](O)c1ccccc1[N{amine}] |$R1$,^1:2,&1:1|")
+        .unwrap();
+    let second = ws.add_structure("[NH4+].[Cl-]").unwrap();
+    ws.set_structure_property(second, "name", "salt");
+    // a removed atom leaves a hole in the indices
+    let removed = ws.add_structure("C").unwrap();
+    ws.remove_structure(removed);
+
+    let json = serde_json::to_string(&ws).unwrap();
+    let mut read = serde_json::from_str::<Workspace>(&json).unwrap();
+    assert_eq!(read.structures().collect::<Vec<_>>(), vec![first, second]);
+    for id in [first, second] {
+        let root = ws.structure_root(id).unwrap();
+        assert_eq!(read.structure_root(id), Some(root));
+        assert_eq!(read.structure_of(root), Some(id));
+        assert_eq!(read.to_cxsws(root), ws.to_cxsws(root));
+    }
+    assert_eq!(read.structure_property(second, "name"), Some("salt"));
+
+    // the read workspace goes on working
+    let root = read.structure_root(first).unwrap();
+    let site = read
+        .find_node_in_structure(root, |atom| atom.selector.is_some())
+        .unwrap();
+    read.substitute(site, "C", 0, BondType::Single).unwrap();
+    assert_eq!(read.structures().count(), 2);
+    let next = read.add_structure("O").unwrap();
+    assert!(next > second);
+}
+
+#[test]
+fn atom_round_trip() {
+    let atom = Atom::new("[13CH2+:3{site}]").unwrap();
+    let json = serde_json::to_string(&atom).unwrap();
+    let read = serde_json::from_str::<Atom>(&json).unwrap();
+    assert_eq!(read.to_token(), "[13CH2+:3{site}]");
+}
@@ -0,0 +1,37 @@
+use smiles_with_selectors::{
+    definitions::{bond::BondType, element::Element},
+    workspace::Workspace,
+};
+
+#[test]
+fn structure_of_follows_roots() {
+    let mut ws = Workspace::new();
+    let ethanol = ws.add_structure("CCO.[Na+]").unwrap();
+    let benzene = ws.add_structure("c1ccccc1").unwrap();
+    for atom in ws.structure_atoms(ethanol).unwrap() {
+        assert_eq!(ws.structure_of(atom), Some(ethanol));
+    }
+    let root = ws.structure_root(ethanol).unwrap();
+    let oxygen = ws
+        .find_node_in_structure(root, |atom| atom.element == Element::O)
+        .unwrap();
+    ws.reset_root(oxygen).unwrap();
+    assert_eq!(ws.structure_root(ethanol), Some(oxygen));
+    assert_eq!(ws.structure_of(root), Some(ethanol));
+
+    // benzene joins ethanol
+    let ring_atom = ws.structure_root(benzene).unwrap();
+    ws.connect(oxygen, ring_atom, BondType::Single).unwrap();
+    assert_eq!(ws.structure_of(ring_atom), Some(ethanol));
+    assert!(ws.structure_info(benzene).is_none());
+
+    // both sides of a split stay in the structure
+    assert!(ws.break_bond(oxygen, ring_atom, None).unwrap().split);
+    assert_eq!(ws.structure_of(ring_atom), Some(ethanol));
+    assert_eq!(ws.structure_roots(ethanol).unwrap().len(), 3);
+
+    ws.remove_structure(ethanol).unwrap();
+    assert_eq!(ws.structures().count(), 0);
+    assert_eq!(ws.graph.node_count(), 0);
+    assert_eq!(ws.structure_of(ring_atom), None);
+}
@@ -0,0 +1,61 @@
+use smiles_with_selectors::workspace::Workspace;
+
+mod common;
+
+use common::assert_close;
+
+#[test]
+fn butane_indices() {
+    let mut ws = Workspace::new();
+    let atoms = ws.add_structure_atoms("CCCC").unwrap().1;
+    let matrix = ws.distance_matrix(atoms[0]).unwrap();
+    assert_eq!(matrix.distance(atoms[0], atoms[3]), Some(3));
+    assert_eq!(matrix.distance(atoms[2], atoms[1]), Some(1));
+    let indices = ws.topological_indices(atoms[0]).unwrap();
+    assert_eq!(indices.wiener, 10);
+    assert_close(indices.balaban_j, 1.9747);
+    assert_close(indices.chi[0], 2. + 2f64.sqrt());
+    assert_close(indices.chi[1], 2f64.sqrt() + 0.5);
+    assert_close(indices.chi[2], 1.);
+    assert_close(indices.chi[3], 0.5);
+    assert_eq!((indices.zagreb_m1, indices.zagreb_m2), (10, 8));
+}
+
+#[test]
+fn kappa_shapes() {
+    let mut ws = Workspace::new();
+    let pentane = ws.add_structure_atoms("CCCCC").unwrap().1[0];
+    let kappa = ws.topological_indices(pentane).unwrap().kappa;
+    assert_close(kappa[0], 5.);
+    assert_close(kappa[1], 4.);
+    assert_close(kappa[2], 4.);
+    // hydrogens are not counted, Hall-Kier α shrinks shapes of sp2 atoms
+    let explicit = ws.add_structure_atoms("[H]C([H])([H])CCCC").unwrap().1[0];
+    assert_eq!(ws.topological_indices(explicit).unwrap().kappa, kappa);
+    let benzene = ws.add_structure_atoms("c1ccccc1").unwrap().1[0];
+    let indices = ws.topological_indices(benzene).unwrap();
+    assert!(indices.kappa[0] < 6.);
+    assert_eq!(indices.wiener, 27);
+}
+
+#[test]
+fn shortest_paths() {
+    let mut ws = Workspace::new();
+    let atoms = ws
+        .add_structure_atoms("[CH3{Replacer}]C1CCC([OH{Replacer}])CC1.[Na+]")
+        .unwrap()
+        .1;
+    let sites = ws.filter_nodes(|atom| atom.selector.as_deref() == Some("Replacer"));
+    let path = ws.shortest_path(sites[0], sites[1]).unwrap();
+    assert_eq!(path.len() - 1, 5);
+    assert_eq!((path[0], path[5]), (sites[0], sites[1]));
+    for pair in path.windows(2) {
+        assert!(ws.get_edge_undirected(pair[0], pair[1]).is_some());
+    }
+    let sodium = atoms[atoms.len() - 1];
+    assert_eq!(ws.shortest_path(sites[0], sodium), None);
+    assert_eq!(ws.shortest_path(sodium, sodium), Some(vec![sodium]));
+    let matrix = ws.distance_matrix(sodium).unwrap();
+    assert_eq!(matrix.distance(sites[0], sodium), None);
+    assert_eq!(matrix.distance(sites[0], sites[1]), Some(5));
+}
@@ -0,0 +1,60 @@
+use smiles_with_selectors::workspace::{encode::WriteOptions, Workspace};
+
+#[test]
+fn write_options() {
+    let explicit_bonds = WriteOptions {
+        explicit_bonds: true,
+        ..Default::default()
+    };
+    let kekule = WriteOptions {
+        kekule: true,
+        ..Default::default()
+    };
+    let non_isomeric = WriteOptions {
+        isomeric: false,
+        ..Default::default()
+    };
+    let all = WriteOptions {
+        explicit_bonds: true,
+        kekule: true,
+        isomeric: false,
+        ..Default::default()
+    };
+    for (smiles, expected) in [
+        (
+            "c1ccc2[nH]ccc2c1",
+            [
+                "c:1:c:c:c:2:[nH]:c:c:c2:c1",
+                "C1=CC=C2[NH]C=CC2=C1",
+                "c1ccc2[nH]ccc2c1",
+                "C-1=C-C=C-2-[NH]-C=C-C2=C1",
+            ],
+        ),
+        (
+            "F/C=C/[13CH2][C@H](O)Cl",
+            [
+                "F/C=C/[13CH2]-[C@H](-O)-Cl",
+                "F/C=C/[13CH2][C@H](O)Cl",
+                "FC=C[CH2][CH](O)Cl",
+                "F-C=C-[CH2]-[CH](-O)-Cl",
+            ],
+        ),
+    ] {
+        let mut ws = Workspace::new();
+        let id = ws.add_structure(smiles).unwrap();
+        let root = ws.structure_root(id).unwrap();
+        for (options, expected) in [&explicit_bonds, &kekule, &non_isomeric, &all]
+            .into_iter()
+            .zip(expected)
+        {
+            let written = ws.to_sws_with(root, options).unwrap();
+            assert_eq!(written, expected);
+            // all of them are read as the same graph
+            let again = ws.add_structure(&written).unwrap();
+            assert_eq!(
+                ws.structure_atoms(again).unwrap().len(),
+                ws.structure_atoms(id).unwrap().len()
+            );
+        }
+    }
+}